@@ -0,0 +1,249 @@
+// steps through a bundle of the hitreg debug capture, see pewpew::hitreg::capture
+// usage: capture_viewer captures/<bundle>
+// right/space: next frame, left: previous frame, escape: quit
+
+use pewpew::hitreg::capture;
+use pewpew::hitreg::capture::{FrameKind, SequenceCapture};
+use sdl2::event::Event;
+use sdl2::image::{InitFlag, LoadTexture};
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use std::env;
+use std::path::{Path, PathBuf};
+
+const WINDOW_WIDTH: u32 = 1280;
+const WINDOW_HEIGHT: u32 = 720;
+const LINE_HEIGHT: i32 = 22;
+
+pub fn main() -> Result<(), String> {
+    let bundle = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .ok_or("usage: capture_viewer <bundle directory>")?;
+
+    let sequences = load_sequences(&bundle)?;
+    // every frame of every sequence, in the order they were shown
+    let steps = sequences
+        .iter()
+        .enumerate()
+        .flat_map(|(sequence, capture)| {
+            (0..capture.frame_ends.len()).map(move |frame| (sequence, frame))
+        })
+        .collect::<Vec<_>>();
+    if steps.is_empty() {
+        return Err(format!("{} has no frames", bundle.display()));
+    }
+
+    let sdl_context = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+    let _image_context = sdl2::image::init(InitFlag::PNG)?;
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let font = ttf_context.load_font("res/fonts/Walter_Turncoat/WalterTurncoat-Regular.ttf", 16)?;
+
+    let window = video_subsystem
+        .window("PewPew capture viewer", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .position_centered()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut canvas = window
+        .into_canvas()
+        .present_vsync()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let texture_creator = canvas.texture_creator();
+    let mut event_pump = sdl_context.event_pump()?;
+
+    let mut step = 0;
+    let mut redraw = true;
+    'running: loop {
+        for event in event_pump.wait_timeout_iter(100) {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Right | Keycode::Space),
+                    ..
+                } => step = (step + 1).min(steps.len() - 1),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Left),
+                    ..
+                } => step = step.saturating_sub(1),
+                _ => {}
+            }
+            redraw = true;
+        }
+
+        if redraw {
+            let (sequence, frame) = steps[step];
+            draw_step(
+                &mut canvas,
+                &texture_creator,
+                &font,
+                &bundle,
+                sequence as u32,
+                &sequences[sequence],
+                frame,
+            )?;
+            redraw = false;
+        }
+    }
+
+    Ok(())
+}
+
+fn load_sequences(bundle: &Path) -> Result<Vec<SequenceCapture>, String> {
+    let mut sequences = Vec::new();
+    loop {
+        let path = capture::sequence_file(bundle, sequences.len() as u32);
+        if !path.exists() {
+            return Ok(sequences);
+        }
+        sequences.push(SequenceCapture::load(&path)?);
+    }
+}
+
+fn draw_step(
+    canvas: &mut WindowCanvas,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+    bundle: &Path,
+    sequence: u32,
+    capture: &SequenceCapture,
+    frame: usize,
+) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(40, 40, 40));
+    canvas.clear();
+
+    // the frame as it was on screen, scaled into the right part of the window
+    let frame_area = Rect::new(
+        WINDOW_WIDTH as i32 / 3,
+        0,
+        WINDOW_WIDTH * 2 / 3,
+        WINDOW_HEIGHT,
+    );
+    match texture_creator.load_texture(capture::frame_file(bundle, sequence, frame)) {
+        Ok(texture) => {
+            let query = texture.query();
+            let scale = (frame_area.width() as f32 / query.width as f32)
+                .min(frame_area.height() as f32 / query.height as f32);
+            let size = (
+                (query.width as f32 * scale) as u32,
+                (query.height as f32 * scale) as u32,
+            );
+            let mut target = Rect::new(0, 0, size.0, size.1);
+            target.center_on(frame_area.center());
+            canvas.copy(&texture, None, target)?;
+        }
+        Err(_) => {
+            draw_line(
+                canvas,
+                texture_creator,
+                font,
+                frame_area.x() + 10,
+                10,
+                "no image of this frame",
+            )?;
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (ended_ms, kind) = capture.frame_ends[frame];
+    lines.push(format!(
+        "sequence {sequence}, frame {}/{}",
+        frame + 1,
+        capture.frame_ends.len()
+    ));
+    lines.push(format!("sensortag {:#06x}", capture.sensortag_id));
+    lines.push(format!(
+        "{} frame, ended at {ended_ms}ms",
+        match kind {
+            FrameKind::Black => "black reference",
+            FrameKind::White => "white reference",
+            FrameKind::Code => "code",
+        }
+    ));
+    if let Some((brightness, is_white)) = capture.brightness_of_frame(frame) {
+        lines.push(match is_white {
+            Some(is_white) => format!(
+                "brightness {brightness}: {}",
+                if is_white { "white" } else { "black" }
+            ),
+            None => format!("brightness {brightness}"),
+        });
+    }
+    lines.push(format!(
+        "black {}, white {}, threshold {}",
+        capture.black_reference,
+        capture
+            .white_reference
+            .map_or("-".to_string(), |white| white.to_string()),
+        capture.white_threshold
+    ));
+    lines.push(String::new());
+    lines.push("samples in this frame (ms, value):".to_string());
+    for sample in capture.samples_in_frame(frame) {
+        lines.push(format!("  {}ms  {}", sample.received_ms, sample.value));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "decoded {}",
+        capture
+            .decoded
+            .iter()
+            .map(|bit| if *bit { '1' } else { '0' })
+            .collect::<String>()
+    ));
+    lines.push(format!("result {}", capture.result));
+    lines.push(String::new());
+    lines.push("codes:".to_string());
+    for (what, code) in &capture.codes {
+        lines.push(format!("  {what}  {code}"));
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_line(
+            canvas,
+            texture_creator,
+            font,
+            10,
+            10 + i as i32 * LINE_HEIGHT,
+            line,
+        )?;
+    }
+
+    canvas.present();
+    Ok(())
+}
+
+fn draw_line(
+    canvas: &mut WindowCanvas,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+    x: i32,
+    y: i32,
+    line: &str,
+) -> Result<(), String> {
+    if line.is_empty() || y > WINDOW_HEIGHT as i32 {
+        return Ok(());
+    }
+
+    let surface = font
+        .render(line)
+        .blended(Color::WHITE)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    canvas.copy(
+        &texture,
+        None,
+        Rect::new(x, y, surface.width(), surface.height()),
+    )
+}
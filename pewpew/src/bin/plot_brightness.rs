@@ -0,0 +1,77 @@
+// plots the brightness of sensortags as svg, see pewpew::hitreg::trace_plot
+// usage: plot_brightness <serial capture | bundle directory> [output directory]
+// a serial capture is what serial_print printed, it gets one plot per sensortag
+// a bundle of the hitreg debug capture gets one plot per sequence, with the frames
+// and the decisions of hitreg
+
+use pewpew::hitreg::capture;
+use pewpew::hitreg::capture::SequenceCapture;
+use pewpew::hitreg::trace_plot::Trace;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn main() -> Result<(), String> {
+    let mut args = env::args().skip(1);
+    let input = args
+        .next()
+        .map(PathBuf::from)
+        .ok_or("usage: plot_brightness <serial capture | bundle directory> [output directory]")?;
+
+    let plots = if input.is_dir() {
+        plots_of_bundle(&input)?
+    } else {
+        plots_of_serial_capture(&input)?
+    };
+    if plots.is_empty() {
+        return Err(format!("{} has no brightness samples", input.display()));
+    }
+
+    let output = match args.next() {
+        Some(output) => PathBuf::from(output),
+        None if input.is_dir() => input.clone(),
+        None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    fs::create_dir_all(&output).map_err(|e| format!("{}: {e}", output.display()))?;
+
+    for (name, trace) in plots {
+        let path = output.join(name);
+        fs::write(&path, trace.to_svg()).map_err(|e| format!("{}: {e}", path.display()))?;
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+fn plots_of_bundle(bundle: &Path) -> Result<Vec<(String, Trace)>, String> {
+    let mut plots = Vec::new();
+    loop {
+        let sequence = plots.len() as u32;
+        let path = capture::sequence_file(bundle, sequence);
+        if !path.exists() {
+            return Ok(plots);
+        }
+
+        let capture = SequenceCapture::load(&path)?;
+        let title = format!(
+            "sequence {sequence}, sensortag {:#06x}, {}",
+            capture.sensortag_id, capture.result
+        );
+        plots.push((
+            format!("sequence-{sequence}.svg"),
+            Trace::from_capture(&capture, title),
+        ));
+    }
+}
+
+fn plots_of_serial_capture(path: &Path) -> Result<Vec<(String, Trace)>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let name = path
+        .file_stem()
+        .map_or("capture".into(), |stem| stem.to_string_lossy());
+
+    Ok(Trace::from_serial_print(&text)
+        .into_iter()
+        .map(|(sensortag_id, trace)| (format!("{name}-sensortag-{sensortag_id}.svg"), trace))
+        .collect())
+}
@@ -0,0 +1,310 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// every thread has one inbox that all messages for it go into, no matter who sent them
+// so waiting for "a message from anyone" is waiting on a single queue, which blocks on a condvar
+// instead of polling several channels in turn
+// messages are kept in the order they were sent, which makes the inbox fair between senders
+// a receiver that only wants messages of one sender takes the first message that matches
+// and leaves the others where they are, see Inbox::recv_first
+// finding that message is linear in the number of messages in front of it, which stays small:
+// the brightness samples, hundreds per second, only go to hitreg, which takes everything in order,
+// and the inbox of the gui, the only one that is searched, gets shots, reloads and answers of
+// hitreg, a handful per second that are taken every frame
+// like mpsc, an inbox is disconnected once all outboxes to it are dropped and it is empty,
+// and sending fails once the inbox is dropped
+
+struct State<M> {
+    queue: VecDeque<M>,
+
+    /// number of outboxes that can still send
+    senders: usize,
+    receiver_alive: bool,
+
+    /// only for bounded inboxes, see bounded
+    capacity: Option<usize>,
+}
+
+struct Shared<M> {
+    state: Mutex<State<M>>,
+    arrived: Condvar,
+}
+
+impl<M> Shared<M> {
+    fn lock(&self) -> MutexGuard<'_, State<M>> {
+        // a thread that panicked while holding the lock cannot have left the queue half changed,
+        // every change of it is a single call
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// the sending end of an inbox, can be cloned to have several senders
+pub struct Outbox<M> {
+    shared: Arc<Shared<M>>,
+}
+
+/// the receiving end, there is only one per inbox
+pub struct Inbox<M> {
+    shared: Arc<Shared<M>>,
+}
+
+pub fn channel<M>() -> (Outbox<M>, Inbox<M>) {
+    with_capacity(None)
+}
+
+/// an inbox that holds at most `capacity` messages, for messages that can be dropped when nobody
+/// is receiving them, see Outbox::send
+pub fn bounded<M>(capacity: usize) -> (Outbox<M>, Inbox<M>) {
+    with_capacity(Some(capacity))
+}
+
+fn with_capacity<M>(capacity: Option<usize>) -> (Outbox<M>, Inbox<M>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            senders: 1,
+            receiver_alive: true,
+            capacity,
+        }),
+        arrived: Condvar::new(),
+    });
+
+    (
+        Outbox {
+            shared: shared.clone(),
+        },
+        Inbox { shared },
+    )
+}
+
+impl<M> Outbox<M> {
+    /// never blocks, a bounded inbox that is full drops its oldest message to make room,
+    /// so whoever receives later gets the newest ones
+    pub fn send(&self, message: M) -> Result<(), SendError<M>> {
+        let mut state = self.shared.lock();
+        if !state.receiver_alive {
+            return Err(SendError(message));
+        }
+        if state
+            .capacity
+            .is_some_and(|capacity| state.queue.len() >= capacity)
+        {
+            state.queue.pop_front();
+        }
+
+        state.queue.push_back(message);
+        drop(state);
+        self.shared.arrived.notify_one();
+        Ok(())
+    }
+}
+
+impl<M> Outbox<M> {
+    /// a fresh inbox in place of one that was dropped, because the thread that received from it
+    /// ended, None while the old inbox is still there
+    /// messages sent in between were refused, so nothing old is waiting in it
+    pub fn reconnect(&self) -> Option<Inbox<M>> {
+        let mut state = self.shared.lock();
+        if state.receiver_alive {
+            return None;
+        }
+
+        state.receiver_alive = true;
+        Some(Inbox {
+            shared: self.shared.clone(),
+        })
+    }
+}
+
+impl<M> Clone for Outbox<M> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Outbox {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<M> Drop for Outbox<M> {
+    fn drop(&mut self) {
+        self.shared.lock().senders -= 1;
+        // a receiver waiting for the last sender has to find out that nothing will come anymore
+        self.shared.arrived.notify_all();
+    }
+}
+
+impl<M> Inbox<M> {
+    pub fn recv(&self) -> Result<M, RecvError> {
+        self.recv_first(|_| true)
+    }
+
+    pub fn try_recv(&self) -> Result<M, TryRecvError> {
+        self.try_recv_first(|_| true)
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<M, RecvTimeoutError> {
+        self.recv_first_timeout(timeout, |_| true)
+    }
+
+    /// blocks until a message matches, the messages before it stay in the inbox
+    pub fn recv_first(&self, matches: impl Fn(&M) -> bool) -> Result<M, RecvError> {
+        self.wait_for_first(None, matches).map_err(|_| RecvError)
+    }
+
+    pub fn try_recv_first(&self, matches: impl Fn(&M) -> bool) -> Result<M, TryRecvError> {
+        let mut state = self.shared.lock();
+        match take_first(&mut state, &matches) {
+            Some(message) => Ok(message),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn recv_first_timeout(
+        &self,
+        timeout: Duration,
+        matches: impl Fn(&M) -> bool,
+    ) -> Result<M, RecvTimeoutError> {
+        self.wait_for_first(Some(Instant::now() + timeout), matches)
+    }
+
+    fn wait_for_first(
+        &self,
+        deadline: Option<Instant>,
+        matches: impl Fn(&M) -> bool,
+    ) -> Result<M, RecvTimeoutError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(message) = take_first(&mut state, &matches) {
+                return Ok(message);
+            }
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            state = match deadline {
+                None => self
+                    .shared
+                    .arrived
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    self.shared
+                        .arrived
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+}
+
+impl<M> Drop for Inbox<M> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        state.queue.clear();
+    }
+}
+
+fn take_first<M>(state: &mut State<M>, matches: &impl Fn(&M) -> bool) -> Option<M> {
+    let index = state.queue.iter().position(matches)?;
+    state.queue.remove(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comm::bus;
+    use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn matching_messages_are_taken_first_and_the_rest_stays_in_order() {
+        let (outbox, inbox) = bus::channel();
+        for message in [1, 2, 3, 4] {
+            outbox.send(message).unwrap();
+        }
+
+        assert_eq!(inbox.try_recv_first(|m| m % 2 == 0), Ok(2));
+        assert_eq!(inbox.try_recv_first(|m| *m > 10), Err(TryRecvError::Empty));
+        assert_eq!(inbox.try_recv(), Ok(1));
+        assert_eq!(inbox.try_recv(), Ok(3));
+        assert_eq!(inbox.try_recv(), Ok(4));
+    }
+
+    #[test]
+    fn recv_waits_for_a_message_from_another_thread() {
+        let (outbox, inbox) = bus::channel();
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            outbox.send("late").unwrap();
+        });
+
+        assert_eq!(inbox.recv(), Ok("late"));
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn inbox_is_disconnected_once_all_senders_are_gone() {
+        let (outbox, inbox) = bus::channel();
+        let other = outbox.clone();
+        outbox.send(1).unwrap();
+        drop(outbox);
+
+        assert_eq!(inbox.recv_timeout(Duration::from_millis(1)), Ok(1));
+        assert_eq!(
+            inbox.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        // waiting is woken up by the last sender going away
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(other);
+        });
+        assert!(inbox.recv().is_err());
+        assert_eq!(inbox.try_recv(), Err(TryRecvError::Disconnected));
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn sending_fails_without_inbox() {
+        let (outbox, inbox) = bus::channel();
+        drop(inbox);
+
+        assert!(outbox.send(1).is_err());
+    }
+
+    #[test]
+    fn dropped_inbox_can_be_replaced() {
+        let (outbox, inbox) = bus::channel();
+
+        assert!(outbox.reconnect().is_none());
+        drop(inbox);
+        assert!(outbox.send(1).is_err());
+
+        let inbox = outbox.reconnect().unwrap();
+        outbox.send(2).unwrap();
+        assert_eq!(inbox.try_recv(), Ok(2));
+    }
+
+    #[test]
+    fn full_bounded_inbox_drops_the_oldest_messages() {
+        let (outbox, inbox) = bus::bounded(2);
+
+        for message in [1, 2, 3, 4] {
+            assert_eq!(outbox.send(message), Ok(()));
+        }
+        assert_eq!(inbox.try_recv(), Ok(3));
+        assert_eq!(inbox.try_recv(), Ok(4));
+        assert_eq!(inbox.try_recv(), Err(TryRecvError::Empty));
+    }
+}
@@ -1,103 +1,126 @@
-use crate::comm::message::{GuiToHitreg, HitregToGui, SerialToGui, ToGui};
-use std::sync::mpsc::{Receiver, RecvError, SendError, Sender, TryRecvError};
+use crate::comm::bus::{Inbox, Outbox};
+use crate::comm::message::{
+    GuiToHitreg, HitregToGui, SerialToGui, SerialToHitReg, SupervisorToGui, ToGui, ToHitreg,
+};
+use std::sync::mpsc::SendError;
 use std::time::Duration;
 
+// the supervisor keeps an outbox to every inbox of the gui, so they never disconnect and
+// receiving only ever finds nothing, see comm::supervisor
+// sending to hitreg fails while the hitreg thread is gone, until the supervisor restarted it
+
 pub struct GuiComm {
-    serial_to_gui_rx: Receiver<SerialToGui>,
-    gui_to_hitreg_tx: Sender<GuiToHitreg>,
-    hitreg_to_gui_rx: Receiver<HitregToGui>,
-
-    // there is a recv method that uses try_recv on serial_to_hitreg and gui_to_hitreg
-    // if there are a lot of messages from serial, this will 'starve' messages from gui
-    // thus we use this boolean to alternate between the two and make it fair
-    which: bool,
+    to_hitreg: Outbox<ToHitreg>,
+
+    // messages from serial and from hitreg, in the order they were sent, see comm::bus
+    inbox: Inbox<ToGui>,
+
+    // copies of the brightness samples hitreg gets, see try_recv_brightness
+    brightness_tap: Inbox<SerialToHitReg>,
 }
 
 impl GuiComm {
     pub fn new(
-        serial_to_gui_rx: Receiver<SerialToGui>,
-        gui_to_hitreg_tx: Sender<GuiToHitreg>,
-        hitreg_to_gui_rx: Receiver<HitregToGui>,
+        to_hitreg: Outbox<ToHitreg>,
+        inbox: Inbox<ToGui>,
+        brightness_tap: Inbox<SerialToHitReg>,
     ) -> Self {
         Self {
-            serial_to_gui_rx,
-            gui_to_hitreg_tx,
-            hitreg_to_gui_rx,
-            which: false,
+            to_hitreg,
+            inbox,
+            brightness_tap,
         }
     }
 
     pub fn send(&self, message: GuiToHitreg) -> Result<(), SendError<GuiToHitreg>> {
-        self.gui_to_hitreg_tx.send(message)
+        self.to_hitreg
+            .send(ToHitreg::FromGui(message))
+            .map_err(|e| match e.0 {
+                ToHitreg::FromGui(message) => SendError(message),
+                ToHitreg::FromSerial(_) => unreachable!("only messages from the gui are sent here"),
+            })
     }
 
-    pub fn recv_from_serial(&self) -> Result<SerialToGui, RecvError> {
-        self.serial_to_gui_rx.recv()
+    pub fn recv_from_serial(&self) -> SerialToGui {
+        into_from_serial(connected(self.inbox.recv_first(is_from_serial).ok()))
     }
 
-    pub fn try_recv_from_serial(&self) -> Result<SerialToGui, TryRecvError> {
-        self.serial_to_gui_rx.try_recv()
+    pub fn try_recv_from_serial(&self) -> Option<SerialToGui> {
+        self.inbox
+            .try_recv_first(is_from_serial)
+            .ok()
+            .map(into_from_serial)
     }
 
-    pub fn recv_from_hitreg(&self) -> Result<HitregToGui, RecvError> {
-        self.hitreg_to_gui_rx.recv()
+    pub fn recv_from_hitreg(&self) -> HitregToGui {
+        into_from_hitreg(connected(self.inbox.recv_first(is_from_hitreg).ok()))
     }
 
-    pub fn try_recv_from_hitreg(&self) -> Result<HitregToGui, TryRecvError> {
-        self.hitreg_to_gui_rx.try_recv()
+    /// None if nothing came within `timeout`
+    pub fn recv_from_hitreg_timeout(&self, timeout: Duration) -> Option<HitregToGui> {
+        self.inbox
+            .recv_first_timeout(timeout, is_from_hitreg)
+            .ok()
+            .map(into_from_hitreg)
     }
 
-    pub fn recv(&mut self) -> Result<ToGui, RecvError> {
-        // TODO there is an edge case here that is not handled correctly (yet) where both receivers are disconnected
-        // i cant be bothered right now
-
-        self.which = !self.which;
-        if self.which {
-            loop {
-                if let Ok(message) = self.hitreg_to_gui_rx.recv_timeout(Duration::from_millis(1)) {
-                    return Ok(ToGui::FromHitreg(message));
-                } else if let Ok(message) =
-                    self.serial_to_gui_rx.recv_timeout(Duration::from_millis(1))
-                {
-                    return Ok(ToGui::FromSerial(message));
-                }
-            }
-        } else {
-            loop {
-                if let Ok(message) = self.serial_to_gui_rx.recv_timeout(Duration::from_millis(1)) {
-                    return Ok(ToGui::FromSerial(message));
-                } else if let Ok(message) =
-                    self.hitreg_to_gui_rx.recv_timeout(Duration::from_millis(1))
-                {
-                    return Ok(ToGui::FromHitreg(message));
-                }
-            }
-        }
+    pub fn try_recv_from_hitreg(&self) -> Option<HitregToGui> {
+        self.inbox
+            .try_recv_first(is_from_hitreg)
+            .ok()
+            .map(into_from_hitreg)
     }
 
-    pub fn try_recv(&mut self) -> Result<ToGui, TryRecvError> {
-        // TODO there is an edge case here that is not handled correctly (yet) where both receivers are disconnected
-        // i cant be bothered right now
-
-        self.which = !self.which;
-
-        #[allow(clippy::collapsible_else_if)]
-        if self.which {
-            if let Ok(message) = self.hitreg_to_gui_rx.try_recv() {
-                Ok(ToGui::FromHitreg(message))
-            } else if let Ok(message) = self.serial_to_gui_rx.try_recv() {
-                Ok(ToGui::FromSerial(message))
-            } else {
-                Err(TryRecvError::Empty)
-            }
-        } else {
-            if let Ok(message) = self.serial_to_gui_rx.try_recv() {
-                Ok(ToGui::FromSerial(message))
-            } else if let Ok(message) = self.hitreg_to_gui_rx.try_recv() {
-                Ok(ToGui::FromHitreg(message))
-            } else {
-                Err(TryRecvError::Empty)
-            }
-        }
+    /// how the worker threads are doing, see supervisor
+    pub fn try_recv_from_supervisor(&self) -> Option<SupervisorToGui> {
+        self.inbox
+            .try_recv_first(|message| matches!(message, ToGui::FromSupervisor(_)))
+            .ok()
+            .map(|message| match message {
+                ToGui::FromSupervisor(message) => message,
+                _ => unreachable!("only messages from the supervisor are taken"),
+            })
+    }
+
+    /// a copy of a brightness sample that was sent to hitreg
+    /// the tap only holds a limited number of samples, older ones are dropped until this is called
+    pub fn try_recv_brightness(&self) -> Option<SerialToHitReg> {
+        self.brightness_tap.try_recv().ok()
+    }
+
+    /// blocks until serial, hitreg or the supervisor sends something
+    pub fn recv(&mut self) -> ToGui {
+        connected(self.inbox.recv().ok())
+    }
+
+    pub fn try_recv(&mut self) -> Option<ToGui> {
+        self.inbox.try_recv().ok()
+    }
+}
+
+/// what a blocking receive got, it only returns without a message once every outbox is gone
+fn connected<M>(message: Option<M>) -> M {
+    message.expect("the supervisor keeps an outbox to the inbox of the gui")
+}
+
+fn is_from_serial(message: &ToGui) -> bool {
+    matches!(message, ToGui::FromSerial(_))
+}
+
+fn into_from_serial(message: ToGui) -> SerialToGui {
+    match message {
+        ToGui::FromSerial(message) => message,
+        _ => unreachable!("only taken if is_from_serial"),
+    }
+}
+
+fn is_from_hitreg(message: &ToGui) -> bool {
+    matches!(message, ToGui::FromHitreg(_))
+}
+
+fn into_from_hitreg(message: ToGui) -> HitregToGui {
+    match message {
+        ToGui::FromHitreg(message) => message,
+        _ => unreachable!("only taken if is_from_hitreg"),
     }
 }
@@ -1,98 +1,93 @@
-use crate::comm::message::{GuiToHitreg, HitregToGui, SerialToHitReg, ToHitreg};
-use std::sync::mpsc::{Receiver, RecvError, SendError, Sender, TryRecvError};
+use crate::comm::bus::{Inbox, Outbox};
+use crate::comm::message::{GuiToHitreg, HitregToGui, SerialToHitReg, ToGui, ToHitreg};
+use std::sync::mpsc::SendError;
+use std::time::Duration;
+
+// the supervisor and the gui keep an outbox to the inbox of hitreg, so it never disconnects and
+// receiving only ever finds nothing, see comm::supervisor
+// sending to the gui fails once the gui is gone
 
 pub struct HitregComm {
-    hitreg_to_gui_tx: Sender<HitregToGui>,
-    serial_to_hitreg_rx: Receiver<SerialToHitReg>,
-    gui_to_hitreg_rx: Receiver<GuiToHitreg>,
-
-    // there is a recv method that uses try_recv on serial_to_hitreg and gui_to_hitreg
-    // if there are a lot of messages from serial, this will 'starve' messages from gui
-    // thus we use this boolean to alternate between the two and make it fair
-    which: bool,
+    to_gui: Outbox<ToGui>,
+
+    // messages from serial and from the gui, in the order they were sent, see comm::bus
+    inbox: Inbox<ToHitreg>,
 }
 
 impl HitregComm {
-    pub fn new(
-        hitreg_to_gui_tx: Sender<HitregToGui>,
-        serial_to_hitreg_rx: Receiver<SerialToHitReg>,
-        gui_to_hitreg_rx: Receiver<GuiToHitreg>,
-    ) -> Self {
-        Self {
-            hitreg_to_gui_tx,
-            serial_to_hitreg_rx,
-            gui_to_hitreg_rx,
-            which: false,
-        }
+    pub fn new(to_gui: Outbox<ToGui>, inbox: Inbox<ToHitreg>) -> Self {
+        Self { to_gui, inbox }
     }
 
     pub fn send(&self, message: HitregToGui) -> Result<(), SendError<HitregToGui>> {
-        self.hitreg_to_gui_tx.send(message)
+        self.to_gui
+            .send(ToGui::FromHitreg(message))
+            .map_err(|e| match e.0 {
+                ToGui::FromHitreg(message) => SendError(message),
+                _ => unreachable!("only messages from hitreg are sent here"),
+            })
+    }
+
+    pub fn recv_from_serial(&self) -> SerialToHitReg {
+        into_from_serial(connected(self.inbox.recv_first(is_from_serial).ok()))
+    }
+
+    pub fn try_recv_from_serial(&self) -> Option<SerialToHitReg> {
+        self.inbox
+            .try_recv_first(is_from_serial)
+            .ok()
+            .map(into_from_serial)
+    }
+
+    pub fn recv_from_gui(&self) -> GuiToHitreg {
+        into_from_gui(connected(self.inbox.recv_first(is_from_gui).ok()))
     }
 
-    pub fn recv_from_serial(&self) -> Result<SerialToHitReg, RecvError> {
-        self.serial_to_hitreg_rx.recv()
+    pub fn try_recv_from_gui(&self) -> Option<GuiToHitreg> {
+        self.inbox
+            .try_recv_first(is_from_gui)
+            .ok()
+            .map(into_from_gui)
     }
 
-    pub fn try_recv_from_serial(&self) -> Result<SerialToHitReg, TryRecvError> {
-        self.serial_to_hitreg_rx.try_recv()
+    /// blocks until serial or the gui sends something
+    pub fn recv(&mut self) -> ToHitreg {
+        connected(self.inbox.recv().ok())
     }
 
-    pub fn recv_from_gui(&self) -> Result<GuiToHitreg, RecvError> {
-        self.gui_to_hitreg_rx.recv()
+    pub fn try_recv(&mut self) -> Option<ToHitreg> {
+        self.inbox.try_recv().ok()
     }
 
-    pub fn try_recv_from_gui(&self) -> Result<GuiToHitreg, TryRecvError> {
-        self.gui_to_hitreg_rx.try_recv()
+    /// None if nothing came within `timeout`
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<ToHitreg> {
+        self.inbox.recv_timeout(timeout).ok()
     }
+}
+
+/// what a blocking receive got, it only returns without a message once every outbox is gone
+fn connected<M>(message: Option<M>) -> M {
+    message.expect("the supervisor keeps an outbox to the inbox of hitreg")
+}
+
+fn is_from_serial(message: &ToHitreg) -> bool {
+    matches!(message, ToHitreg::FromSerial(_))
+}
 
-    pub fn recv(&mut self) -> Result<ToHitreg, RecvError> {
-        // TODO there is an edge case here that is not handled correctly (yet) where both receivers are disconnected
-        // i cant be bothered right now
-
-        self.which = !self.which;
-        if self.which {
-            loop {
-                if let Ok(message) = self.serial_to_hitreg_rx.try_recv() {
-                    return Ok(ToHitreg::FromSerial(message));
-                } else if let Ok(message) = self.gui_to_hitreg_rx.try_recv() {
-                    return Ok(ToHitreg::FromGui(message));
-                }
-            }
-        } else {
-            loop {
-                if let Ok(message) = self.gui_to_hitreg_rx.try_recv() {
-                    return Ok(ToHitreg::FromGui(message));
-                } else if let Ok(message) = self.serial_to_hitreg_rx.try_recv() {
-                    return Ok(ToHitreg::FromSerial(message));
-                }
-            }
-        }
+fn into_from_serial(message: ToHitreg) -> SerialToHitReg {
+    match message {
+        ToHitreg::FromSerial(message) => message,
+        ToHitreg::FromGui(_) => unreachable!("only taken if is_from_serial"),
     }
+}
+
+fn is_from_gui(message: &ToHitreg) -> bool {
+    matches!(message, ToHitreg::FromGui(_))
+}
 
-    pub fn try_recv(&mut self) -> Result<ToHitreg, TryRecvError> {
-        // TODO there is an edge case here that is not handled correctly (yet) where both receivers are disconnected
-        // i cant be bothered right now
-
-        self.which = !self.which;
-
-        #[allow(clippy::collapsible_else_if)]
-        if self.which {
-            if let Ok(message) = self.serial_to_hitreg_rx.try_recv() {
-                Ok(ToHitreg::FromSerial(message))
-            } else if let Ok(message) = self.gui_to_hitreg_rx.try_recv() {
-                Ok(ToHitreg::FromGui(message))
-            } else {
-                Err(TryRecvError::Empty)
-            }
-        } else {
-            if let Ok(message) = self.gui_to_hitreg_rx.try_recv() {
-                Ok(ToHitreg::FromGui(message))
-            } else if let Ok(message) = self.serial_to_hitreg_rx.try_recv() {
-                Ok(ToHitreg::FromSerial(message))
-            } else {
-                Err(TryRecvError::Empty)
-            }
-        }
+fn into_from_gui(message: ToHitreg) -> GuiToHitreg {
+    match message {
+        ToHitreg::FromGui(message) => message,
+        ToHitreg::FromSerial(_) => unreachable!("only taken if is_from_gui"),
     }
 }
@@ -1,5 +1,24 @@
+use crate::hitreg::structured_light::StripeLayout;
 use hecs::Entity;
-use std::time::SystemTime;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// when a message from serial went through the stages of its way, see common::latency
+#[derive(Debug, Clone, Copy)]
+pub struct Stamps {
+    /// when the serial thread read the packet the message was made from
+    pub received_at: Instant,
+
+    /// when it was put into the inbox of the receiver, set by SerialComm
+    pub sent_at: Instant,
+}
+
+impl Stamps {
+    pub fn now() -> Self {
+        let now = Instant::now();
+        Stamps { received_at: now, sent_at: now }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum SerialToGuiKind {
@@ -14,6 +33,7 @@ pub struct SerialToGui {
     pub ammo: u8,
     pub ammo_max: u8,
     pub kind: SerialToGuiKind,
+    pub stamps: Stamps,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +41,7 @@ pub struct SerialToHitReg {
     pub sensortag_id: u16,
     pub timestamp: u32,
     pub value_raw: u16,
+    pub stamps: Stamps,
 }
 
 #[derive(Debug, Clone)]
@@ -32,13 +53,68 @@ pub enum FromSerial {
 #[derive(Debug, Clone)]
 pub enum GuiToHitreg {
     FlashingSequenceStart{sensortag_id: u16, num_frames: u32, sequences: Vec<(Entity, Vec<bool>)>},
+
+    /// like FlashingSequenceStart, but the frames show stripes across the whole viewport
+    /// instead of hitboxes, see hitreg::structured_light
+    AimSequenceStart{sensortag_id: u16, layout: StripeLayout},
+
+    /// first stage of the coarse to fine flashing sequence, region i is flashed with the code i+1
+    RegionSequenceStart{sensortag_id: u16, num_regions: u32},
+
+    /// like FlashingSequenceStart, but every frame shows one of `levels` grey levels per hitbox,
+    /// see hitreg::grey_levels
+    /// the black frame is followed by a white reference frame, which ends with FlashWhiteFrameEnd
+    GreyLevelSequenceStart{sensortag_id: u16, levels: u32, num_frames: u32, sequences: Vec<(Entity, Vec<u8>)>},
     FlashBlackFrameEnd(SystemTime),
+    FlashWhiteFrameEnd(SystemTime),
+
+    /// sent right after the whole screen changed from black to white or back,
+    /// see hitreg::latency_probe
+    LatencyProbe{sensortag_ids: Vec<u16>},
     FlashFrameEnd(SystemTime),
+
+    /// the following sequences belong to one shot and are saved into this bundle,
+    /// see hitreg::capture
+    CaptureInto(PathBuf),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HitregToGui {
     Result(Option<Entity>),
+
+    /// answer to an AimSequenceStart, the point on the screen the sensortag pointed at
+    AimPoint(Option<(i32, i32)>),
+
+    /// answer to a RegionSequenceStart, the index of the region the sensortag pointed at
+    Region(Option<u32>),
+
+    /// answer to a LatencyProbe, how long every sensortag took to report the change
+    /// None if it did not react at all
+    ProbeLatencies(Vec<(u16, Option<Duration>)>),
+
+    /// frames or brightness packets of the flashing sequence got lost, nothing can be said about a hit
+    TimedOut,
+}
+
+/// the threads the supervisor watches, see supervisor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Worker {
+    Serial,
+    Hitreg,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkerHealth {
+    Running,
+
+    /// the thread ended because it panicked or returned, it is started again after restart_in
+    Stopped{reason: String, restart_in: Duration},
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisorToGui {
+    pub worker: Worker,
+    pub health: WorkerHealth,
 }
 
 #[derive(Debug, Clone)]
@@ -51,4 +127,5 @@ pub enum ToHitreg {
 pub enum ToGui {
     FromHitreg(HitregToGui),
     FromSerial(SerialToGui),
+    FromSupervisor(SupervisorToGui),
 }
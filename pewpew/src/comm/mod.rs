@@ -1,23 +1,27 @@
 use crate::comm::gui::GuiComm;
-use crate::comm::hitreg::HitregComm;
-use crate::comm::message::{GuiToHitreg, HitregToGui, SerialToGui, SerialToHitReg};
-use crate::comm::serial::SerialComm;
-use std::sync::mpsc::channel;
+use crate::comm::message::{SerialToHitReg, ToGui, ToHitreg};
+use crate::comm::supervisor::SupervisorComm;
 
+pub mod bus;
 pub mod gui;
 pub mod hitreg;
 pub mod message;
 pub mod serial;
+pub mod supervisor;
 
-pub fn new() -> (SerialComm, HitregComm, GuiComm) {
-    let (serial_to_hitreg_tx, serial_to_hitreg_rx) = channel::<SerialToHitReg>();
-    let (gui_to_hitreg_tx, gui_to_hitreg_rx) = channel::<GuiToHitreg>();
-    let (hitreg_to_gui_tx, hitreg_to_gui_rx) = channel::<HitregToGui>();
-    let (serial_to_gui_tx, serial_to_gui_rx) = channel::<SerialToGui>();
+/// brightness samples the gui can look at without taking them away from hitreg,
+/// if the gui does not look, the oldest samples are dropped once this many are waiting
+const BRIGHTNESS_TAP_CAPACITY: usize = 4096;
+
+/// the comms of the serial and hitreg threads are handed out by the supervisor
+pub fn new() -> (SupervisorComm, GuiComm) {
+    let (to_hitreg, hitreg_inbox) = bus::channel::<ToHitreg>();
+    let (to_gui, gui_inbox) = bus::channel::<ToGui>();
+    let (brightness_tap, brightness_tap_inbox) =
+        bus::bounded::<SerialToHitReg>(BRIGHTNESS_TAP_CAPACITY);
 
     (
-        SerialComm::new(serial_to_hitreg_tx, serial_to_gui_tx),
-        HitregComm::new(hitreg_to_gui_tx, serial_to_hitreg_rx, gui_to_hitreg_rx),
-        GuiComm::new(serial_to_gui_rx, gui_to_hitreg_tx, hitreg_to_gui_rx),
+        SupervisorComm::new(to_hitreg.clone(), to_gui, brightness_tap, hitreg_inbox),
+        GuiComm::new(to_hitreg, gui_inbox, brightness_tap_inbox),
     )
 }
@@ -1,29 +1,52 @@
-use crate::comm::message::{FromSerial, SerialToGui, SerialToHitReg};
+use crate::comm::bus::Outbox;
+use crate::comm::message::{FromSerial, SerialToGui, SerialToHitReg, ToGui, ToHitreg};
 use std::sync::mpsc::SendError;
-use std::sync::mpsc::Sender;
+use std::time::Instant;
 
 pub struct SerialComm {
-    serial_to_hitreg_tx: Sender<SerialToHitReg>,
-    serial_to_gui_tx: Sender<SerialToGui>,
+    to_hitreg: Outbox<ToHitreg>,
+    to_gui: Outbox<ToGui>,
+
+    // a copy of everything sent to hitreg, for the oscilloscope of the gui
+    brightness_tap: Outbox<SerialToHitReg>,
 }
 
 impl SerialComm {
     pub fn new(
-        serial_to_hitreg_tx: Sender<SerialToHitReg>,
-        serial_to_gui_tx: Sender<SerialToGui>,
+        to_hitreg: Outbox<ToHitreg>,
+        to_gui: Outbox<ToGui>,
+        brightness_tap: Outbox<SerialToHitReg>,
     ) -> Self {
         Self {
-            serial_to_hitreg_tx,
-            serial_to_gui_tx,
+            to_hitreg,
+            to_gui,
+            brightness_tap,
         }
     }
 
-    pub fn send_to_gui(&self, message: SerialToGui) -> Result<(), SendError<SerialToGui>> {
-        self.serial_to_gui_tx.send(message)
+    pub fn send_to_gui(&self, mut message: SerialToGui) -> Result<(), SendError<SerialToGui>> {
+        message.stamps.sent_at = Instant::now();
+        self.to_gui
+            .send(ToGui::FromSerial(message))
+            .map_err(|e| match e.0 {
+                ToGui::FromSerial(message) => SendError(message),
+                _ => unreachable!("only messages from serial are sent here"),
+            })
     }
 
-    pub fn send_to_hitreg(&self, message: SerialToHitReg) -> Result<(), SendError<SerialToHitReg>> {
-        self.serial_to_hitreg_tx.send(message)
+    pub fn send_to_hitreg(
+        &self,
+        mut message: SerialToHitReg,
+    ) -> Result<(), SendError<SerialToHitReg>> {
+        message.stamps.sent_at = Instant::now();
+        // nobody may be looking at the tap anymore, hitreg must get its samples anyway
+        let _ = self.brightness_tap.send(message.clone());
+        self.to_hitreg
+            .send(ToHitreg::FromSerial(message))
+            .map_err(|e| match e.0 {
+                ToHitreg::FromSerial(message) => SendError(message),
+                ToHitreg::FromGui(_) => unreachable!("only messages from serial are sent here"),
+            })
     }
 
     pub fn send(&self, message: FromSerial) -> Result<(), SendError<FromSerial>> {
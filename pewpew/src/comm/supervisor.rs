@@ -0,0 +1,61 @@
+use crate::comm::bus::{Inbox, Outbox};
+use crate::comm::hitreg::HitregComm;
+use crate::comm::message::{SerialToHitReg, SupervisorToGui, ToGui, ToHitreg};
+use crate::comm::serial::SerialComm;
+use std::sync::mpsc::SendError;
+
+// the supervisor keeps the ends of the bus that the worker threads use,
+// so a worker that is started again gets fresh endpoints that the gui is still connected to
+// it keeps them until the gui is done, so the inboxes of the gui and of hitreg never disconnect,
+// only sending to a thread that is gone fails
+
+pub struct SupervisorComm {
+    to_hitreg: Outbox<ToHitreg>,
+    to_gui: Outbox<ToGui>,
+    brightness_tap: Outbox<SerialToHitReg>,
+
+    // the inbox of the first hitreg thread, later ones reconnect to_hitreg
+    hitreg_inbox: Option<Inbox<ToHitreg>>,
+}
+
+impl SupervisorComm {
+    pub fn new(
+        to_hitreg: Outbox<ToHitreg>,
+        to_gui: Outbox<ToGui>,
+        brightness_tap: Outbox<SerialToHitReg>,
+        hitreg_inbox: Inbox<ToHitreg>,
+    ) -> Self {
+        Self {
+            to_hitreg,
+            to_gui,
+            brightness_tap,
+            hitreg_inbox: Some(hitreg_inbox),
+        }
+    }
+
+    pub fn send(&self, message: SupervisorToGui) -> Result<(), SendError<SupervisorToGui>> {
+        self.to_gui
+            .send(ToGui::FromSupervisor(message))
+            .map_err(|e| match e.0 {
+                ToGui::FromSupervisor(message) => SendError(message),
+                _ => unreachable!("only messages from the supervisor are sent here"),
+            })
+    }
+
+    pub fn serial_comm(&self) -> SerialComm {
+        SerialComm::new(
+            self.to_hitreg.clone(),
+            self.to_gui.clone(),
+            self.brightness_tap.clone(),
+        )
+    }
+
+    /// None while the comm of the previous hitreg thread still exists
+    pub fn hitreg_comm(&mut self) -> Option<HitregComm> {
+        let inbox = self
+            .hitreg_inbox
+            .take()
+            .or_else(|| self.to_hitreg.reconnect())?;
+        Some(HitregComm::new(self.to_gui.clone(), inbox))
+    }
+}
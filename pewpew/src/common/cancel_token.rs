@@ -4,6 +4,9 @@ use std::sync::atomic::{AtomicBool, Ordering};
 #[derive(Clone)]
 pub struct CancelToken {
     canceled: Arc<AtomicBool>,
+
+    /// set for tokens from child, canceling the parent cancels them too
+    parent: Option<Arc<AtomicBool>>,
 }
 
 impl CancelToken {
@@ -13,12 +16,28 @@ impl CancelToken {
 
     pub fn was_canceled(&self) -> bool {
         self.canceled.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.load(Ordering::Relaxed))
+    }
+
+    /// a token that is canceled together with this one, but canceling or dropping it leaves this
+    /// one alone, for threads that are restarted when they end, see supervisor
+    pub fn child(&self) -> CancelToken {
+        CancelToken {
+            canceled: Arc::new(AtomicBool::new(false)),
+            parent: Some(self.canceled.clone()),
+        }
     }
 }
 
 impl Default for CancelToken {
     fn default() -> Self {
-        CancelToken { canceled: Arc::new(AtomicBool::new(false)) }
+        CancelToken {
+            canceled: Arc::new(AtomicBool::new(false)),
+            parent: None,
+        }
     }
 }
 
@@ -27,3 +46,30 @@ impl Drop for CancelToken {
         self.cancel();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::common::cancel_token::CancelToken;
+
+    #[test]
+    fn dropping_a_clone_cancels_all_of_them() {
+        let token = CancelToken::default();
+        drop(token.clone());
+
+        assert!(token.was_canceled());
+    }
+
+    #[test]
+    fn dropping_a_child_leaves_the_parent_alone() {
+        let token = CancelToken::default();
+        let child = token.child();
+        drop(child.clone());
+
+        assert!(child.was_canceled());
+        assert!(!token.was_canceled());
+
+        let child = token.child();
+        token.cancel();
+        assert!(child.was_canceled());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::comm::message::Stamps;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// where the time between pulling the trigger and seeing the result goes
+// a shot passes serial (packet received, forwarded to the gui), waits in the inbox of the gui,
+// waits for other shots to be flashed, is flashed and decoded by hitreg, and is shown with the
+// next frame after the result arrived, every one of those stages is a span
+// messages from serial carry their stamps (see comm::message::Stamps), the gui follows a shot
+// with a ShotTrace and records all of its spans once its result is on the screen
+// the histograms are shared by all threads, hitreg records how long brightness packets wait
+// for it, the debug overlay shows them and main dumps them on exit
+
+/// the file main writes the histograms to on exit
+pub const DUMP_FILE: &str = "latency.txt";
+
+/// upper bounds of the buckets, a last bucket takes everything slower
+const BUCKET_BOUNDS: [Duration; 10] = [
+    Duration::from_millis(1),
+    Duration::from_millis(2),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(20),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(500),
+    Duration::from_millis(1000),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Span {
+    /// serial read the packet of the shot until it was in the inbox of the gui
+    Forwarding,
+
+    /// the shot was in the inbox of the gui until the game took it
+    GuiQueue,
+
+    /// the game took the shot until its flashing sequence started, other shots were flashed
+    WaitingForFlash,
+
+    /// the flashing sequence started until its result arrived
+    Flashing,
+
+    /// the result arrived until the frame showing it was presented
+    ShowingResult,
+
+    /// the packet of the shot was received until its result was shown
+    Shot,
+
+    /// a brightness packet was in the inbox of hitreg until the decoder got it
+    HitregQueue,
+}
+
+impl Span {
+    fn name(&self) -> &'static str {
+        match self {
+            Span::Forwarding => "forwarding",
+            Span::GuiQueue => "gui queue",
+            Span::WaitingForFlash => "waiting for flash",
+            Span::Flashing => "flashing",
+            Span::ShowingResult => "showing result",
+            Span::Shot => "whole shot",
+            Span::HitregQueue => "hitreg queue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// one per bucket in BUCKET_BOUNDS and one for everything slower
+    counts: [u32; BUCKET_BOUNDS.len() + 1],
+    total: Duration,
+    max: Duration,
+}
+
+impl Histogram {
+    pub fn record(&mut self, duration: Duration) {
+        let bucket = BUCKET_BOUNDS
+            .iter()
+            .position(|bound| duration <= *bound)
+            .unwrap_or(BUCKET_BOUNDS.len());
+        self.counts[bucket] += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total / self.count().max(1)
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// upper bound of the bucket the `percent`th percentile falls into, at most the maximum
+    pub fn percentile(&self, percent: u32) -> Duration {
+        let wanted = (self.count() * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= wanted {
+                return BUCKET_BOUNDS
+                    .get(bucket)
+                    .map_or(self.max, |bound| (*bound).min(self.max));
+            }
+        }
+        self.max
+    }
+}
+
+static HISTOGRAMS: Mutex<BTreeMap<Span, Histogram>> = Mutex::new(BTreeMap::new());
+
+fn histograms() -> MutexGuard<'static, BTreeMap<Span, Histogram>> {
+    // every change of a histogram is a single call, a panic cannot leave one half changed
+    HISTOGRAMS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn record(span: Span, duration: Duration) {
+    histograms().entry(span).or_default().record(duration);
+}
+
+/// one line per span that has samples, for the debug overlay and the dump
+pub fn report() -> Vec<String> {
+    histograms()
+        .iter()
+        .map(|(span, histogram)| {
+            format!(
+                "{}: n={} mean={}ms p50<={}ms p90<={}ms max={}ms",
+                span.name(),
+                histogram.count(),
+                histogram.mean().as_millis(),
+                histogram.percentile(50).as_millis(),
+                histogram.percentile(90).as_millis(),
+                histogram.max().as_millis(),
+            )
+        })
+        .collect()
+}
+
+/// writes the report and the bucket counts of every span, does nothing if nothing was recorded
+pub fn dump(path: &Path) -> std::io::Result<()> {
+    let histograms = histograms().clone();
+    if histograms.is_empty() {
+        return Ok(());
+    }
+
+    let mut text = String::new();
+    for (span, histogram) in &histograms {
+        writeln!(text, "[{}]", span.name()).unwrap();
+        for (bucket, count) in histogram.counts.iter().enumerate() {
+            match BUCKET_BOUNDS.get(bucket) {
+                Some(bound) => writeln!(text, "<={}ms {count}", bound.as_millis()).unwrap(),
+                None => writeln!(text, "slower {count}").unwrap(),
+            }
+        }
+        writeln!(text).unwrap();
+    }
+    for line in report() {
+        writeln!(text, "{line}").unwrap();
+    }
+
+    fs::write(path, text)
+}
+
+/// the way of a single shot through the gui, see the top of this file
+#[derive(Debug, Clone)]
+pub struct ShotTrace {
+    stamps: Stamps,
+    taken_at: Instant,
+    flash_started_at: Option<Instant>,
+    result_at: Option<Instant>,
+}
+
+impl ShotTrace {
+    /// the game took the message of the shot out of its inbox
+    pub fn taken(stamps: Stamps, at: Instant) -> Self {
+        ShotTrace {
+            stamps,
+            taken_at: at,
+            flash_started_at: None,
+            result_at: None,
+        }
+    }
+
+    pub fn flash_started(&mut self, at: Instant) {
+        self.flash_started_at = Some(at);
+    }
+
+    pub fn result_arrived(&mut self, at: Instant) {
+        self.result_at = Some(at);
+    }
+
+    /// the spans of the shot, if it was shown at `shown_at`, stages it never reached are left out
+    pub fn spans(&self, shown_at: Instant) -> Vec<(Span, Duration)> {
+        let mut spans = vec![
+            (
+                Span::Forwarding,
+                self.stamps.sent_at - self.stamps.received_at,
+            ),
+            (Span::GuiQueue, self.taken_at - self.stamps.sent_at),
+        ];
+        if let Some(flash_started_at) = self.flash_started_at {
+            spans.push((Span::WaitingForFlash, flash_started_at - self.taken_at));
+            if let Some(result_at) = self.result_at {
+                spans.push((Span::Flashing, result_at - flash_started_at));
+            }
+        }
+        if let Some(result_at) = self.result_at {
+            spans.push((Span::ShowingResult, shown_at - result_at));
+        }
+        spans.push((Span::Shot, shown_at - self.stamps.received_at));
+        spans
+    }
+
+    /// records the spans of the shot and returns how long it took as a whole
+    pub fn shown(self, shown_at: Instant) -> Duration {
+        let spans = self.spans(shown_at);
+        let mut histograms = histograms();
+        for (span, duration) in &spans {
+            histograms.entry(*span).or_default().record(*duration);
+        }
+        shown_at - self.stamps.received_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comm::message::Stamps;
+    use crate::common::latency::{Histogram, ShotTrace, Span};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn percentiles_are_bucket_bounds() {
+        let mut histogram = Histogram::default();
+        for ms in [1, 3, 4, 8, 9, 15, 40, 45, 70, 130] {
+            histogram.record(Duration::from_millis(ms));
+        }
+
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.mean(), Duration::from_micros(32500));
+        assert_eq!(histogram.percentile(50), Duration::from_millis(10));
+        assert_eq!(histogram.percentile(90), Duration::from_millis(100));
+        // the last bucket is not wider than what was seen
+        assert_eq!(histogram.percentile(100), Duration::from_millis(130));
+        assert_eq!(Histogram::default().percentile(50), Duration::ZERO);
+    }
+
+    #[test]
+    fn shot_is_split_into_spans() {
+        let ms = Duration::from_millis;
+        let received_at = Instant::now();
+        let stamps = Stamps {
+            received_at,
+            sent_at: received_at + ms(1),
+        };
+
+        let mut trace = ShotTrace::taken(stamps, received_at + ms(5));
+        trace.flash_started(received_at + ms(25));
+        trace.result_arrived(received_at + ms(325));
+
+        assert_eq!(
+            trace.spans(received_at + ms(360)),
+            vec![
+                (Span::Forwarding, ms(1)),
+                (Span::GuiQueue, ms(4)),
+                (Span::WaitingForFlash, ms(20)),
+                (Span::Flashing, ms(300)),
+                (Span::ShowingResult, ms(35)),
+                (Span::Shot, ms(360)),
+            ]
+        );
+    }
+}
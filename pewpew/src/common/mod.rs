@@ -1 +1,2 @@
-pub mod cancel_token;
\ No newline at end of file
+pub mod cancel_token;
+pub mod latency;
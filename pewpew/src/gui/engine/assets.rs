@@ -0,0 +1,363 @@
+use crate::gui::engine::components::hitbox_shape::{AlphaMask, HitboxShape};
+use crate::gui::engine::sprite_sheet::{HitboxKind, Sprite, SpriteSheet};
+use sdl2::image::LoadTexture;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::WindowContext;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// every file the gui loads is listed in MANIFEST_FILE under a name, code only knows the names
+// a line of the manifest is "<kind> <name> <path>", the path relative to the manifest,
+// empty lines and lines starting with # are skipped
+// textures are loaded the first time they are asked for and kept for the whole run, so
+// switching scenes does not load them again, fonts are loaded per size whenever asked for
+// sounds are listed here too, but loaded and played by audio::Audio
+// textures are referred to by handles, which stay valid as long as the Assets that gave them out
+// how a texture is cut into frames and animated is in its sprite sheet, see sprite_sheet
+
+pub const MANIFEST_FILE: &str = "res/assets.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Texture,
+    Font,
+    Sound,
+}
+
+impl AssetKind {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "texture" => Some(AssetKind::Texture),
+            "font" => Some(AssetKind::Font),
+            "sound" => Some(AssetKind::Sound),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// the manifest could not be read
+    ManifestUnreadable { path: PathBuf, reason: String },
+
+    /// a line of the manifest makes no sense, `line` counts from 1
+    InvalidManifest { line: usize, reason: String },
+
+    /// the code asked for a name the manifest does not list
+    UnknownName { kind: AssetKind, name: String },
+
+    /// the manifest lists the file, but it could not be loaded
+    LoadFailed { path: PathBuf, reason: String },
+
+    /// a line of a sprite sheet makes no sense, `line` counts from 1
+    InvalidSpriteSheet {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+
+    /// the code asked for an animation the sprite sheet of the texture does not list
+    UnknownAnimation { texture: String, name: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::ManifestUnreadable { path, reason } => {
+                write!(f, "cannot read asset manifest {}: {reason}", path.display())
+            }
+            AssetError::InvalidManifest { line, reason } => {
+                write!(f, "line {line} of the asset manifest: {reason}")
+            }
+            AssetError::UnknownName { kind, name } => {
+                write!(f, "the asset manifest lists no {kind:?} named \"{name}\"")
+            }
+            AssetError::LoadFailed { path, reason } => {
+                write!(f, "cannot load {}: {reason}", path.display())
+            }
+            AssetError::InvalidSpriteSheet { path, line, reason } => {
+                write!(f, "line {line} of {}: {reason}", path.display())
+            }
+            AssetError::UnknownAnimation { texture, name } => {
+                write!(
+                    f,
+                    "the sprite sheet of \"{texture}\" lists no animation \"{name}\""
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    paths: HashMap<(AssetKind, String), PathBuf>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, AssetError> {
+        let content = fs::read_to_string(path).map_err(|e| AssetError::ManifestUnreadable {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Manifest::parse(&content, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// `root` is what the paths in `content` are relative to
+    pub fn parse(content: &str, root: &Path) -> Result<Self, AssetError> {
+        let mut paths = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: String| AssetError::InvalidManifest {
+                line: i + 1,
+                reason,
+            };
+
+            // paths may contain spaces, so everything after the name is the path
+            let mut words = line.splitn(3, char::is_whitespace);
+            let (Some(kind), Some(name), Some(path)) = (words.next(), words.next(), words.next())
+            else {
+                return Err(invalid("expected \"<kind> <name> <path>\"".to_string()));
+            };
+            let kind = AssetKind::parse(kind)
+                .ok_or_else(|| invalid(format!("unknown kind \"{kind}\"")))?;
+
+            if paths
+                .insert((kind, name.to_string()), root.join(path.trim()))
+                .is_some()
+            {
+                return Err(invalid(format!("{kind:?} \"{name}\" is listed twice")));
+            }
+        }
+
+        Ok(Manifest { paths })
+    }
+
+    pub fn path(&self, kind: AssetKind, name: &str) -> Result<&Path, AssetError> {
+        self.paths
+            .get(&(kind, name.to_string()))
+            .map(PathBuf::as_path)
+            .ok_or_else(|| AssetError::UnknownName {
+                kind,
+                name: name.to_string(),
+            })
+    }
+}
+
+/// refers to a texture loaded by Assets::texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(usize);
+
+impl TextureHandle {
+    /// a handle of nothing, for textures that are never drawn
+    #[cfg(test)]
+    pub fn unloaded() -> Self {
+        TextureHandle(usize::MAX)
+    }
+
+    /// the handle of the image a test registered as `index`th, see render::software
+    #[cfg(test)]
+    pub fn nth(index: usize) -> Self {
+        TextureHandle(index)
+    }
+}
+
+pub struct Assets<'sdl> {
+    manifest: Manifest,
+    texture_creator: &'sdl TextureCreator<WindowContext>,
+    ttf_context: &'sdl Sdl2TtfContext,
+
+    /// indexed by TextureHandle
+    textures: Vec<Texture<'sdl>>,
+    texture_handles: HashMap<String, TextureHandle>,
+    sprite_sheets: HashMap<String, Rc<SpriteSheet>>,
+    alpha_masks: HashMap<String, Arc<AlphaMask>>,
+}
+
+impl<'sdl> Assets<'sdl> {
+    pub fn new(
+        manifest: Manifest,
+        texture_creator: &'sdl TextureCreator<WindowContext>,
+        ttf_context: &'sdl Sdl2TtfContext,
+    ) -> Self {
+        Assets {
+            manifest,
+            texture_creator,
+            ttf_context,
+            textures: Vec::new(),
+            texture_handles: HashMap::new(),
+            sprite_sheets: HashMap::new(),
+            alpha_masks: HashMap::new(),
+        }
+    }
+
+    pub fn texture(&mut self, name: &str) -> Result<TextureHandle, AssetError> {
+        if let Some(handle) = self.texture_handles.get(name) {
+            return Ok(*handle);
+        }
+
+        let path = self.manifest.path(AssetKind::Texture, name)?;
+        let texture = self
+            .texture_creator
+            .load_texture(path)
+            .map_err(|reason| load_failed(path, reason))?;
+
+        let handle = TextureHandle(self.textures.len());
+        self.textures.push(texture);
+        self.texture_handles.insert(name.to_string(), handle);
+        Ok(handle)
+    }
+
+    pub fn image(&self, handle: TextureHandle) -> &Texture<'sdl> {
+        &self.textures[handle.0]
+    }
+
+    /// the sprite sheet of the texture `name`, the default one if it has no sidecar
+    pub fn sprite_sheet(&mut self, name: &str) -> Result<Rc<SpriteSheet>, AssetError> {
+        if let Some(sheet) = self.sprite_sheets.get(name) {
+            return Ok(sheet.clone());
+        }
+
+        let path = SpriteSheet::sidecar_of(self.manifest.path(AssetKind::Texture, name)?);
+        let sheet = Rc::new(if path.exists() {
+            let content =
+                fs::read_to_string(&path).map_err(|e| load_failed(&path, e.to_string()))?;
+            SpriteSheet::parse(&content, &path)?
+        } else {
+            SpriteSheet::default()
+        });
+        self.sprite_sheets.insert(name.to_string(), sheet.clone());
+        Ok(sheet)
+    }
+
+    /// the texture `name` playing its animation `animation`, for texture::Builder::animated
+    pub fn sprite(&mut self, name: &str, animation: &str) -> Result<Sprite, AssetError> {
+        let sheet = self.sprite_sheet(name)?;
+        Ok(Sprite {
+            image: self.texture(name)?,
+            grid: sheet.grid,
+            pivot: sheet.pivot,
+            animation: sheet
+                .animation(animation)
+                .ok_or_else(|| AssetError::UnknownAnimation {
+                    texture: name.to_string(),
+                    name: animation.to_string(),
+                })?,
+        })
+    }
+
+    /// the hitbox shape the sprite sheet of the texture `name` asks for
+    pub fn hitbox_shape(&mut self, name: &str) -> Result<HitboxShape, AssetError> {
+        let sheet = self.sprite_sheet(name)?;
+        Ok(match sheet.hitbox {
+            HitboxKind::Rectangle => HitboxShape::Rectangle,
+            HitboxKind::Circle => HitboxShape::Circle,
+            HitboxKind::Alpha => {
+                if let Some(mask) = self.alpha_masks.get(name) {
+                    return Ok(HitboxShape::TextureAlpha(mask.clone()));
+                }
+                let path = self.manifest.path(AssetKind::Texture, name)?;
+                let mask = Arc::new(
+                    AlphaMask::load(path, sheet.grid)
+                        .map_err(|reason| load_failed(path, reason))?,
+                );
+                self.alpha_masks.insert(name.to_string(), mask.clone());
+                HitboxShape::TextureAlpha(mask)
+            }
+        })
+    }
+
+    pub fn font(&self, name: &str, point_size: u16) -> Result<Font<'sdl, 'static>, AssetError> {
+        let path = self.manifest.path(AssetKind::Font, name)?;
+        self.ttf_context
+            .load_font(path, point_size)
+            .map_err(|reason| load_failed(path, reason))
+    }
+}
+
+pub(crate) fn load_failed(path: &Path, reason: String) -> AssetError {
+    AssetError::LoadFailed {
+        path: path.to_path_buf(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::assets::{AssetError, AssetKind, MANIFEST_FILE, Manifest};
+    use crate::gui::engine::sprite_sheet::SpriteSheet;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn manifest_maps_names_to_paths_below_its_directory() {
+        let manifest = Manifest::parse(
+            "# comment\n\ntexture chicken images/flying_huhn.png\nsound shot audio/AWP Shooting.mp3\n",
+            Path::new("res"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.path(AssetKind::Texture, "chicken").unwrap(),
+            Path::new("res/images/flying_huhn.png")
+        );
+        assert_eq!(
+            manifest.path(AssetKind::Sound, "shot").unwrap(),
+            Path::new("res/audio/AWP Shooting.mp3")
+        );
+        assert!(matches!(
+            manifest.path(AssetKind::Sound, "chicken"),
+            Err(AssetError::UnknownName {
+                kind: AssetKind::Sound,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn invalid_lines_are_reported_with_their_number() {
+        for content in [
+            "texture chicken\n",
+            "picture chicken a.png\n",
+            "font default a.ttf\nfont default b.ttf\n",
+        ] {
+            let error = Manifest::parse(content, Path::new("res")).unwrap_err();
+            assert!(
+                matches!(error, AssetError::InvalidManifest { line, .. } if line == content.lines().count()),
+                "{content:?} gave {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn manifest_of_the_game_lists_existing_files() {
+        let manifest = Manifest::load(Path::new(MANIFEST_FILE)).unwrap();
+        for path in manifest.paths.values() {
+            assert!(path.exists(), "{} does not exist", path.display());
+        }
+    }
+
+    #[test]
+    fn sprite_sheets_of_the_game_are_valid() {
+        let manifest = Manifest::load(Path::new(MANIFEST_FILE)).unwrap();
+        for ((kind, _), path) in &manifest.paths {
+            let sidecar = SpriteSheet::sidecar_of(path);
+            if *kind == AssetKind::Texture && sidecar.exists() {
+                let content = fs::read_to_string(&sidecar).unwrap();
+                if let Err(e) = SpriteSheet::parse(&content, &sidecar) {
+                    panic!("{e}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::gui::engine::assets::{AssetError, AssetKind, Manifest, load_failed};
+use log::{debug, info, warn};
+use sdl2::mixer::Sdl2MixerContext;
+use sdl2::mixer::{AUDIO_S16LSB, Channel, Chunk, DEFAULT_CHANNELS, InitFlag, MAX_VOLUME};
+use sdl2::{AudioSubsystem, Sdl};
+use std::collections::{HashMap, VecDeque};
+
+// everything the gui plays goes through Audio, which all scenes share, see engine::resources
+// game logic does not play sounds itself, it spawns a PlaySound, often from an Action, and
+// systems::play_sounds plays them once per frame
+// a sound is loaded into the bank the first time it is asked for by its name in the manifest,
+// after that it is only a Sound handle
+// without an audio device the gui is silent instead of failing, the names are still looked up
+// in the manifest so that a wrong one is noticed, but nothing is loaded or played
+// the mixer has NUM_CHANNELS channels, if all of them are busy the sound that started first
+// is cut off for the new one
+// every player has a gun of their own, the manifest has numbered sets of gun sounds (shot_1,
+// reload_1, death_1, shot_2, ...) and the players take turns through them, see GunSoundAssignment
+
+/// how many sounds can play at the same time
+const NUM_CHANNELS: i32 = 16;
+
+/// the sounds of a set of gun sounds are named <prefix>_<number of the set>, starting at 1
+const GUN_SOUND_PREFIXES: [&str; 3] = ["shot", "reload", "death"];
+
+/// refers to a sound loaded by Audio::sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sound(usize);
+
+/// what the gun of a player sounds like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GunSounds {
+    pub shot: Sound,
+    pub reload: Sound,
+    pub death: Sound,
+
+    /// the same for all players
+    pub dry_shot: Sound,
+}
+
+/// which gun sounds every player gets
+#[derive(Debug, Clone)]
+pub struct GunSoundAssignment {
+    sets: Vec<GunSounds>,
+}
+
+impl GunSoundAssignment {
+    /// with more players than sets, players share a set
+    pub fn of_player(&self, player_id: usize) -> GunSounds {
+        self.sets[player_id % self.sets.len()]
+    }
+}
+
+/// the opened mixer, it is closed again when this is dropped
+struct Device {
+    _audio: AudioSubsystem,
+    _mixer: Sdl2MixerContext,
+
+    /// in the order their sounds started, the front one is cut off first
+    started: VecDeque<Channel>,
+}
+
+impl Device {
+    fn open(sdl: &Sdl) -> Result<Self, String> {
+        let audio = sdl.audio()?;
+        let mixer = sdl2::mixer::init(InitFlag::MP3)?;
+        sdl2::mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 1024)?;
+        sdl2::mixer::allocate_channels(NUM_CHANNELS);
+
+        Ok(Device {
+            _audio: audio,
+            _mixer: mixer,
+            started: VecDeque::new(),
+        })
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        Channel::all().halt();
+        sdl2::mixer::close_audio();
+    }
+}
+
+pub struct Audio {
+    manifest: Manifest,
+
+    /// None while silent
+    device: Option<Device>,
+
+    /// indexed by Sound, None for everything while silent
+    sounds: Vec<Option<Chunk>>,
+    sound_handles: HashMap<String, Sound>,
+
+    /// of all sounds, from 0.0 (muted) to 1.0
+    volume: f32,
+}
+
+impl Audio {
+    /// plays on the default audio device, or stays silent if it cannot be opened
+    pub fn open(sdl: &Sdl, manifest: Manifest, volume: f32) -> Self {
+        let mut audio = Audio::silent(manifest);
+        audio.volume = volume.clamp(0.0, 1.0);
+        match Device::open(sdl) {
+            Ok(device) => audio.device = Some(device),
+            Err(e) => warn!(target: "Gui Thread", "no audio, the game stays silent: {e}"),
+        }
+        audio
+    }
+
+    /// never plays anything
+    pub fn silent(manifest: Manifest) -> Self {
+        Audio {
+            manifest,
+            device: None,
+            sounds: Vec::new(),
+            sound_handles: HashMap::new(),
+            volume: 1.0,
+        }
+    }
+
+    pub fn sound(&mut self, name: &str) -> Result<Sound, AssetError> {
+        if let Some(sound) = self.sound_handles.get(name) {
+            return Ok(*sound);
+        }
+
+        let path = self.manifest.path(AssetKind::Sound, name)?;
+        let chunk = match self.device {
+            Some(_) => Some(Chunk::from_file(path).map_err(|reason| load_failed(path, reason))?),
+            None => None,
+        };
+
+        let sound = Sound(self.sounds.len());
+        self.sounds.push(chunk);
+        self.sound_handles.insert(name.to_string(), sound);
+        Ok(sound)
+    }
+
+    /// all numbered sets of gun sounds in the manifest, see the top of this file
+    pub fn gun_sound_assignment(&mut self) -> Result<GunSoundAssignment, AssetError> {
+        let dry_shot = self.sound("dry_shot")?;
+
+        let mut sets = Vec::new();
+        for number in 1.. {
+            let names = GUN_SOUND_PREFIXES.map(|prefix| format!("{prefix}_{number}"));
+            if number > 1
+                && names
+                    .iter()
+                    .any(|name| self.manifest.path(AssetKind::Sound, name).is_err())
+            {
+                break;
+            }
+
+            // the first set has to be complete, a missing sound of it is an error
+            let [shot, reload, death] = names;
+            sets.push(GunSounds {
+                shot: self.sound(&shot)?,
+                reload: self.sound(&reload)?,
+                death: self.sound(&death)?,
+                dry_shot,
+            });
+        }
+
+        Ok(GunSoundAssignment { sets })
+    }
+
+    /// `volume` is relative to the volume of all sounds, from 0.0 to 1.0
+    pub fn play(&mut self, sound: Sound, volume: f32) {
+        let (Some(device), Some(chunk)) = (&mut self.device, &self.sounds[sound.0]) else {
+            return;
+        };
+
+        let channel = match Channel::all().play(chunk, 0) {
+            Ok(channel) => channel,
+            Err(_) => {
+                // every channel is busy
+                let Some(oldest) = device.started.pop_front() else {
+                    return;
+                };
+                match oldest.play(chunk, 0) {
+                    Ok(channel) => channel,
+                    Err(e) => {
+                        debug!(target: "Gui Thread", "a sound was left out: {e}");
+                        return;
+                    }
+                }
+            }
+        };
+
+        channel.set_volume((MAX_VOLUME as f32 * self.volume * volume.clamp(0.0, 1.0)) as i32);
+        device.started.retain(|started| *started != channel);
+        device.started.push_back(channel);
+    }
+
+    #[allow(unused)]
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        info!(target: "Gui Thread", "volume is {:.0}%", self.volume * 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::assets::{AssetError, Manifest};
+    use crate::gui::engine::audio::Audio;
+    use std::path::Path;
+
+    fn manifest(content: &str) -> Manifest {
+        Manifest::parse(content, Path::new("res")).unwrap()
+    }
+
+    #[test]
+    fn players_take_turns_through_the_gun_sound_sets() {
+        let mut audio = Audio::silent(manifest(
+            "sound dry_shot a.mp3\n\
+             sound shot_1 b.mp3\nsound reload_1 c.mp3\nsound death_1 d.mp3\n\
+             sound shot_2 e.mp3\nsound reload_2 f.mp3\nsound death_2 g.mp3\n\
+             sound shot_3 h.mp3\nsound death_3 i.mp3\n",
+        ));
+        let assignment = audio.gun_sound_assignment().unwrap();
+
+        let first = assignment.of_player(0);
+        let second = assignment.of_player(1);
+        assert_eq!(first.shot, audio.sound("shot_1").unwrap());
+        assert_eq!(second.death, audio.sound("death_2").unwrap());
+        assert_eq!(first.dry_shot, second.dry_shot);
+        // the third set is incomplete, so the third player gets the first set again
+        assert_eq!(assignment.of_player(2), first);
+    }
+
+    #[test]
+    fn silent_audio_still_knows_the_names() {
+        let mut audio = Audio::silent(manifest("sound intro does_not_exist.mp3\n"));
+
+        let intro = audio.sound("intro").unwrap();
+        assert_eq!(audio.sound("intro").unwrap(), intro);
+        audio.play(intro, 1.0);
+        assert!(matches!(
+            audio.sound("outro"),
+            Err(AssetError::UnknownName { .. })
+        ));
+        assert!(matches!(
+            audio.gun_sound_assignment(),
+            Err(AssetError::UnknownName { .. })
+        ));
+    }
+}
@@ -1,8 +1,9 @@
-use crate::gui::engine::event::Event;
+use crate::gui::engine::event::{Event, Subscription, send_with_events};
 use hecs::{Entity, World};
-use std::sync::{Arc, Mutex};
+use std::cell::RefCell;
+use std::rc::Rc;
 
-pub type ActionCallable = dyn FnMut(Entity, &mut World) + Send + Sync;
+pub type ActionCallable = dyn FnMut(Entity, &mut World);
 
 pub struct Action {
     // must be an option
@@ -10,48 +11,107 @@ pub struct Action {
     // but itself is part of the world, so it is not possible to borrow the world as mutable
     // since the action itself is already a borrow to the world
     // thus, the action is taken out of the option, run, and put back in it
-    pub action: Option<Arc<Mutex<ActionCallable>>>,
-    pub event: Event,
+    pub action: Option<Rc<RefCell<ActionCallable>>>,
+    pub event: Box<dyn Subscription>,
 }
 
+send_with_events!(Action);
+
 impl Action {
-    pub fn when<A: 'static + Send + Sync + FnMut(Entity, &mut World)>(event: Event, a: A) -> Self {
+    pub fn when<T: 'static, A: 'static + FnMut(Entity, &mut World)>(event: Event<T>, a: A) -> Self {
+        Action::subscribed(Box::new(event), a)
+    }
+
+    fn subscribed<A: 'static + FnMut(Entity, &mut World)>(
+        event: Box<dyn Subscription>,
+        a: A,
+    ) -> Self {
         Self {
-            action: Some(Arc::new(Mutex::new(a))),
+            action: Some(Rc::new(RefCell::new(a))),
             event,
         }
     }
 
-    pub fn oneshot(mut self) -> Action {
-        Action::when_oneshot(self.event, move |entity, world| {
-            let action_callable = self.action.take().unwrap();
-
-            (action_callable.lock().unwrap())(entity, world);
-
-            self.action = Some(action_callable);
-        })
-    }
-
-    pub fn when_oneshot<A: 'static + Send + Sync + FnMut(Entity, &mut World)>(
-        event: Event,
+    /// runs once per trigger with its payload, in the order of the triggers
+    pub fn when_with<T: Clone + 'static, A: 'static + FnMut(T, Entity, &mut World)>(
+        event: Event<T>,
         mut a: A,
     ) -> Self {
-        let mut has_run = false;
+        // the action runs as often as `event` was triggered, every run takes the next
+        // payload from a clone of it, which has seen the same triggers
+        let mut payloads = event.clone();
         Action::when(event, move |entity, world| {
-            if !has_run {
-                a(entity, world);
-                has_run = true;
+            if let Some(payload) = payloads.consume_next() {
+                a(payload, entity, world);
             }
         })
     }
 
-    pub fn despawn_self_when(event: Event) -> Action {
+    pub fn oneshot(mut self) -> Action {
+        let action_callable = self.action.take().unwrap();
+        Action::subscribed(
+            self.event,
+            once(move |entity, world| (action_callable.borrow_mut())(entity, world)),
+        )
+    }
+
+    pub fn when_oneshot<T: 'static, A: 'static + FnMut(Entity, &mut World)>(
+        event: Event<T>,
+        a: A,
+    ) -> Self {
+        Action::when(event, once(a))
+    }
+
+    pub fn despawn_self_when<T: 'static>(event: Event<T>) -> Action {
         Action::when_oneshot(event, |my_entity_id, world: &mut World| {
             let _ = world.despawn(my_entity_id);
         })
     }
 
-    pub fn trigger_other_event_when(event: Event, mut other_event: Event) -> Self {
+    pub fn trigger_other_event_when<T: 'static>(event: Event<T>, mut other_event: Event) -> Self {
         Action::when(event, move |_, _| other_event.trigger())
     }
 }
+
+fn once<A: 'static + FnMut(Entity, &mut World)>(
+    mut a: A,
+) -> impl 'static + FnMut(Entity, &mut World) {
+    let mut has_run = false;
+    move |entity, world| {
+        if !has_run {
+            a(entity, world);
+            has_run = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::components::action::Action;
+    use crate::gui::engine::event::Event;
+    use crate::gui::engine::systems;
+    use hecs::World;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn actions_get_the_payloads_in_the_order_of_the_triggers() {
+        let mut world = World::new();
+        let mut score_changed = Event::new();
+        let shown = Arc::new(Mutex::new(Vec::new()));
+        let shown_clone = shown.clone();
+        world.spawn((Action::when_with(
+            score_changed.clone(),
+            move |score: u32, _, _| shown_clone.lock().unwrap().push(score),
+        ),));
+        world.spawn((Action::despawn_self_when(score_changed.clone()),));
+
+        score_changed.trigger_with(5);
+        score_changed.trigger_with(20);
+        systems::work_actions::run(&mut world);
+        score_changed.trigger_with(32);
+        systems::work_actions::run(&mut world);
+
+        assert_eq!(*shown.lock().unwrap(), [5, 20, 32]);
+        assert_eq!(world.len(), 1);
+    }
+}
@@ -1,6 +1,9 @@
 use crate::gui::engine::components::Point;
+use crate::gui::engine::components::hitbox_shape::HitboxShape;
 use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
-use crate::gui::engine::event::Event;
+use crate::gui::engine::components::texture::Texture;
+use crate::gui::engine::event::{Event, send_with_events};
+use sdl2::rect::Rect;
 
 pub struct Hitbox {
     pub position: PointWithAlignment,
@@ -12,14 +15,26 @@ pub struct Hitbox {
     pub width: u32,
     pub height: u32,
     pub z_index: i32,
+    pub shape: HitboxShape,
     pub hit_event: Option<Event>,
 }
 
+send_with_events!(Hitbox);
+
+impl Hitbox {
+    /// the part of the screen that counts as a hit, see HitboxShape::spans
+    pub fn spans(&self, texture: Option<&Texture>) -> Vec<Rect> {
+        self.shape
+            .spans(self.position.align_rect(self.width, self.height), texture)
+    }
+}
+
 pub struct Builder {
     position: PointWithAlignment,
     width: u32,
     height: u32,
     z_index: i32,
+    shape: HitboxShape,
     hit_event: Option<Event>,
 }
 
@@ -30,6 +45,7 @@ impl Builder {
             width,
             height,
             z_index: 0,
+            shape: HitboxShape::Rectangle,
             hit_event: None,
         }
     }
@@ -46,6 +62,11 @@ impl Builder {
         self
     }
 
+    pub fn with_shape(mut self, shape: HitboxShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
     pub fn build(self) -> Hitbox {
         Hitbox {
             position: self.position,
@@ -53,6 +74,7 @@ impl Builder {
             width: self.width,
             height: self.height,
             z_index: self.z_index,
+            shape: self.shape,
             hit_event: self.hit_event,
         }
     }
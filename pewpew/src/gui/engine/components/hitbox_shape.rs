@@ -0,0 +1,368 @@
+use crate::gui::engine::components::texture::Texture;
+use crate::gui::engine::sprite_sheet::Grid;
+use sdl2::image::LoadSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::surface::Surface;
+use std::path::Path;
+use std::sync::Arc;
+
+// the flashing sequence and the scoring only know hitboxes as a list of screen rects (spans),
+// so every shape is rasterized into horizontal spans, rows with the same spans are merged
+
+/// pixels with a lower alpha do not count as part of the sprite
+const ALPHA_THRESHOLD: u8 = 128;
+
+#[derive(Clone)]
+pub enum HitboxShape {
+    /// the whole hitbox rect
+    Rectangle,
+
+    /// inscribed into the hitbox rect, an ellipse if the rect is not square
+    Circle,
+
+    /// corners relative to the hitbox rect, (0, 0) is the top left and (1, 1) the bottom right
+    #[allow(unused)]
+    Polygon(Vec<(f32, f32)>),
+
+    /// the opaque pixels of the current keyframe of the texture of the same entity
+    /// uses the rect of the texture instead of the one of the hitbox
+    TextureAlpha(Arc<AlphaMask>),
+}
+
+/// the opaque pixels of every cell of a sprite sheet
+pub struct AlphaMask {
+    frame_width: u32,
+    frame_height: u32,
+
+    /// per cell, per row, the [start, end) ranges of opaque pixels
+    frames: Vec<Vec<Vec<(u32, u32)>>>,
+}
+
+impl AlphaMask {
+    /// the cells are laid out like `grid` says, see sprite_sheet
+    pub fn load(path: &Path, grid: Grid) -> Result<Self, String> {
+        let surface = Surface::from_file(path)?.convert_format(PixelFormatEnum::RGBA32)?;
+        let (width, height, pitch) = (surface.width(), surface.height(), surface.pitch());
+
+        let alpha = surface.with_lock(|pixels| {
+            (0..height)
+                .flat_map(|y| (0..width).map(move |x| (y * pitch + x * 4 + 3) as usize))
+                .map(|index| pixels[index])
+                .collect::<Vec<_>>()
+        });
+
+        Ok(Self::from_alpha(&alpha, width, height, grid))
+    }
+
+    /// one alpha value per pixel, row by row
+    pub fn from_alpha(alpha: &[u8], width: u32, height: u32, grid: Grid) -> Self {
+        let (frame_width, frame_height) = (width / grid.columns, height / grid.rows);
+
+        let frames = (0..grid.num_cells())
+            .map(|cell| {
+                let (column, row) = grid.position(cell);
+                (0..frame_height)
+                    .map(|y| {
+                        let row_start =
+                            ((row * frame_height + y) * width + column * frame_width) as usize;
+                        opaque_runs(&alpha[row_start..row_start + frame_width as usize])
+                    })
+                    .collect()
+            })
+            .collect();
+
+        AlphaMask {
+            frame_width,
+            frame_height,
+            frames,
+        }
+    }
+}
+
+fn opaque_runs(row: &[u8]) -> Vec<(u32, u32)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+
+    for (x, alpha) in row.iter().enumerate() {
+        match (start, *alpha >= ALPHA_THRESHOLD) {
+            (None, true) => start = Some(x as u32),
+            (Some(run_start), false) => {
+                runs.push((run_start, x as u32));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(run_start) = start {
+        runs.push((run_start, row.len() as u32));
+    }
+
+    runs
+}
+
+impl HitboxShape {
+    /// the shape on screen as non-overlapping rects
+    ///
+    /// hitbox_rect is where the hitbox is, the texture of the same entity (if any) decides
+    /// the keyframe and the flipping, rotation is not supported
+    pub fn spans(&self, hitbox_rect: Rect, texture: Option<&Texture>) -> Vec<Rect> {
+        let (flip_horizontally, flip_vertically) = texture
+            .map(|texture| (texture.flip_horizontally, texture.flip_vertically))
+            .unwrap_or((false, false));
+
+        match self {
+            HitboxShape::Rectangle => vec![hitbox_rect],
+            HitboxShape::Circle => {
+                rasterize(hitbox_rect, flip_horizontally, flip_vertically, |v| {
+                    let half_width = (1.0 - (2.0 * v - 1.0).powi(2)).max(0.0).sqrt() / 2.0;
+                    vec![(0.5 - half_width, 0.5 + half_width)]
+                })
+            }
+            HitboxShape::Polygon(corners) => {
+                rasterize(hitbox_rect, flip_horizontally, flip_vertically, |v| {
+                    polygon_row(corners, v)
+                })
+            }
+            HitboxShape::TextureAlpha(mask) => {
+                let Some(texture) = texture else {
+                    return vec![];
+                };
+                let rect = texture.position.align_rect(
+                    (mask.frame_width as f32 * texture.scale) as u32,
+                    (mask.frame_height as f32 * texture.scale) as u32,
+                );
+                let frame = &mask.frames[texture.current_cell() as usize % mask.frames.len()];
+
+                rasterize(rect, flip_horizontally, flip_vertically, |v| {
+                    let y = ((v * mask.frame_height as f32) as usize).min(frame.len() - 1);
+                    frame[y]
+                        .iter()
+                        .map(|(start, end)| {
+                            (
+                                *start as f32 / mask.frame_width as f32,
+                                *end as f32 / mask.frame_width as f32,
+                            )
+                        })
+                        .collect()
+                })
+            }
+        }
+    }
+}
+
+/// row_of gets the vertical position of a row between 0 and 1 and returns the horizontal
+/// ranges between 0 and 1 that are part of the shape
+fn rasterize<F: Fn(f32) -> Vec<(f32, f32)>>(
+    rect: Rect,
+    flip_horizontally: bool,
+    flip_vertically: bool,
+    row_of: F,
+) -> Vec<Rect> {
+    let (width, height) = (rect.width() as f32, rect.height() as f32);
+    let mut spans = Vec::new();
+    let mut open: Vec<Rect> = Vec::new();
+    let mut open_ranges = Vec::new();
+
+    for y in 0..rect.height() as i32 {
+        let mut v = (y as f32 + 0.5) / height;
+        if flip_vertically {
+            v = 1.0 - v;
+        }
+
+        let ranges = row_of(v)
+            .into_iter()
+            .map(|(start, end)| {
+                let (start, end) = ((start * width).round() as i32, (end * width).round() as i32);
+                if flip_horizontally {
+                    (rect.width() as i32 - end, rect.width() as i32 - start)
+                } else {
+                    (start, end)
+                }
+            })
+            .filter(|(start, end)| start < end)
+            .collect::<Vec<_>>();
+
+        if ranges == open_ranges {
+            for span in &mut open {
+                span.set_height(span.height() + 1);
+            }
+            continue;
+        }
+
+        spans.append(&mut open);
+        open = ranges
+            .iter()
+            .map(|(start, end)| Rect::new(rect.x() + start, rect.y() + y, (end - start) as u32, 1))
+            .collect();
+        open_ranges = ranges;
+    }
+
+    spans.append(&mut open);
+    spans
+}
+
+/// even-odd scanline through the polygon at height v
+fn polygon_row(corners: &[(f32, f32)], v: f32) -> Vec<(f32, f32)> {
+    let mut crossings = corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .filter(|((_, y1), (_, y2))| (*y1 <= v) != (*y2 <= v))
+        .map(|((x1, y1), (x2, y2))| x1 + (v - y1) / (y2 - y1) * (x2 - x1))
+        .collect::<Vec<_>>();
+    crossings.sort_by(f32::total_cmp);
+
+    crossings
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+/// number of pixels covered by the spans
+pub fn area(spans: &[Rect]) -> u32 {
+    spans.iter().map(|span| span.width() * span.height()).sum()
+}
+
+/// center of mass of the spans
+pub fn centroid(spans: &[Rect]) -> Option<(f32, f32)> {
+    let area = area(spans) as f32;
+    if area == 0.0 {
+        return None;
+    }
+
+    let (x, y) = spans.iter().fold((0.0, 0.0), |(x, y), span| {
+        let span_area = (span.width() * span.height()) as f32;
+        (
+            x + (span.x() as f32 + span.width() as f32 / 2.0) * span_area,
+            y + (span.y() as f32 + span.height() as f32 / 2.0) * span_area,
+        )
+    });
+    Some((x / area, y / area))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::assets::TextureHandle;
+    use crate::gui::engine::components::Point;
+    use crate::gui::engine::components::hitbox_shape::{AlphaMask, HitboxShape, area, centroid};
+    use crate::gui::engine::components::point_with_alignment::{
+        HAlign, PointWithAlignment, VAlign,
+    };
+    use crate::gui::engine::components::texture;
+    use crate::gui::engine::components::texture::AnimationEndBehavior;
+    use crate::gui::engine::sprite_sheet::{Animation, AnimationFrame, Grid, Sprite};
+    use sdl2::rect::Rect;
+    use std::sync::Arc;
+
+    fn covers(spans: &[Rect], x: i32, y: i32) -> bool {
+        spans.iter().any(|span| {
+            span.left() <= x && x < span.right() && span.top() <= y && y < span.bottom()
+        })
+    }
+
+    #[test]
+    fn circle_leaves_out_the_corners() {
+        let spans = HitboxShape::Circle.spans(Rect::new(0, 0, 100, 100), None);
+
+        assert!(covers(&spans, 50, 50));
+        assert!(covers(&spans, 2, 50));
+        assert!(!covers(&spans, 5, 5));
+        assert!(!covers(&spans, 95, 95));
+        // a circle with radius 50 has about 7854 pixels
+        assert!((7700..8000).contains(&area(&spans)));
+    }
+
+    #[test]
+    fn polygon_is_filled() {
+        // triangle pointing up
+        let triangle = HitboxShape::Polygon(vec![(0.5, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let spans = triangle.spans(Rect::new(10, 10, 100, 100), None);
+
+        assert!(covers(&spans, 60, 15));
+        assert!(!covers(&spans, 15, 15));
+        assert!(covers(&spans, 15, 105));
+        assert!((4900..5100).contains(&area(&spans)));
+    }
+
+    #[test]
+    fn rows_with_the_same_ranges_are_merged() {
+        // a square as polygon is one span, like the rectangle
+        let square = HitboxShape::Polygon(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+
+        assert_eq!(
+            square.spans(Rect::new(0, 0, 40, 20), None),
+            vec![Rect::new(0, 0, 40, 20)]
+        );
+    }
+
+    #[test]
+    fn alpha_mask_follows_keyframe_flip_and_scale_of_the_texture() {
+        // two 4x2 keyframes, the first one is opaque on the left, the second one at the top
+        #[rustfmt::skip]
+        let alpha = [
+            255, 255, 0, 0,
+            255, 255, 0, 0,
+            255, 255, 255, 255,
+            0, 0, 0, 0,
+        ];
+        let grid = Grid {
+            columns: 1,
+            rows: 2,
+        };
+        let shape = HitboxShape::TextureAlpha(Arc::new(AlphaMask::from_alpha(&alpha, 4, 4, grid)));
+        let position = PointWithAlignment {
+            point: Point { x: 100, y: 100 },
+            v_align: VAlign::Top,
+            h_align: HAlign::Left,
+        };
+        // the hitbox rect does not matter, the texture decides
+        let hitbox_rect = Rect::new(0, 0, 1, 1);
+
+        let sprite = Sprite {
+            image: TextureHandle::unloaded(),
+            grid,
+            pivot: (0.5, 0.5),
+            animation: Animation {
+                frames: (0..2)
+                    .map(|cell| AnimationFrame {
+                        cell,
+                        duration: None,
+                    })
+                    .collect(),
+                end_behavior: AnimationEndBehavior::Loop,
+            },
+        };
+        let mut texture = texture::Builder::animated(&sprite, position)
+            .with_scale(10.0)
+            .build();
+        assert_eq!(
+            shape.spans(hitbox_rect, Some(&texture)),
+            vec![Rect::new(100, 100, 20, 20)]
+        );
+
+        texture.flip_horizontally = true;
+        assert_eq!(
+            shape.spans(hitbox_rect, Some(&texture)),
+            vec![Rect::new(120, 100, 20, 20)]
+        );
+
+        texture.current_keyframe = 1;
+        texture.flip_vertically = true;
+        assert_eq!(
+            shape.spans(hitbox_rect, Some(&texture)),
+            vec![Rect::new(100, 110, 40, 10)]
+        );
+
+        // no texture, nothing to hit
+        assert_eq!(shape.spans(hitbox_rect, None), vec![]);
+    }
+
+    #[test]
+    fn centroid_is_weighted_by_area() {
+        let spans = [Rect::new(0, 0, 10, 10), Rect::new(10, 0, 30, 10)];
+
+        assert_eq!(area(&spans), 400);
+        assert_eq!(centroid(&spans), Some((20.0, 5.0)));
+        assert_eq!(centroid(&[]), None);
+    }
+}
@@ -1,7 +1,10 @@
 use std::ops::Add;
 
 pub mod hitbox;
+pub mod hitbox_shape;
 pub mod movement;
+pub mod occluder;
+pub mod play_sound;
 pub mod point_with_alignment;
 pub mod texture;
 pub mod timer;
@@ -0,0 +1,46 @@
+use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
+
+/// hides the hitboxes behind it from the flashing sequence,
+/// e.g. scenery that is drawn in front of the chickens
+#[derive(Clone)]
+pub struct Occluder {
+    pub position: PointWithAlignment,
+    pub width: u32,
+    pub height: u32,
+
+    /// same meaning as the z_index of textures and hitboxes,
+    /// hides hitboxes with a lower or the same z_index
+    pub z_index: i32,
+}
+
+pub struct Builder {
+    position: PointWithAlignment,
+    width: u32,
+    height: u32,
+    z_index: i32,
+}
+
+impl Builder {
+    pub fn new(position: PointWithAlignment, width: u32, height: u32) -> Self {
+        Builder {
+            position,
+            width,
+            height,
+            z_index: 0,
+        }
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn build(self) -> Occluder {
+        Occluder {
+            position: self.position,
+            width: self.width,
+            height: self.height,
+            z_index: self.z_index,
+        }
+    }
+}
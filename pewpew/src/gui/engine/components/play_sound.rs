@@ -0,0 +1,21 @@
+use crate::gui::engine::audio::Sound;
+
+/// an entity with a PlaySound is played once by systems::play_sounds and despawned
+pub struct PlaySound {
+    pub sound: Sound,
+
+    /// relative to the volume of all sounds, from 0.0 to 1.0
+    pub volume: f32,
+}
+
+impl PlaySound {
+    pub fn new(sound: Sound) -> Self {
+        PlaySound { sound, volume: 1.0 }
+    }
+
+    #[allow(unused)]
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+}
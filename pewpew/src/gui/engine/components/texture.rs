@@ -1,9 +1,11 @@
+use crate::gui::engine::assets::TextureHandle;
 use std::time::Duration;
 use crate::gui::engine::components::Point;
 use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
-use crate::gui::engine::event::Event;
+use crate::gui::engine::event::{Event, send_with_events};
+use crate::gui::engine::sprite_sheet::{Animation, AnimationFrame, Grid, Sprite};
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum AnimationEndBehavior {
     Freeze,
     Loop,
@@ -19,14 +21,26 @@ pub struct Texture {
     pub original_point: Point,
     pub animation_end_behavior: AnimationEndBehavior,
     pub z_index: i32,
-    pub image_id: usize,
-    pub num_frames: u32,
+    pub image: TextureHandle,
+
+    /// how the image is cut into cells, see sprite_sheet
+    pub grid: Grid,
+
+    /// what the texture rotates around, relative to its size
+    pub pivot: (f32, f32),
+
+    /// the keyframes of the animation, every one shows a cell of the grid
+    pub keyframes: Vec<AnimationFrame>,
+
+    /// index into keyframes
     pub current_keyframe: u32,
+
+    /// 2.0 plays the animation twice as fast as the sprite sheet says
+    pub playback_speed: f32,
     pub scale: f32,
     pub flip_horizontally: bool,
     pub flip_vertically: bool,
     pub rotation_deg: f64,
-    pub keyframe_duration: Duration,
     pub next_keyframe_switch_at_elapsed_game_time: Option<u128>,
 
     /// triggered every time, animated texture reaches its end
@@ -39,43 +53,85 @@ pub struct Texture {
     pub outside_viewport_event: Option<Event>,
 }
 
+send_with_events!(Texture);
+
+impl Texture {
+    pub fn num_keyframes(&self) -> u32 {
+        self.keyframes.len() as u32
+    }
+
+    /// the cell of the grid the current keyframe shows
+    pub fn current_cell(&self) -> u32 {
+        self.keyframes[self.current_keyframe as usize].cell
+    }
+
+    /// how long the current keyframe is shown, None if until something else changes it
+    pub fn keyframe_duration(&self) -> Option<Duration> {
+        self.keyframes[self.current_keyframe as usize]
+            .duration
+            .map(|duration| duration.div_f32(self.playback_speed))
+    }
+}
+
 pub struct Builder {
     position: PointWithAlignment,
     animation_end_behavior: AnimationEndBehavior,
     z_index: i32,
-    image_id: usize,
-    num_frames: u32,
+    image: TextureHandle,
+    grid: Grid,
+    pivot: (f32, f32),
+    keyframes: Vec<AnimationFrame>,
     current_frame: u32,
+    playback_speed: f32,
     scale: f32,
     flip_horizontally: bool,
     flip_vertically: bool,
     rotation_deg: f64,
-    frame_advance_interval: Option<Duration>,
     animation_end_event: Option<Event>,
     at_viewport_edge_event: Option<Event>,
     outside_viewport_event: Option<Event>,
 }
 
 impl Builder {
-    pub fn new(image_id: usize, position: PointWithAlignment) -> Self {
+    /// the whole image as a single still frame
+    pub fn new(image: TextureHandle, position: PointWithAlignment) -> Self {
+        Self::animated(
+            &Sprite {
+                image,
+                grid: Grid {
+                    columns: 1,
+                    rows: 1,
+                },
+                pivot: (0.5, 0.5),
+                animation: Animation::still(0),
+            },
+            position,
+        )
+    }
+
+    /// the sprite plays its animation as its sprite sheet says, see Assets::sprite
+    pub fn animated(sprite: &Sprite, position: PointWithAlignment) -> Self {
         Builder {
             position,
-            image_id,
-            animation_end_behavior: AnimationEndBehavior::Loop,
+            image: sprite.image,
+            animation_end_behavior: sprite.animation.end_behavior.clone(),
             z_index: 0,
-            num_frames: 1,
+            grid: sprite.grid,
+            pivot: sprite.pivot,
+            keyframes: sprite.animation.frames.clone(),
             current_frame: 0,
+            playback_speed: 1.0,
             flip_vertically: false,
             flip_horizontally: false,
             scale: 1.0,
             rotation_deg: 0.0,
-            frame_advance_interval: None,
             animation_end_event: None,
             at_viewport_edge_event: None,
             outside_viewport_event: None,
         }
     }
 
+    #[allow(unused)]
     pub fn with_animation_end_behavior(mut self, animation_end_behavior: AnimationEndBehavior) -> Self {
         self.animation_end_behavior = animation_end_behavior;
         self
@@ -98,8 +154,9 @@ impl Builder {
         self
     }
 
-    pub fn with_num_frames(mut self, num_frames: u32) -> Self {
-        self.num_frames = num_frames;
+    /// 2.0 plays the animation twice as fast as the sprite sheet says
+    pub fn with_playback_speed(mut self, playback_speed: f32) -> Self {
+        self.playback_speed = playback_speed;
         self
     }
 
@@ -130,26 +187,21 @@ impl Builder {
         self
     }
 
-    pub fn with_frame_advance_interval(mut self, frame_advance_interval: Duration) -> Self {
-        self.frame_advance_interval = Some(frame_advance_interval);
-        self
-    }
-
     pub fn build(self) -> Texture {
         Texture {
             position: self.position,
             animation_end_behavior: self.animation_end_behavior,
-            image_id: self.image_id,
+            image: self.image,
             z_index: self.z_index,
-            num_frames: self.num_frames,
+            grid: self.grid,
+            pivot: self.pivot,
+            keyframes: self.keyframes,
             current_keyframe: self.current_frame,
+            playback_speed: self.playback_speed,
             scale: self.scale,
             flip_horizontally: self.flip_vertically,
             flip_vertically: self.flip_horizontally,
             rotation_deg: self.rotation_deg,
-            keyframe_duration: self
-                .frame_advance_interval
-                .unwrap_or(Duration::from_secs(u64::MAX)),
             next_keyframe_switch_at_elapsed_game_time: None,
             animation_end_event: self.animation_end_event,
             at_viewport_edge_event: self.at_viewport_edge_event,
@@ -1,5 +1,5 @@
 use std::time::Duration;
-use crate::gui::engine::event::Event;
+use crate::gui::engine::event::{Event, send_with_events};
 
 pub struct Timer {
     pub duration: Duration,
@@ -8,6 +8,8 @@ pub struct Timer {
     pub next_activation_at_elapsed_game_time: Option<u128>,
 }
 
+send_with_events!(Timer);
+
 pub struct Builder {
     pub duration: Duration,
     pub looping: bool,
@@ -1,89 +1,200 @@
 use crate::gui::engine::components::timer;
 use crate::gui::engine::components::timer::Timer;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use log::warn;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
-// events can be cloned infinitely often
-// if these clones are all quickly dropped again
-// the runtime and memory consumption of the event should stay the same
-// thus the shared data contains a map of active ids mapped to indices
-// in the vector
-// this allows for cleanup when an event is dropped
-
-#[derive(Default)]
-struct SharedEventData {
-    trigger_counts: Vec<u32>,
-    id_idx_map: HashMap<usize, usize>,
-    id_counter: usize,
+// events can be cloned infinitely often, every clone is a subscriber that sees every trigger
+// after it was made exactly once
+// an Event<T> carries a payload with every trigger, e.g. the player that shot, Event without a
+// type is Event<()>, which only counts the triggers
+// all subscribers share one log of the payloads, every subscriber only remembers how far it has
+// read, so a payload is stored once no matter how many subscribers there are, and the log only
+// keeps what some subscriber has not read yet
+// subscribers live in slots, the slot of a dropped clone is reused by the next one, so clones
+// that are quickly dropped again keep the runtime and memory consumption of the event the same
+// events only exist on the gui thread, so their logs are not behind a lock, they are kept in
+// LOGS, one registry per thread, and an Event itself is only the id of its log and its slot
+// an Event is neither Send nor Sync, so code that takes one to another thread does not compile
+// hecs wants components to be Send + Sync though, so the components that hold events say they
+// are, see send_with_events, that is sound because the log is not in the Event: one that gets to
+// another thread in a World anyway panics when it is used and only leaks its slot when dropped
+// a subscriber that never reads, e.g. the clone a scene triggers the event with, would keep
+// every payload forever, so the log only keeps the newest MAX_UNREAD payloads, a subscriber
+// that is further behind skips the older ones without noticing, so the first time that happens
+// to a log it is logged as a warning
+
+/// triggers a subscriber can fall behind before it misses the oldest ones
+const MAX_UNREAD: usize = 1024;
+
+thread_local! {
+    /// the logs of all events made on this thread, by id
+    static LOGS: RefCell<HashMap<u64, Rc<dyn Any>>> = RefCell::new(HashMap::new());
 }
 
-impl SharedEventData {
-    pub fn trigger(&mut self) {
-        for x in &mut self.trigger_counts {
-            *x += 1;
+/// ids are never reused, not even across threads, so an Event that was moved to another
+/// thread does not find a log there instead of finding the wrong one
+static NEXT_LOG_ID: AtomicU64 = AtomicU64::new(0);
+
+struct Log<T> {
+    /// oldest first
+    payloads: VecDeque<T>,
+
+    /// the number of the trigger payloads[0] came with, counting from 0
+    first: u64,
+
+    /// by slot, the number of the next trigger the subscriber has not read, None if it is free
+    cursors: Vec<Option<u64>>,
+    free_slots: Vec<usize>,
+
+    /// whether a payload was forgotten before every subscriber had read it
+    dropped_unread: bool,
+}
+
+impl<T> Log<T> {
+    fn new() -> Self {
+        Log {
+            payloads: VecDeque::new(),
+            first: 0,
+            cursors: Vec::new(),
+            free_slots: Vec::new(),
+            dropped_unread: false,
         }
     }
 
-    pub fn consume_all(&mut self, idx: usize) -> u32 {
-        let trigger_count = self.trigger_counts[self.id_idx_map[&idx]];
-        self.trigger_counts[self.id_idx_map[&idx]] = 0;
-
-        trigger_count
+    /// the number the next trigger will have
+    fn end(&self) -> u64 {
+        self.first + self.payloads.len() as u64
     }
 
-    pub fn add_subscriber(&mut self) -> usize {
-        let new_id = self.id_counter;
-        self.id_counter += 1;
+    fn trigger(&mut self, payload: T) {
+        // forget what every subscriber has read already
+        let oldest_unread = self.cursors.iter().flatten().min().copied();
+        while self.first < oldest_unread.unwrap_or(self.end()) {
+            self.payloads.pop_front();
+            self.first += 1;
+        }
 
-        let new_idx = self.trigger_counts.len();
-        self.trigger_counts.push(0);
+        self.payloads.push_back(payload);
+        if self.payloads.len() > MAX_UNREAD {
+            self.payloads.pop_front();
+            self.first += 1;
 
-        if self.id_idx_map.insert(new_id, new_idx).is_some() {
-            unreachable!();
+            if !self.dropped_unread {
+                warn!(target: "Gui Thread", "an Event<{}> has a subscriber that is more than {MAX_UNREAD} triggers behind, it misses the oldest ones", std::any::type_name::<T>());
+                self.dropped_unread = true;
+            }
         }
+    }
 
-        new_id
+    /// the number of the next trigger `slot` has not read, skipping the forgotten ones
+    fn cursor(&self, slot: usize) -> u64 {
+        self.cursors[slot].unwrap().max(self.first)
     }
 
-    pub fn remove_subscriber(&mut self, id: usize) {
-        let rm_idx = self.id_idx_map.remove(&id).unwrap();
+    fn consume_all(&mut self, slot: usize) -> u32 {
+        let unread = self.end() - self.cursor(slot);
+        self.cursors[slot] = Some(self.end());
+        unread as u32
+    }
 
-        self.trigger_counts.remove(rm_idx);
+    fn consume_next(&mut self, slot: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        let cursor = self.cursor(slot);
+        let payload = self.payloads.get((cursor - self.first) as usize)?.clone();
+        self.cursors[slot] = Some(cursor + 1);
+        Some(payload)
+    }
 
-        for (_, idx) in self.id_idx_map.iter_mut() {
-            if *idx > rm_idx {
-                *idx -= 1;
+    fn add_subscriber(&mut self) -> usize {
+        let cursor = Some(self.end());
+        match self.free_slots.pop() {
+            Some(slot) => {
+                self.cursors[slot] = cursor;
+                slot
+            }
+            None => {
+                self.cursors.push(cursor);
+                self.cursors.len() - 1
             }
         }
     }
+
+    fn remove_subscriber(&mut self, slot: usize) {
+        self.cursors[slot] = None;
+        self.free_slots.push(slot);
+    }
+
+    fn num_subscribers(&self) -> usize {
+        self.cursors.len() - self.free_slots.len()
+    }
 }
 
-pub struct Event {
-    shared_data: Arc<Mutex<SharedEventData>>,
-    my_id: usize,
+pub struct Event<T: 'static = ()> {
+    log_id: u64,
+    slot: usize,
+
+    /// the payloads stay in LOGS, so they do not make the Event any less Send or Sync
+    _payload: PhantomData<fn() -> T>,
+
+    /// the log is only in LOGS of the thread the Event was made on
+    _not_send: PhantomData<*const ()>,
 }
 
-impl Default for Event {
-    fn default() -> Self {
-        let mut shared_data = SharedEventData::default();
-        let my_id = shared_data.add_subscriber();
-        Self {
-            shared_data: Arc::new(Mutex::new(shared_data)),
-            my_id,
+impl<T: 'static> Event<T> {
+    /// an event without subscribers other than the returned one
+    pub fn new() -> Self {
+        let mut log = Log::<T>::new();
+        let slot = log.add_subscriber();
+
+        let log_id = NEXT_LOG_ID.fetch_add(1, Ordering::Relaxed);
+        LOGS.with_borrow_mut(|logs| logs.insert(log_id, Rc::new(RefCell::new(log))));
+
+        Event {
+            log_id,
+            slot,
+            _payload: PhantomData,
+            _not_send: PhantomData,
         }
     }
-}
 
-impl Drop for Event {
-    fn drop(&mut self) {
-        let mut locked = self.shared_data.lock().unwrap();
-        locked.remove_subscriber(self.my_id);
+    /// how often the event was triggered since the last call, skipping the payloads
+    pub fn consume_all(&mut self) -> u32 {
+        self.log().borrow_mut().consume_all(self.slot)
+    }
+
+    /// the payload of the oldest trigger this subscriber has not read yet
+    pub fn consume_next(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.log().borrow_mut().consume_next(self.slot)
+    }
+
+    pub fn trigger_with(&mut self, payload: T) {
+        self.log().borrow_mut().trigger(payload);
+    }
+
+    /// the registry is not borrowed anymore when this returns, so whatever is done with the
+    /// log may make or drop other events, e.g. by dropping payloads that contain some
+    fn log(&self) -> Rc<RefCell<Log<T>>> {
+        let log = LOGS.with_borrow(|logs| logs.get(&self.log_id).cloned());
+        let Some(log) = log else {
+            panic!("an Event can only be used on the thread it was made on");
+        };
+        // the id belongs to this event, so the log has its payload type
+        log.downcast().unwrap()
     }
 }
 
 impl Event {
-    #[allow(unused)]
     pub fn trigger_after(self, duration: Duration) -> Timer {
         timer::Builder::new(duration, self).build()
     }
@@ -92,24 +203,168 @@ impl Event {
         timer::Builder::new(duration, self).looping().build()
     }
 
-    pub fn consume_all(&mut self) -> u32 {
-        // we are single threaded, so unwrap is ok
-        let mut locked = self.shared_data.lock().unwrap();
-        locked.consume_all(self.my_id)
+    pub fn trigger(&mut self) {
+        self.trigger_with(());
     }
+}
 
-    pub fn trigger(&mut self) {
-        // we are single threaded, so unwrap is ok
-        self.shared_data.lock().unwrap().trigger();
+// only for Event<()>, so that Event::default() needs no type
+impl Default for Event {
+    fn default() -> Self {
+        Event::new()
+    }
+}
+
+impl<T: 'static> Drop for Event<T> {
+    fn drop(&mut self) {
+        // not log(), a panic while dropping would abort, e.g. when the thread is shutting down
+        let log = LOGS
+            .try_with(|logs| logs.borrow().get(&self.log_id).cloned())
+            .ok()
+            .flatten();
+        let Some(log) = log else {
+            return;
+        };
+
+        let mut locked = log.downcast_ref::<RefCell<Log<T>>>().unwrap().borrow_mut();
+        locked.remove_subscriber(self.slot);
+
+        // nobody can trigger or read the log anymore, it is dropped together with `log`
+        if locked.num_subscribers() == 0 {
+            LOGS.with_borrow_mut(|logs| logs.remove(&self.log_id));
+        }
     }
 }
 
-impl Clone for Event {
+impl<T: 'static> Clone for Event<T> {
     fn clone(&self) -> Self {
-        let my_id = { self.shared_data.lock().unwrap().add_subscriber() };
+        let slot = self.log().borrow_mut().add_subscriber();
         Event {
-            shared_data: self.shared_data.clone(),
-            my_id,
+            log_id: self.log_id,
+            slot,
+            _payload: PhantomData,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+/// implements Send and Sync for a component that holds events, so that it can be in a World
+/// worlds stay on the gui thread, and if one does not, its events panic instead of racing,
+/// see the top of this file
+macro_rules! send_with_events {
+    ($component:ty) => {
+        // SAFETY: an Event only holds ids, every thread only ever reaches the logs in its
+        // own LOGS, so no log is shared between threads, whatever thread the Event is on
+        unsafe impl Send for $component {}
+        // SAFETY: as for Send, also for what &Event does to the log, e.g. clone
+        unsafe impl Sync for $component {}
+    };
+}
+pub(crate) use send_with_events;
+
+/// an event of any payload, for what only needs to know whether it was triggered,
+/// see components::action
+pub trait Subscription {
+    fn consume_all(&mut self) -> u32;
+}
+
+impl<T: 'static> Subscription for Event<T> {
+    fn consume_all(&mut self) -> u32 {
+        Event::consume_all(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::event::{Event, LOGS, MAX_UNREAD};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn every_subscriber_reads_every_payload_once() {
+        let mut shooter = Event::<usize>::new();
+        let mut magazine = shooter.clone();
+        let mut score = shooter.clone();
+
+        shooter.trigger_with(1);
+        shooter.trigger_with(0);
+        assert_eq!(magazine.consume_next(), Some(1));
+        shooter.trigger_with(1);
+
+        assert_eq!(magazine.consume_next(), Some(0));
+        assert_eq!(magazine.consume_next(), Some(1));
+        assert_eq!(magazine.consume_next(), None);
+        assert_eq!(score.consume_all(), 3);
+        assert_eq!(score.consume_next(), None);
+    }
+
+    #[test]
+    fn clones_only_see_later_triggers() {
+        let mut event = Event::default();
+        event.trigger();
+        let mut clone = event.clone();
+        event.trigger();
+
+        assert_eq!(event.consume_all(), 2);
+        assert_eq!(clone.consume_all(), 1);
+    }
+
+    #[test]
+    fn dropped_clones_leave_nothing_behind() {
+        let mut event = Event::<u32>::new();
+        for score in 0..10 {
+            let mut clone = event.clone();
+            event.trigger_with(score);
+            assert_eq!(clone.consume_next(), Some(score));
+        }
+        event.consume_all();
+        event.trigger_with(10);
+
+        let log = event.log();
+        assert_eq!(log.borrow().cursors.len(), 2);
+        assert_eq!(log.borrow().payloads.len(), 1);
+    }
+
+    #[test]
+    fn log_is_gone_with_the_last_subscriber() {
+        let num_logs = || LOGS.with_borrow(|logs| logs.len());
+
+        let mut event = Event::<u32>::new();
+        let clone = event.clone();
+        event.trigger_with(1);
+        assert_eq!(num_logs(), 1);
+
+        drop(event);
+        assert_eq!(num_logs(), 1);
+        drop(clone);
+        assert_eq!(num_logs(), 0);
+    }
+
+    #[test]
+    fn events_in_components_only_work_on_the_thread_they_were_made_on() {
+        // an Event alone cannot go to another thread, but a component with one can
+        let event = Event::default();
+        let moved = event.clone().trigger_after(Duration::ZERO);
+
+        let used_elsewhere = thread::spawn(move || {
+            let mut timer = moved;
+            timer.event.trigger();
+        })
+        .join();
+        assert!(used_elsewhere.is_err());
+        assert_eq!(LOGS.with_borrow(|logs| logs.len()), 1);
+    }
+
+    #[test]
+    fn subscribers_that_never_read_miss_the_oldest_triggers() {
+        let mut event = Event::<usize>::new();
+        let mut reader = event.clone();
+        for i in 0..MAX_UNREAD + 5 {
+            event.trigger_with(i);
         }
+
+        assert_eq!(reader.consume_next(), Some(5));
+        assert_eq!(event.consume_all(), MAX_UNREAD as u32);
+        assert_eq!(event.log().borrow().payloads.len(), MAX_UNREAD);
     }
 }
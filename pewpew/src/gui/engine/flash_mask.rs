@@ -0,0 +1,263 @@
+use crate::gui::engine::components::Point;
+use crate::gui::engine::render::DrawTarget;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+// a flashing frame is an id mask of what is actually visible on screen
+// everything is drawn back to front, so the front-most hitbox or occluder wins every pixel
+// a hitbox whose code bit is 0 is drawn black instead of being left out,
+// otherwise the hitbox behind it would shine through
+// hitboxes are given as spans (see HitboxShape::spans), so they do not have to be rectangles
+
+/// one hitbox or occluder as it appears in the flash frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Layer {
+    spans: Vec<Rect>,
+
+    /// index of the hitbox, None for an occluder
+    hitbox: Option<usize>,
+}
+
+pub struct FlashMask {
+    /// back to front
+    layers: Vec<Layer>,
+}
+
+impl FlashMask {
+    /// hitboxes as (z_index, spans) and occluders as (z_index, rect)
+    ///
+    /// hitboxes with the same z_index are drawn in the given order, the last one is in front
+    /// occluders are in front of hitboxes with the same z_index
+    pub fn new(hitboxes: &[(i32, Vec<Rect>)], occluders: &[(i32, Rect)]) -> Self {
+        let mut layers = hitboxes
+            .iter()
+            .enumerate()
+            .map(|(index, (z_index, spans))| (*z_index, Some(index), spans.clone()))
+            .chain(
+                occluders
+                    .iter()
+                    .map(|(z_index, rect)| (*z_index, None, vec![*rect])),
+            )
+            .collect::<Vec<_>>();
+
+        // sort is stable, so the order within a z_index stays the same
+        layers.sort_by_key(|(z_index, _, _)| *z_index);
+
+        FlashMask {
+            layers: layers
+                .into_iter()
+                .map(|(_, hitbox, spans)| Layer { spans, hitbox })
+                .collect(),
+        }
+    }
+
+    /// for every hitbox, whether some part of it is not covered by a single hitbox or
+    /// occluder in front of it, a hitbox without any spans is never visible
+    ///
+    /// hitboxes that are covered by several things together still count as visible,
+    /// they only cost a code that never shows up
+    pub fn visible_hitboxes(&self) -> Vec<bool> {
+        let num_hitboxes = self
+            .layers
+            .iter()
+            .filter(|layer| layer.hitbox.is_some())
+            .count();
+        let mut visible = vec![false; num_hitboxes];
+
+        for (position, layer) in self.layers.iter().enumerate() {
+            if let Some(index) = layer.hitbox {
+                visible[index] = !layer.spans.is_empty()
+                    && !self.layers[position + 1..]
+                        .iter()
+                        .any(|in_front| covers(&in_front.spans, &layer.spans));
+            }
+        }
+
+        visible
+    }
+
+    /// the hitbox that is visible at the point, if any
+    pub fn hitbox_at(&self, point: Point) -> Option<usize> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|layer| layer.spans.iter().any(|span| contains_point(*span, point)))
+            .and_then(|layer| layer.hitbox)
+    }
+
+    /// draws every hitbox in the color it has in this frame, and every occluder in the
+    /// background color
+    pub fn draw<F: Fn(usize) -> Color>(
+        &self,
+        target: &mut impl DrawTarget,
+        background: Color,
+        color_of: F,
+    ) {
+        for layer in &self.layers {
+            target.set_draw_color(layer.hitbox.map_or(background, &color_of));
+            target.fill_rects(&layer.spans);
+        }
+    }
+
+    /// like draw, but every hitbox is cut into pieces along the areas, and every piece gets
+    /// the color of its area, e.g. for the regions of a coarse to fine sequence
+    pub fn draw_in_areas<F: Fn(usize) -> Color>(
+        &self,
+        target: &mut impl DrawTarget,
+        background: Color,
+        areas: &[Rect],
+        color_of_area: F,
+    ) {
+        for layer in &self.layers {
+            if layer.hitbox.is_none() {
+                target.set_draw_color(background);
+                target.fill_rects(&layer.spans);
+                continue;
+            }
+
+            for (index, area) in areas.iter().enumerate() {
+                let pieces = layer
+                    .spans
+                    .iter()
+                    .filter_map(|span| intersection(*span, *area))
+                    .collect::<Vec<_>>();
+                target.set_draw_color(color_of_area(index));
+                target.fill_rects(&pieces);
+            }
+        }
+    }
+}
+
+/// what two rects have in common, None if that is nothing
+fn intersection(a: Rect, b: Rect) -> Option<Rect> {
+    let left = a.left().max(b.left());
+    let top = a.top().max(b.top());
+    let right = a.right().min(b.right());
+    let bottom = a.bottom().min(b.bottom());
+
+    (left < right && top < bottom)
+        .then(|| Rect::new(left, top, (right - left) as u32, (bottom - top) as u32))
+}
+
+/// whether every span of inner is inside one span of outer
+fn covers(outer: &[Rect], inner: &[Rect]) -> bool {
+    inner
+        .iter()
+        .all(|inner| outer.iter().any(|outer| contains(*outer, *inner)))
+}
+
+fn contains(outer: Rect, inner: Rect) -> bool {
+    outer.left() <= inner.left()
+        && inner.right() <= outer.right()
+        && outer.top() <= inner.top()
+        && inner.bottom() <= outer.bottom()
+}
+
+fn contains_point(rect: Rect, point: Point) -> bool {
+    rect.left() <= point.x
+        && point.x < rect.right()
+        && rect.top() <= point.y
+        && point.y < rect.bottom()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::components::Point;
+    use crate::gui::engine::flash_mask::{FlashMask, intersection};
+    use sdl2::rect::Rect;
+
+    #[test]
+    fn front_most_hitbox_wins() {
+        // the second hitbox overlaps the right half of the first one
+        let mask = FlashMask::new(
+            &[
+                (0, vec![Rect::new(0, 0, 100, 100)]),
+                (1, vec![Rect::new(50, 0, 100, 100)]),
+            ],
+            &[],
+        );
+
+        assert_eq!(mask.hitbox_at(Point { x: 25, y: 50 }), Some(0));
+        assert_eq!(mask.hitbox_at(Point { x: 75, y: 50 }), Some(1));
+        assert_eq!(mask.hitbox_at(Point { x: 200, y: 50 }), None);
+        assert_eq!(mask.visible_hitboxes(), vec![true, true]);
+    }
+
+    #[test]
+    fn draw_order_of_equal_z_index_is_kept() {
+        let mask = FlashMask::new(
+            &[
+                (0, vec![Rect::new(0, 0, 100, 100)]),
+                (0, vec![Rect::new(0, 0, 100, 100)]),
+            ],
+            &[],
+        );
+
+        assert_eq!(mask.hitbox_at(Point { x: 50, y: 50 }), Some(1));
+        assert_eq!(mask.visible_hitboxes(), vec![false, true]);
+    }
+
+    #[test]
+    fn occluder_hides_hitboxes_behind_it() {
+        let tree = (5, Rect::new(100, 0, 50, 500));
+        let mask = FlashMask::new(
+            &[
+                // completely behind the tree
+                (0, vec![Rect::new(110, 100, 20, 20)]),
+                // half behind the tree
+                (0, vec![Rect::new(130, 200, 40, 40)]),
+                // in front of the tree
+                (6, vec![Rect::new(110, 300, 20, 20)]),
+            ],
+            &[tree],
+        );
+
+        assert_eq!(mask.visible_hitboxes(), vec![false, true, true]);
+        assert_eq!(mask.hitbox_at(Point { x: 120, y: 110 }), None);
+        assert_eq!(mask.hitbox_at(Point { x: 140, y: 210 }), None);
+        assert_eq!(mask.hitbox_at(Point { x: 160, y: 210 }), Some(1));
+        assert_eq!(mask.hitbox_at(Point { x: 120, y: 310 }), Some(2));
+    }
+
+    #[test]
+    fn occluder_is_in_front_of_hitboxes_with_the_same_z_index() {
+        let mask = FlashMask::new(
+            &[(0, vec![Rect::new(0, 0, 10, 10)])],
+            &[(0, Rect::new(0, 0, 10, 10))],
+        );
+
+        assert_eq!(mask.visible_hitboxes(), vec![false]);
+    }
+
+    #[test]
+    fn shaped_hitbox_lets_the_one_behind_through_its_gaps() {
+        // an L shape in front of a square, the gap of the L is at the top right
+        let l_shape = vec![Rect::new(0, 0, 50, 50), Rect::new(0, 50, 100, 50)];
+        let mask = FlashMask::new(&[(0, vec![Rect::new(60, 10, 30, 30)]), (1, l_shape)], &[]);
+
+        assert_eq!(mask.visible_hitboxes(), vec![true, true]);
+        assert_eq!(mask.hitbox_at(Point { x: 70, y: 20 }), Some(0));
+        assert_eq!(mask.hitbox_at(Point { x: 20, y: 20 }), Some(1));
+        assert_eq!(mask.hitbox_at(Point { x: 70, y: 70 }), Some(1));
+    }
+
+    #[test]
+    fn intersection_of_rects() {
+        assert_eq!(
+            intersection(Rect::new(0, 0, 100, 100), Rect::new(50, 80, 100, 100)),
+            Some(Rect::new(50, 80, 50, 20))
+        );
+        // touching is not overlapping
+        assert_eq!(
+            intersection(Rect::new(0, 0, 100, 100), Rect::new(100, 0, 10, 10)),
+            None
+        );
+    }
+
+    #[test]
+    fn hitbox_without_spans_is_not_visible() {
+        let mask = FlashMask::new(&[(0, vec![])], &[]);
+
+        assert_eq!(mask.visible_hitboxes(), vec![false]);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::gui::engine::flash_timing::FlashTiming;
+use crate::gui::engine::render::DrawTarget;
+use crate::hitreg::grey_levels;
+use sdl2::pixels::Color;
+use sdl2::render::{BlendMode, Texture, WindowCanvas};
+use std::time::Duration;
+
+// flashing the whole screen black and white several times per second is a risk for
+// photosensitive players
+// the safe style only flashes the hitboxes, at reduced contrast, on a dimmed copy of the scene,
+// and never more than 3 times per second
+// that includes the screen regions of a coarse to fine sequence and the stripes of an aim point
+// sequence, they are cut down to the hitboxes in them
+// the smaller brightness step is handled by hitreg with a white reference frame in every sequence
+
+/// pixel values of a dark and a bright frame in the safe style
+const SAFE_OFF: u8 = 40;
+const SAFE_ON: u8 = 128;
+
+/// how much of the scene behind the frames is covered, 255 hides it completely
+const BACKDROP_DIMMING: u8 = 200;
+
+/// a flash is a change from dark to bright and back, so 3 flashes per second allow one
+/// frame every 333ms
+const SAFE_MIN_TIME_PER_FRAME: Duration = Duration::from_millis(334);
+
+/// how the frames of a flashing sequence look
+#[derive(Clone, Copy)]
+pub struct FlashStyle<'a> {
+    /// pixel value of dark and of bright parts of a frame
+    pub off: u8,
+    pub on: u8,
+
+    /// every frame stays on screen at least this long, even if the sensortags are faster
+    pub min_time_per_frame: Duration,
+
+    /// frames light up nothing but the hitboxes, the rest of the screen stays the background
+    pub only_hitboxes: bool,
+
+    /// the scene at the time of the shot, shown dimmed behind the frames
+    pub backdrop: Option<&'a Texture<'a>>,
+}
+
+impl Default for FlashStyle<'_> {
+    fn default() -> Self {
+        FlashStyle {
+            off: 0,
+            on: 255,
+            min_time_per_frame: Duration::ZERO,
+            only_hitboxes: false,
+            backdrop: None,
+        }
+    }
+}
+
+impl<'a> FlashStyle<'a> {
+    pub fn safe(backdrop: Option<&'a Texture<'a>>) -> Self {
+        FlashStyle {
+            off: SAFE_OFF,
+            on: SAFE_ON,
+            min_time_per_frame: SAFE_MIN_TIME_PER_FRAME,
+            only_hitboxes: true,
+            backdrop,
+        }
+    }
+
+    /// with less than full contrast, hitreg needs a white reference frame to tell
+    /// dark from bright
+    pub fn needs_white_reference(&self) -> bool {
+        self.off > 0 || self.on < 255
+    }
+
+    pub fn color(&self, on: bool) -> Color {
+        let value = if on { self.on } else { self.off };
+        Color::RGB(value, value, value)
+    }
+
+    /// see grey_levels::grey
+    pub fn grey(&self, digit: u8, levels: u32) -> Color {
+        let value = grey_levels::grey_between(digit, levels, self.off, self.on);
+        Color::RGB(value, value, value)
+    }
+
+    /// for the hitbox that was hit
+    pub fn highlight(&self) -> Color {
+        Color::RGB(self.on, 0, 0)
+    }
+
+    pub fn time_per_frame(&self, flash_timing: FlashTiming) -> Duration {
+        flash_timing.time_per_frame().max(self.min_time_per_frame)
+    }
+
+    /// what is on screen where nothing is flashed
+    pub fn draw_background(&self, canvas: &mut WindowCanvas) {
+        canvas.set_draw_color(self.color(false));
+        canvas.clear();
+
+        if let Some(backdrop) = self.backdrop {
+            canvas.copy(backdrop, None, None).unwrap();
+            dim_backdrop(canvas);
+        }
+    }
+}
+
+/// darkens everything drawn so far, so that the scene behind the frames does not pass for a
+/// part of them
+pub fn dim_backdrop(target: &mut impl DrawTarget) {
+    target.set_blend_mode(BlendMode::Blend);
+    target.set_draw_color(Color::RGBA(0, 0, 0, BACKDROP_DIMMING));
+    target.fill_rect(None);
+    target.set_blend_mode(BlendMode::None);
+}
@@ -0,0 +1,107 @@
+use log::{info, warn};
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+// every frame of a flashing sequence has to stay on screen until the sensortags reported it
+// how long that takes depends on the screen, the sensortags and the radio,
+// so it is measured once per setup (see systems::latency_probe) and stored in FILE
+
+const FILE: &str = "flash_timing.txt";
+
+/// used as long as nothing was measured
+pub const DEFAULT_TIME_PER_FRAME: Duration = Duration::from_millis(220);
+
+/// below this the screen would not even show every frame for a few refreshes
+const MIN_TIME_PER_FRAME: Duration = Duration::from_millis(50);
+
+/// the measured latency is the slowest one seen during the measurement, but not necessarily
+/// the slowest one there is
+const SAFETY_FACTOR: f32 = 1.25;
+const SAFETY_MARGIN: Duration = Duration::from_millis(30);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlashTiming {
+    /// slowest time from a change on the screen until a sensortag reported it
+    pub measured_latency: Option<Duration>,
+}
+
+impl FlashTiming {
+    pub fn load() -> Self {
+        match fs::read_to_string(FILE) {
+            Ok(content) => match parse(&content) {
+                Some(measured_latency) => {
+                    info!(target: "Gui Thread", "loaded measured flash latency of {}ms from {FILE}", measured_latency.as_millis());
+                    FlashTiming {
+                        measured_latency: Some(measured_latency),
+                    }
+                }
+                None => {
+                    warn!(target: "Gui Thread", "{FILE} does not contain a latency in ms, ignoring it");
+                    FlashTiming::default()
+                }
+            },
+            Err(_) => {
+                info!(target: "Gui Thread", "no {FILE} found, flash latency has to be measured");
+                FlashTiming::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        match self.measured_latency {
+            Some(measured_latency) => {
+                fs::write(FILE, format!("{}\n", measured_latency.as_millis()))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// how long every frame of a flashing sequence stays on screen
+    pub fn time_per_frame(&self) -> Duration {
+        match self.measured_latency {
+            Some(measured_latency) => {
+                let with_factor = (measured_latency.as_millis() as f32 * SAFETY_FACTOR).round();
+                (Duration::from_millis(with_factor as u64) + SAFETY_MARGIN).max(MIN_TIME_PER_FRAME)
+            }
+            None => DEFAULT_TIME_PER_FRAME,
+        }
+    }
+}
+
+fn parse(content: &str) -> Option<Duration> {
+    content.trim().parse().ok().map(Duration::from_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::flash_timing::{DEFAULT_TIME_PER_FRAME, FlashTiming, parse};
+    use std::time::Duration;
+
+    #[test]
+    fn unmeasured_setup_uses_default() {
+        assert_eq!(
+            FlashTiming::default().time_per_frame(),
+            DEFAULT_TIME_PER_FRAME
+        );
+    }
+
+    #[test]
+    fn measured_latency_gets_a_safety_margin() {
+        let timing = FlashTiming {
+            measured_latency: Some(Duration::from_millis(120)),
+        };
+        assert_eq!(timing.time_per_frame(), Duration::from_millis(180));
+
+        let fast = FlashTiming {
+            measured_latency: Some(Duration::from_millis(5)),
+        };
+        assert_eq!(fast.time_per_frame(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn stored_latency_is_parsed() {
+        assert_eq!(parse("134\n"), Some(Duration::from_millis(134)));
+        assert_eq!(parse("fast"), None);
+    }
+}
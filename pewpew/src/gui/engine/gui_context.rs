@@ -1,13 +1,28 @@
+use crate::comm::gui::GuiComm;
+use crate::comm::message::{Worker, WorkerHealth};
+use crate::common::cancel_token::CancelToken;
+use crate::gui::engine::flash_style::FlashStyle;
+use crate::gui::engine::flash_timing::FlashTiming;
+use crate::gui::engine::present_clock::PresentClock;
+use crate::user_settings;
+use log::info;
+use sdl2::event::Event;
 use sdl2::image::InitFlag;
+use sdl2::keyboard::Keycode;
 use sdl2::render::WindowCanvas;
-use sdl2::Sdl;
-use crate::common::cancel_token::CancelToken;
-use crate::comm::gui::GuiComm;
+use sdl2::{EventPump, Sdl};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// pauses and resumes the game in every scene, see scenes::common::pause
+pub const PAUSE_KEY: Keycode = Keycode::P;
 
 pub struct Settings {
     width: Option<i32>,
     height: Option<i32>,
     fullscreen: bool, // if fullscreen == true, width and height have no effect
+    safe_flashing: bool,
+    capture_hitreg: bool,
 }
 
 impl Default for Settings {
@@ -16,12 +31,13 @@ impl Default for Settings {
             width: None,
             height: None,
             fullscreen: true,
+            safe_flashing: user_settings::SAFE_FLASHING,
+            capture_hitreg: user_settings::CAPTURE_HITREG,
         }
     }
 }
 
 impl Settings {
-
     #[allow(unused)]
     pub fn with_dimensions(mut self, width: i32, height: i32) -> Self {
         self.width = Some(width);
@@ -34,13 +50,33 @@ impl Settings {
         self.fullscreen = false;
         self
     }
+
+    #[allow(unused)]
+    pub fn with_safe_flashing(mut self) -> Self {
+        self.safe_flashing = true;
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_hitreg_capture(mut self) -> Self {
+        self.capture_hitreg = true;
+        self
+    }
 }
 
 pub struct GuiContext {
     sdl_context: Sdl,
+    // there can only be one event pump, so it lives here, see handle_events
+    event_pump: EventPump,
     canvas: WindowCanvas,
+    present_clock: PresentClock,
     cancel_token: CancelToken,
     comm: GuiComm,
+    worker_health: HashMap<Worker, WorkerHealth>,
+    paused: bool,
+    flash_timing: FlashTiming,
+    safe_flashing: bool,
+    capture_hitreg: bool,
 }
 
 impl GuiContext {
@@ -49,7 +85,7 @@ impl GuiContext {
 
         let video = sdl_context.video().unwrap();
         let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG).unwrap();
-        let _screen_refresh_rate = video.desktop_display_mode(0).unwrap().refresh_rate;
+        let screen_refresh_rate = video.desktop_display_mode(0).unwrap().refresh_rate;
         let screen_width = video.desktop_display_mode(0).unwrap().w;
         let screen_height = video.desktop_display_mode(0).unwrap().h;
 
@@ -77,11 +113,20 @@ impl GuiContext {
             .build()
             .map_err(|e| e.to_string())
             .unwrap();
+        let event_pump = sdl_context.event_pump().unwrap();
+
         GuiContext {
             sdl_context,
+            event_pump,
             canvas,
+            present_clock: PresentClock::new(screen_refresh_rate),
             cancel_token,
             comm,
+            worker_health: HashMap::new(),
+            paused: false,
+            flash_timing: FlashTiming::load(),
+            safe_flashing: settings.safe_flashing,
+            capture_hitreg: settings.capture_hitreg,
         }
     }
 
@@ -89,15 +134,101 @@ impl GuiContext {
         &mut self.sdl_context
     }
 
+    /// to be called once per frame by every scene
+    /// closing the window and escape quit, PAUSE_KEY pauses and resumes,
+    /// all other events are returned for the scene
+    pub fn handle_events(&mut self) -> Vec<Event> {
+        let mut unhandled = Vec::new();
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => {
+                    info!(target: "Gui Thread", "quit requested");
+                    self.cancel_token.cancel();
+                }
+                Event::KeyDown {
+                    keycode: Some(PAUSE_KEY),
+                    repeat: false,
+                    ..
+                } => {
+                    self.paused = !self.paused;
+                    if self.paused {
+                        sdl2::mixer::Channel::all().pause();
+                    } else {
+                        sdl2::mixer::Channel::all().resume();
+                    }
+                }
+                _ => unhandled.push(event),
+            }
+        }
+        unhandled
+    }
+
+    /// scenes return as soon as this is true, see gui::run
+    pub fn quit_requested(&self) -> bool {
+        self.cancel_token.was_canceled()
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
     pub fn canvas(&mut self) -> &mut WindowCanvas {
         &mut self.canvas
     }
 
+    /// presents the canvas, returns when the frame is estimated to be visible,
+    /// see PresentClock
+    pub fn present(&mut self) -> SystemTime {
+        self.canvas.present();
+        self.present_clock.presented(SystemTime::now())
+    }
+
     pub fn comm(&mut self) -> &mut GuiComm {
         &mut self.comm
     }
 
-    #[allow(unused)]
+    /// takes the reports of the supervisor, the scene manager does that every frame
+    pub fn update_worker_health(&mut self) {
+        while let Some(report) = self.comm.try_recv_from_supervisor() {
+            self.worker_health.insert(report.worker, report.health);
+        }
+    }
+
+    /// the last reported health of every worker thread, see supervisor
+    pub fn worker_health(&self) -> &HashMap<Worker, WorkerHealth> {
+        &self.worker_health
+    }
+
+    pub fn flash_timing(&self) -> FlashTiming {
+        self.flash_timing
+    }
+
+    pub fn set_flash_timing(&mut self, flash_timing: FlashTiming) {
+        self.flash_timing = flash_timing;
+    }
+
+    pub fn safe_flashing(&self) -> bool {
+        self.safe_flashing
+    }
+
+    /// whether every shot is saved for debugging, see hitreg::capture
+    pub fn capture_hitreg(&self) -> bool {
+        self.capture_hitreg
+    }
+
+    /// how flashing sequences look on this setup, without a backdrop
+    pub fn flash_style(&self) -> FlashStyle<'static> {
+        if self.safe_flashing {
+            FlashStyle::safe(None)
+        } else {
+            FlashStyle::default()
+        }
+    }
+
     pub fn cancel_token(&mut self) -> &mut CancelToken {
         &mut self.cancel_token
     }
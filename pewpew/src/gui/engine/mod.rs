@@ -1,6 +1,15 @@
+pub mod assets;
+pub mod audio;
 pub mod components;
 pub mod systems;
 pub mod gui_context;
 pub mod resources;
 pub mod stopwatch;
 pub mod event;
+pub mod flash_mask;
+pub mod flash_style;
+pub mod flash_timing;
+pub mod present_clock;
+pub mod region_grid;
+pub mod render;
+pub mod sprite_sheet;
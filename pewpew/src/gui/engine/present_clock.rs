@@ -0,0 +1,111 @@
+use log::trace;
+use std::time::{Duration, SystemTime};
+
+// with vsync, present returns shortly after the vertical blank in which the new frame was
+// swapped in, how shortly depends on the driver and on what else the system is doing
+// the blanks themselves come exactly once per refresh, so every return time is snapped to a grid
+// of refresh intervals that is anchored at the earliest return seen so far
+// from the blank on, the screen is scanned out top to bottom, so the middle of the screen
+// lights up half a refresh later
+// what happens after that (compositor, panel) is part of the measured flash latency,
+// see flash_timing
+
+/// how fast the grid follows returns that are late, so that it does not fall behind if the
+/// real refresh rate is a bit lower than the one the display reports
+const ANCHOR_SMOOTHING: f64 = 0.05;
+
+pub struct PresentClock {
+    refresh_interval: Option<Duration>,
+
+    /// a vertical blank, every other one is a whole number of refresh intervals away
+    anchor: Option<SystemTime>,
+}
+
+impl PresentClock {
+    /// refresh_rate in Hz as sdl reports it, 0 if unknown
+    pub fn new(refresh_rate: i32) -> Self {
+        PresentClock {
+            refresh_interval: (refresh_rate > 0)
+                .then(|| Duration::from_secs_f64(1.0 / refresh_rate as f64)),
+            anchor: None,
+        }
+    }
+
+    /// to be called with the time a present returned, returns when the frame is estimated
+    /// to be visible in the middle of the screen
+    pub fn presented(&mut self, returned_at: SystemTime) -> SystemTime {
+        let Some(interval) = self.refresh_interval else {
+            return returned_at;
+        };
+
+        let vblank = self.vblank_before(returned_at, interval);
+        trace!(target: "Gui Thread", "present returned {}us after the vertical blank", returned_at.duration_since(vblank).unwrap_or_default().as_micros());
+
+        vblank + interval / 2
+    }
+
+    fn vblank_before(&mut self, returned_at: SystemTime, interval: Duration) -> SystemTime {
+        let Some(anchor) = self.anchor else {
+            self.anchor = Some(returned_at);
+            return returned_at;
+        };
+
+        let Ok(since_anchor) = returned_at.duration_since(anchor) else {
+            // earlier than the anchor, this one is closer to the real blank
+            self.anchor = Some(returned_at);
+            return returned_at;
+        };
+
+        let interval_ns = interval.as_nanos();
+        let late = Duration::from_nanos((since_anchor.as_nanos() % interval_ns) as u64);
+
+        if late > interval / 2 {
+            // before the blank the grid expects, so the grid is too late
+            self.anchor = Some(returned_at);
+            return returned_at;
+        }
+
+        let vblank = returned_at - late;
+        self.anchor = Some(vblank + late.mul_f64(ANCHOR_SMOOTHING));
+        vblank
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::present_clock::PresentClock;
+    use std::time::{Duration, SystemTime};
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn late_returns_are_snapped_to_the_blank() {
+        let mut clock = PresentClock::new(50);
+        let t0 = SystemTime::now();
+
+        assert_eq!(clock.presented(t0), t0 + ms(10));
+        // two refreshes later, but the present returned 3ms after the blank
+        assert_eq!(clock.presented(t0 + ms(43)), t0 + ms(50));
+    }
+
+    #[test]
+    fn earlier_return_moves_the_grid() {
+        let mut clock = PresentClock::new(50);
+        let t0 = SystemTime::now();
+
+        // the first present was late, the next one shows where the blanks really are
+        clock.presented(t0 + ms(4));
+        assert_eq!(clock.presented(t0 + ms(20)), t0 + ms(30));
+        assert_eq!(clock.presented(t0 + ms(41)), t0 + ms(50));
+    }
+
+    #[test]
+    fn unknown_refresh_rate_keeps_the_return_time() {
+        let mut clock = PresentClock::new(0);
+        let t0 = SystemTime::now();
+
+        assert_eq!(clock.presented(t0), t0);
+    }
+}
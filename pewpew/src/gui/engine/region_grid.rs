@@ -0,0 +1,187 @@
+use crate::hitreg::decoder::num_frames_for;
+use crate::hitreg::grey_levels;
+use sdl2::rect::Rect;
+
+// every frame of the flashing sequence takes 220ms, with many hitboxes on screen a single
+// flashing sequence gets slow
+// the coarse to fine flashing sequence first flashes a grid of screen regions to find out
+// where the sensortag points at, then only codes the hitboxes inside that region
+
+/// grids the coarse stage can choose from, as (columns, rows)
+const GRIDS: [(u32, u32); 5] = [(2, 1), (2, 2), (4, 2), (4, 4), (8, 4)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionGrid {
+    pub viewport: Rect,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl RegionGrid {
+    pub fn new(viewport: Rect, columns: u32, rows: u32) -> Self {
+        RegionGrid {
+            viewport,
+            columns,
+            rows,
+        }
+    }
+
+    pub fn num_regions(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    pub fn num_frames(&self) -> u32 {
+        num_frames_for(self.num_regions())
+    }
+
+    /// regions are numbered row by row, starting at the top left
+    pub fn region(&self, index: u32) -> Rect {
+        let column = index % self.columns;
+        let row = index / self.columns;
+
+        // the last column and row take whatever is left, so no pixel is missing
+        let left = self.viewport.width() * column / self.columns;
+        let right = self.viewport.width() * (column + 1) / self.columns;
+        let top = self.viewport.height() * row / self.rows;
+        let bottom = self.viewport.height() * (row + 1) / self.rows;
+
+        Rect::new(
+            self.viewport.x() + left as i32,
+            self.viewport.y() + top as i32,
+            right - left,
+            bottom - top,
+        )
+    }
+
+    /// hitboxes on a border between regions take part in the second stage of every region they touch
+    pub fn hitboxes_in_region(&self, index: u32, hitboxes: &[Rect]) -> usize {
+        let region = self.region(index);
+        hitboxes
+            .iter()
+            .filter(|hitbox| overlaps(**hitbox, region))
+            .count()
+    }
+
+    /// frames of both stages together, averaged over all regions
+    /// a region without hitboxes needs no second stage at all
+    /// the regions are always black and white, the hitboxes use `levels` grey levels
+    pub fn expected_frames(&self, hitboxes: &[Rect], levels: u32) -> f32 {
+        let second_stage_frames: u32 = (0..self.num_regions())
+            .map(|index| {
+                grey_levels::sequence_length(
+                    self.hitboxes_in_region(index, hitboxes) as u32,
+                    levels,
+                )
+            })
+            .sum();
+
+        self.num_frames() as f32 + second_stage_frames as f32 / self.num_regions() as f32
+    }
+}
+
+pub fn overlaps(a: Rect, b: Rect) -> bool {
+    a.left() < b.right() && b.left() < a.right() && a.top() < b.bottom() && b.top() < a.bottom()
+}
+
+/// the grid that needs the fewest frames on average for these hitboxes, or None if
+/// flashing all hitboxes at once is just as fast
+///
+/// in the worst case (the sensortag points at the most crowded region) the coarse to fine
+/// sequence is never faster, it pays off when the hitboxes are crowded in a few regions
+/// and the shots go anywhere on the screen
+pub fn plan(viewport: Rect, hitboxes: &[Rect], levels: u32) -> Option<RegionGrid> {
+    let direct_frames = grey_levels::sequence_length(hitboxes.len() as u32, levels) as f32;
+
+    GRIDS
+        .iter()
+        .map(|(columns, rows)| RegionGrid::new(viewport, *columns, *rows))
+        .map(|grid| (grid.expected_frames(hitboxes, levels), grid))
+        .filter(|(frames, _)| *frames < direct_frames)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, grid)| grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::region_grid::{RegionGrid, plan};
+    use sdl2::rect::Rect;
+
+    fn viewport() -> Rect {
+        Rect::new(0, 0, 1920, 1080)
+    }
+
+    /// n hitboxes of 10x10 pixels, all inside the given area
+    fn hitboxes_in(area: Rect, n: i32) -> Vec<Rect> {
+        (0..n)
+            .map(|i| {
+                Rect::new(
+                    area.x() + (i * 10) % (area.width() as i32 - 10),
+                    area.y() + (i * 10) / (area.width() as i32 - 10) * 10,
+                    10,
+                    10,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn regions_cover_the_viewport() {
+        let grid = RegionGrid::new(Rect::new(0, 0, 1000, 700), 4, 3);
+        let area: u32 = (0..grid.num_regions())
+            .map(|index| grid.region(index).width() * grid.region(index).height())
+            .sum();
+
+        assert_eq!(area, 1000 * 700);
+        assert_eq!(grid.region(0), Rect::new(0, 0, 250, 233));
+        assert_eq!(grid.region(11), Rect::new(750, 466, 250, 234));
+    }
+
+    #[test]
+    fn hitboxes_on_a_border_count_for_both_regions() {
+        let grid = RegionGrid::new(viewport(), 2, 1);
+        let hitboxes = [Rect::new(955, 500, 10, 10)];
+
+        assert_eq!(grid.hitboxes_in_region(0, &hitboxes), 1);
+        assert_eq!(grid.hitboxes_in_region(1, &hitboxes), 1);
+    }
+
+    #[test]
+    fn few_hitboxes_are_flashed_directly() {
+        assert_eq!(plan(viewport(), &hitboxes_in(viewport(), 10), 2), None);
+        assert_eq!(plan(viewport(), &[], 2), None);
+    }
+
+    #[test]
+    fn evenly_spread_hitboxes_are_flashed_directly() {
+        // 32 hitboxes in every region of a 4x4 grid: 10 frames at once,
+        // but 5 frames to find the region + 6 frames for its hitboxes
+        let grid = RegionGrid::new(viewport(), 4, 4);
+        let hitboxes = (0..grid.num_regions())
+            .flat_map(|index| {
+                let region = grid.region(index);
+                let inner = Rect::new(
+                    region.x() + 20,
+                    region.y() + 20,
+                    region.width() - 40,
+                    region.height() - 40,
+                );
+                hitboxes_in(inner, 32)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(grid.expected_frames(&hitboxes, 2), 11.0);
+        assert_eq!(plan(viewport(), &hitboxes, 2), None);
+    }
+
+    #[test]
+    fn crowded_hitboxes_use_a_grid() {
+        // 250 hitboxes in the top left corner: 8 frames at once
+        // with a 2x2 grid it is 3 frames to find the quarter, and only 1 in 4 shots
+        // lands in the crowded quarter and needs 8 more
+        let hitboxes = hitboxes_in(Rect::new(20, 20, 400, 200), 250);
+
+        let planned = plan(viewport(), &hitboxes, 2).unwrap();
+        assert_eq!(planned.num_regions(), 4);
+        assert_eq!(planned.expected_frames(&hitboxes, 2), 5.0);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::gui::engine::render::software::Bitmap;
+use sdl2::pixels::Color;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// golden images are frames that were checked by eye once and are compared with every run since
+// they are binary ppm (P6) files, which every image viewer opens and which need no image
+// library to read or write
+// a golden that does not exist yet is written by the test that needs it, with UPDATE_GOLDEN=1
+// all of them are written again, e.g. after a change of the drawing that was meant to be
+// if a frame differs, it is written next to its golden as <name>.actual.ppm to compare them
+
+/// relative to the crate, like the tests see it
+pub const GOLDEN_DIR: &str = "golden";
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(GOLDEN_DIR).join(format!("{name}.ppm"))
+}
+
+/// fails the test if `frame` is not the golden image `name`
+pub fn assert_golden(name: &str, frame: &Bitmap) {
+    let path = golden_path(name);
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|value| value == "1");
+    if update || !path.exists() {
+        fs::create_dir_all(GOLDEN_DIR).unwrap();
+        write_ppm(&path, frame).unwrap();
+        return;
+    }
+
+    let golden = read_ppm(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    if golden == *frame {
+        return;
+    }
+
+    let actual = path.with_extension("actual.ppm");
+    write_ppm(&actual, frame).unwrap();
+    if (golden.width, golden.height) != (frame.width, frame.height) {
+        panic!(
+            "{name} is {}x{}, its golden {}x{}, see {}",
+            frame.width,
+            frame.height,
+            golden.width,
+            golden.height,
+            actual.display()
+        );
+    }
+    let differing = golden
+        .pixels()
+        .iter()
+        .zip(frame.pixels())
+        .filter(|(golden, actual)| golden != actual)
+        .count();
+    panic!(
+        "{differing} pixels of {name} differ from {}, see {}",
+        path.display(),
+        actual.display()
+    );
+}
+
+pub fn write_ppm(path: &Path, bitmap: &Bitmap) -> std::io::Result<()> {
+    let mut content = format!("P6\n{} {}\n255\n", bitmap.width, bitmap.height).into_bytes();
+    for pixel in bitmap.pixels() {
+        content.extend([pixel.r, pixel.g, pixel.b]);
+    }
+    fs::write(path, content)
+}
+
+/// only reads what write_ppm writes
+pub fn read_ppm(path: &Path) -> Result<Bitmap, String> {
+    let content = fs::read(path).map_err(|e| e.to_string())?;
+
+    // the header is 4 words separated by whitespace, the pixels start after the last one
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, byte) in content.iter().enumerate() {
+        if byte.is_ascii_whitespace() {
+            if i > start {
+                words.push(String::from_utf8_lossy(&content[start..i]).to_string());
+            }
+            start = i + 1;
+            if words.len() == 4 {
+                break;
+            }
+        }
+    }
+    let [magic, width, height, max] = words.as_slice() else {
+        return Err("the header is cut off".to_string());
+    };
+    if magic != "P6" || max != "255" {
+        return Err(format!(
+            "expected a P6 ppm with 8 bit, got {magic} with {max}"
+        ));
+    }
+    let width = width.parse::<u32>().map_err(|e| e.to_string())?;
+    let height = height.parse::<u32>().map_err(|e| e.to_string())?;
+
+    let pixels = &content[start..];
+    if pixels.len() != (width * height * 3) as usize {
+        return Err(format!(
+            "{} bytes of pixels for {width}x{height}",
+            pixels.len()
+        ));
+    }
+    Ok(Bitmap::from_fn(width, height, |x, y| {
+        let i = ((y * width + x) * 3) as usize;
+        Color::RGB(pixels[i], pixels[i + 1], pixels[i + 2])
+    }))
+}
@@ -0,0 +1,98 @@
+use crate::gui::engine::assets::TextureHandle;
+use crate::gui::engine::components::text::Text;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point as SdlPoint, Rect};
+use sdl2::render::{BlendMode, WindowCanvas};
+
+// the drawing systems draw to a Renderer instead of a WindowCanvas, so that they can draw
+// without a display
+// a DrawTarget only knows rectangles and colors, that is all flash frames need, a Renderer also
+// draws the images of the assets and texts
+// in the game SdlRenderer draws to the window (or to a texture of it), WindowCanvas itself is a
+// DrawTarget
+// the tests draw to software::SoftwareRenderer, a plain pixel buffer, and compare what was drawn
+// with the images in golden::GOLDEN_DIR, so the drawing can be regression tested on a machine
+// without GPU, see golden
+
+pub mod sdl;
+
+#[cfg(test)]
+pub mod golden;
+#[cfg(test)]
+pub mod software;
+
+pub trait DrawTarget {
+    /// (width, height) in pixels
+    fn output_size(&self) -> (u32, u32);
+    fn set_draw_color(&mut self, color: Color);
+    fn set_blend_mode(&mut self, blend_mode: BlendMode);
+
+    /// fills everything with the draw color, regardless of the blend mode
+    fn clear(&mut self);
+
+    /// None fills everything
+    fn fill_rect<R: Into<Option<Rect>>>(&mut self, rect: R);
+    fn fill_rects(&mut self, rects: &[Rect]);
+
+    /// one pixel wide outlines
+    fn draw_rects(&mut self, rects: &[Rect]);
+}
+
+/// a part of an image and where it goes, like WindowCanvas::copy_ex takes it
+#[derive(Debug, Clone, Copy)]
+pub struct ImageCopy {
+    pub source: Rect,
+    pub destination: Rect,
+
+    /// clockwise around the pivot
+    pub rotation_deg: f64,
+
+    /// relative to the top left of destination
+    pub pivot: SdlPoint,
+    pub flip_horizontally: bool,
+    pub flip_vertically: bool,
+}
+
+pub trait Renderer: DrawTarget {
+    /// (width, height) of the whole image in pixels
+    fn image_size(&self, image: TextureHandle) -> (u32, u32);
+
+    /// images are blended with their alpha channel
+    fn draw_image(&mut self, image: TextureHandle, copy: &ImageCopy);
+
+    /// (width, height) of the text in pixels, before it is scaled
+    fn text_size(&mut self, text: &Text) -> (u32, u32);
+
+    /// draws the text stretched to `destination`
+    fn draw_text(&mut self, text: &Text, destination: Rect);
+}
+
+impl DrawTarget for WindowCanvas {
+    fn output_size(&self) -> (u32, u32) {
+        WindowCanvas::output_size(self).unwrap()
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        WindowCanvas::set_draw_color(self, color);
+    }
+
+    fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        WindowCanvas::set_blend_mode(self, blend_mode);
+    }
+
+    fn clear(&mut self) {
+        WindowCanvas::clear(self);
+    }
+
+    fn fill_rect<R: Into<Option<Rect>>>(&mut self, rect: R) {
+        WindowCanvas::fill_rect(self, rect).unwrap();
+    }
+
+    fn fill_rects(&mut self, rects: &[Rect]) {
+        WindowCanvas::fill_rects(self, rects).unwrap();
+    }
+
+    fn draw_rects(&mut self, rects: &[Rect]) {
+        WindowCanvas::draw_rects(self, rects).unwrap();
+    }
+}
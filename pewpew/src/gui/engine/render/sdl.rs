@@ -0,0 +1,110 @@
+use crate::gui::engine::assets::TextureHandle;
+use crate::gui::engine::components::text::Text;
+use crate::gui::engine::render::{DrawTarget, ImageCopy, Renderer};
+use crate::gui::engine::resources::Resources;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, TextureCreator, TextureQuery, WindowCanvas};
+use sdl2::video::WindowContext;
+
+/// draws to a canvas of the window with the textures of the assets and the default font
+pub struct SdlRenderer<'r, 'sdl> {
+    canvas: &'r mut WindowCanvas,
+    resources: &'r mut Resources<'sdl>,
+    texture_creator: &'sdl TextureCreator<WindowContext>,
+}
+
+impl<'r, 'sdl> SdlRenderer<'r, 'sdl> {
+    pub fn new(
+        canvas: &'r mut WindowCanvas,
+        resources: &'r mut Resources<'sdl>,
+        texture_creator: &'sdl TextureCreator<WindowContext>,
+    ) -> Self {
+        SdlRenderer {
+            canvas,
+            resources,
+            texture_creator,
+        }
+    }
+}
+
+/// the texture of the text, rendered with the default font
+fn rendered_text<'c, 'sdl>(
+    resources: &'c mut Resources<'sdl>,
+    texture_creator: &'sdl TextureCreator<WindowContext>,
+    text: &Text,
+) -> &'c Texture<'sdl> {
+    // apparently rendering text is expensive af, so we cache rendered text
+    resources.get_cached_or_render_text_with(text, |resources: &Resources| {
+        let surface = resources
+            .default_font
+            .render(text.text.as_str())
+            .blended(text.color)
+            .unwrap();
+        texture_creator
+            .create_texture_from_surface(&surface)
+            .unwrap()
+    })
+}
+
+impl DrawTarget for SdlRenderer<'_, '_> {
+    fn output_size(&self) -> (u32, u32) {
+        DrawTarget::output_size(&*self.canvas)
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        self.canvas.set_draw_color(color);
+    }
+
+    fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.canvas.set_blend_mode(blend_mode);
+    }
+
+    fn clear(&mut self) {
+        self.canvas.clear();
+    }
+
+    fn fill_rect<R: Into<Option<Rect>>>(&mut self, rect: R) {
+        self.canvas.fill_rect(rect).unwrap();
+    }
+
+    fn fill_rects(&mut self, rects: &[Rect]) {
+        self.canvas.fill_rects(rects).unwrap();
+    }
+
+    fn draw_rects(&mut self, rects: &[Rect]) {
+        self.canvas.draw_rects(rects).unwrap();
+    }
+}
+
+impl Renderer for SdlRenderer<'_, '_> {
+    fn image_size(&self, image: TextureHandle) -> (u32, u32) {
+        let TextureQuery { width, height, .. } = self.resources.assets.image(image).query();
+        (width, height)
+    }
+
+    fn draw_image(&mut self, image: TextureHandle, copy: &ImageCopy) {
+        self.canvas
+            .copy_ex(
+                self.resources.assets.image(image),
+                Some(copy.source),
+                Some(copy.destination),
+                copy.rotation_deg,
+                copy.pivot,
+                copy.flip_horizontally,
+                copy.flip_vertically,
+            )
+            .unwrap();
+    }
+
+    fn text_size(&mut self, text: &Text) -> (u32, u32) {
+        let TextureQuery { width, height, .. } =
+            rendered_text(self.resources, self.texture_creator, text).query();
+        (width, height)
+    }
+
+    fn draw_text(&mut self, text: &Text, destination: Rect) {
+        let texture = rendered_text(self.resources, self.texture_creator, text);
+        self.canvas.copy(texture, None, destination).unwrap();
+    }
+}
@@ -0,0 +1,264 @@
+use crate::gui::engine::assets::TextureHandle;
+use crate::gui::engine::components::text::Text;
+use crate::gui::engine::render::{DrawTarget, ImageCopy, Renderer};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::BlendMode;
+use std::collections::HashMap;
+
+// a renderer that draws into memory, for tests on machines without display
+// it draws like SDL does as far as the goldens can tell: images are scaled by nearest neighbour,
+// flipped and rotated around their pivot and blended with their alpha channel, fills blend
+// with the draw color only in BlendMode::Blend
+// there are no fonts, every character of a text is a box of GLYPH_SIZE, spaces are left empty,
+// so goldens show where texts go and how large they are, not how they read
+
+/// (width, height) of a character at scale 1, about what the default font has at 128pt
+pub const GLYPH_SIZE: (u32, u32) = (72, 180);
+
+/// an RGB image, what the renderer draws to and what images are made of
+/// the alpha of pixels is kept for images, the frame is always opaque
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Bitmap {
+    pub fn new(width: u32, height: u32, color: Color) -> Self {
+        Bitmap {
+            width,
+            height,
+            pixels: vec![color; (width * height) as usize],
+        }
+    }
+
+    /// every pixel is `color_at(x, y)`
+    pub fn from_fn(width: u32, height: u32, color_at: impl Fn(u32, u32) -> Color) -> Self {
+        Bitmap {
+            width,
+            height,
+            pixels: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| color_at(x, y))
+                .collect(),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// blends `color` over the pixel with its alpha, pixels outside are left out
+    fn blend(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let pixel = &mut self.pixels[(y as u32 * self.width + x as u32) as usize];
+        let mix = |source: u8, destination: u8| {
+            ((source as u32 * color.a as u32 + destination as u32 * (255 - color.a as u32) + 127)
+                / 255) as u8
+        };
+        *pixel = Color::RGB(
+            mix(color.r, pixel.r),
+            mix(color.g, pixel.g),
+            mix(color.b, pixel.b),
+        );
+    }
+
+    /// the part of `rect` that is inside the bitmap, as (left, top, right, bottom)
+    /// Rect::intersection would ask SDL, which the tests do not have
+    fn clip(&self, rect: Rect) -> (i32, i32, i32, i32) {
+        (
+            rect.left().max(0),
+            rect.top().max(0),
+            rect.right().min(self.width as i32),
+            rect.bottom().min(self.height as i32),
+        )
+    }
+}
+
+pub struct SoftwareRenderer {
+    frame: Bitmap,
+    draw_color: Color,
+    blend_mode: BlendMode,
+    images: HashMap<TextureHandle, Bitmap>,
+}
+
+impl SoftwareRenderer {
+    /// a black frame
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareRenderer {
+            frame: Bitmap::new(width, height, Color::BLACK),
+            draw_color: Color::BLACK,
+            blend_mode: BlendMode::None,
+            images: HashMap::new(),
+        }
+    }
+
+    /// the handle to draw `image` with, like Assets::texture gives it
+    pub fn add_image(&mut self, image: Bitmap) -> TextureHandle {
+        let handle = TextureHandle::nth(self.images.len());
+        self.images.insert(handle, image);
+        handle
+    }
+
+    /// what was drawn so far
+    pub fn frame(&self) -> &Bitmap {
+        &self.frame
+    }
+
+    /// the draw color as it is put on a pixel in the current blend mode
+    fn fill_color(&self) -> Color {
+        match self.blend_mode {
+            BlendMode::Blend => self.draw_color,
+            _ => Color {
+                a: 255,
+                ..self.draw_color
+            },
+        }
+    }
+
+    fn fill(&mut self, rect: Rect, color: Color) {
+        let (left, top, right, bottom) = self.frame.clip(rect);
+        for y in top..bottom {
+            for x in left..right {
+                self.frame.blend(x, y, color);
+            }
+        }
+    }
+}
+
+impl DrawTarget for SoftwareRenderer {
+    fn output_size(&self) -> (u32, u32) {
+        (self.frame.width, self.frame.height)
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        self.draw_color = color;
+    }
+
+    fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    fn clear(&mut self) {
+        self.frame = Bitmap::new(
+            self.frame.width,
+            self.frame.height,
+            Color {
+                a: 255,
+                ..self.draw_color
+            },
+        );
+    }
+
+    fn fill_rect<R: Into<Option<Rect>>>(&mut self, rect: R) {
+        let rect = rect
+            .into()
+            .unwrap_or(Rect::new(0, 0, self.frame.width, self.frame.height));
+        self.fill(rect, self.fill_color());
+    }
+
+    fn fill_rects(&mut self, rects: &[Rect]) {
+        for rect in rects {
+            self.fill(*rect, self.fill_color());
+        }
+    }
+
+    fn draw_rects(&mut self, rects: &[Rect]) {
+        let color = self.fill_color();
+        for rect in rects {
+            let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+            self.fill(Rect::new(left, top, rect.width(), 1), color);
+            self.fill(Rect::new(left, bottom - 1, rect.width(), 1), color);
+            self.fill(
+                Rect::new(left, top + 1, 1, rect.height().saturating_sub(2)),
+                color,
+            );
+            self.fill(
+                Rect::new(right - 1, top + 1, 1, rect.height().saturating_sub(2)),
+                color,
+            );
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn image_size(&self, image: TextureHandle) -> (u32, u32) {
+        let image = &self.images[&image];
+        (image.width, image.height)
+    }
+
+    fn draw_image(&mut self, image: TextureHandle, copy: &ImageCopy) {
+        let image = &self.images[&image];
+        let (source, destination) = (copy.source, copy.destination);
+        let (sin, cos) = copy.rotation_deg.to_radians().sin_cos();
+        let pivot = (
+            destination.x() as f64 + copy.pivot.x() as f64,
+            destination.y() as f64 + copy.pivot.y() as f64,
+        );
+
+        // every pixel of the frame is turned back by the rotation to find the pixel of the
+        // destination it shows, the frames of the tests are small enough to try all of them
+        for y in 0..self.frame.height as i32 {
+            for x in 0..self.frame.width as i32 {
+                let (dx, dy) = (x as f64 + 0.5 - pivot.0, y as f64 + 0.5 - pivot.1);
+                let u = cos * dx + sin * dy + pivot.0 - destination.x() as f64;
+                let v = -sin * dx + cos * dy + pivot.1 - destination.y() as f64;
+                if u < 0.0
+                    || v < 0.0
+                    || u >= destination.width() as f64
+                    || v >= destination.height() as f64
+                {
+                    continue;
+                }
+
+                let mut column = (u * source.width() as f64 / destination.width() as f64) as u32;
+                let mut row = (v * source.height() as f64 / destination.height() as f64) as u32;
+                if copy.flip_horizontally {
+                    column = source.width() - 1 - column;
+                }
+                if copy.flip_vertically {
+                    row = source.height() - 1 - row;
+                }
+
+                let color = image.pixel(source.x() as u32 + column, source.y() as u32 + row);
+                self.frame.blend(x, y, color);
+            }
+        }
+    }
+
+    fn text_size(&mut self, text: &Text) -> (u32, u32) {
+        (
+            GLYPH_SIZE.0 * text.text.chars().count() as u32,
+            GLYPH_SIZE.1,
+        )
+    }
+
+    fn draw_text(&mut self, text: &Text, destination: Rect) {
+        let num_chars = text.text.chars().count() as u32;
+        for (i, char) in text.text.chars().enumerate() {
+            if char.is_whitespace() {
+                continue;
+            }
+
+            // the box leaves space between characters and lines like a font does
+            let left = destination.x() + (i as u32 * destination.width() / num_chars) as i32;
+            let right = destination.x() + ((i as u32 + 1) * destination.width() / num_chars) as i32;
+            let width = (right - left) as u32;
+            let glyph = Rect::new(
+                left + (width / 6) as i32,
+                destination.y() + (destination.height() / 4) as i32,
+                (width - width / 3).max(1),
+                (destination.height() - destination.height() / 4 - destination.height() / 8).max(1),
+            );
+            self.fill(glyph, text.color);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::gui::engine::assets::{AssetError, Assets};
+use crate::gui::engine::audio::Audio;
 use crate::gui::engine::components::text::Text;
 use sdl2::render::Texture;
 use sdl2::ttf::Font;
@@ -5,18 +7,22 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 pub struct Resources<'sdl_ctx> {
-    pub images: Vec<Texture<'sdl_ctx>>,
+    pub assets: Assets<'sdl_ctx>,
+    pub audio: Audio,
     pub default_font: Font<'sdl_ctx, 'static>,
     pub text_cache: HashMap<(String, u32, u32), (Instant, Texture<'sdl_ctx>)>,
 }
 
 impl<'sdl_ctx> Resources<'sdl_ctx> {
-    pub fn new(default_font: Font<'sdl_ctx, 'static>) -> Self {
-        Resources {
-            images: vec![],
+    /// the default font is the one named "default" in the manifest
+    pub fn new(assets: Assets<'sdl_ctx>, audio: Audio) -> Result<Self, AssetError> {
+        let default_font = assets.font("default", 128)?;
+        Ok(Resources {
+            assets,
+            audio,
             default_font,
             text_cache: HashMap::new(),
-        }
+        })
     }
 
     pub fn get_cached_or_render_text_with<T: FnOnce(&Resources) -> Texture<'sdl_ctx>>(
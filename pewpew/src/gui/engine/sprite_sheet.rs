@@ -0,0 +1,335 @@
+use crate::gui::engine::assets::{AssetError, TextureHandle};
+use crate::gui::engine::components::texture::AnimationEndBehavior;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// how the frames of an image are laid out and played, so that artists can change an animation
+// without touching the code, the code only knows the names of the animations
+// the metadata of an image lives next to it, with the extension SIDECAR_EXTENSION, e.g.
+// images/flying_huhn.sheet for images/flying_huhn.png, an image without one is a single still frame
+// a line of a sheet is one of
+//   grid <columns> <rows>                 cells of equal size, numbered row by row from 0
+//   pivot <x> <y>                         textures rotate around it, (0, 0) is the top left of a
+//                                         cell and (1, 1) its bottom right, default the center
+//   hitbox rectangle|circle|alpha         shape of hitboxes on it, alpha is the opaque pixels
+//   animation <name> <cells> [<ms>...] [loop|freeze]
+// cells are ranges like 0-12 or lists like 0,1,2,1 and may be mixed, there is either one duration
+// for all of them, one per cell or none for cells that are shown until something changes them
+// empty lines and lines starting with # are skipped, like in the asset manifest
+
+pub const SIDECAR_EXTENSION: &str = "sheet";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grid {
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Grid {
+    pub fn num_cells(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    /// (column, row) of `cell`
+    pub fn position(&self, cell: u32) -> (u32, u32) {
+        (cell % self.columns, cell / self.columns)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitboxKind {
+    Rectangle,
+    Circle,
+    Alpha,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationFrame {
+    pub cell: u32,
+
+    /// None for a frame that is shown until something else changes it
+    pub duration: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    pub end_behavior: AnimationEndBehavior,
+}
+
+impl Animation {
+    /// a single frame that stays
+    pub fn still(cell: u32) -> Self {
+        Animation {
+            frames: vec![AnimationFrame {
+                cell,
+                duration: None,
+            }],
+            end_behavior: AnimationEndBehavior::Loop,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    pub grid: Grid,
+    pub pivot: (f32, f32),
+    pub hitbox: HitboxKind,
+    animations: HashMap<String, Animation>,
+}
+
+impl Default for SpriteSheet {
+    /// what an image without sidecar is
+    fn default() -> Self {
+        SpriteSheet {
+            grid: Grid {
+                columns: 1,
+                rows: 1,
+            },
+            pivot: (0.5, 0.5),
+            hitbox: HitboxKind::Rectangle,
+            animations: HashMap::new(),
+        }
+    }
+}
+
+impl SpriteSheet {
+    /// where the sheet of the image at `image_path` is
+    pub fn sidecar_of(image_path: &Path) -> PathBuf {
+        image_path.with_extension(SIDECAR_EXTENSION)
+    }
+
+    /// `path` is only used in errors
+    pub fn parse(content: &str, path: &Path) -> Result<Self, AssetError> {
+        let mut sheet = SpriteSheet::default();
+        let mut animation_lines = Vec::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: String| AssetError::InvalidSpriteSheet {
+                path: path.to_path_buf(),
+                line: i + 1,
+                reason,
+            };
+
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words.as_slice() {
+                ["grid", columns, rows] => {
+                    sheet.grid = Grid {
+                        columns: parse_number(columns).map_err(invalid)?,
+                        rows: parse_number(rows).map_err(invalid)?,
+                    };
+                    if sheet.grid.num_cells() == 0 {
+                        return Err(invalid("a grid needs at least one cell".to_string()));
+                    }
+                }
+                ["pivot", x, y] => {
+                    sheet.pivot = (
+                        parse_number(x).map_err(invalid)?,
+                        parse_number(y).map_err(invalid)?,
+                    );
+                }
+                ["hitbox", kind] => {
+                    sheet.hitbox = match *kind {
+                        "rectangle" => HitboxKind::Rectangle,
+                        "circle" => HitboxKind::Circle,
+                        "alpha" => HitboxKind::Alpha,
+                        _ => return Err(invalid(format!("unknown hitbox \"{kind}\""))),
+                    };
+                }
+                ["animation", name, cells, rest @ ..] => {
+                    // cells are checked against the grid once all lines are read
+                    let animation = parse_animation(cells, rest).map_err(invalid)?;
+                    if sheet
+                        .animations
+                        .insert(name.to_string(), animation)
+                        .is_some()
+                    {
+                        return Err(invalid(format!("animation \"{name}\" is listed twice")));
+                    }
+                    animation_lines.push((i + 1, name.to_string()));
+                }
+                _ => return Err(invalid(format!("cannot make sense of \"{line}\""))),
+            }
+        }
+
+        for (line, name) in animation_lines {
+            if let Some(frame) = sheet.animations[&name]
+                .frames
+                .iter()
+                .find(|frame| frame.cell >= sheet.grid.num_cells())
+            {
+                return Err(AssetError::InvalidSpriteSheet {
+                    path: path.to_path_buf(),
+                    line,
+                    reason: format!(
+                        "cell {} is outside the {}x{} grid",
+                        frame.cell, sheet.grid.columns, sheet.grid.rows
+                    ),
+                });
+            }
+        }
+
+        Ok(sheet)
+    }
+
+    /// every sheet has the still animation "still" of the first cell, unless it lists its own
+    pub fn animation(&self, name: &str) -> Option<Animation> {
+        match self.animations.get(name) {
+            Some(animation) => Some(animation.clone()),
+            None if name == "still" => Some(Animation::still(0)),
+            None => None,
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("\"{word}\" is not a valid number"))
+}
+
+fn parse_animation(cells: &str, rest: &[&str]) -> Result<Animation, String> {
+    let mut cell_list = Vec::new();
+    for part in cells.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse_number::<u32>(first)?, parse_number::<u32>(last)?);
+                if first > last {
+                    return Err(format!("the range {part} is backwards"));
+                }
+                cell_list.extend(first..=last);
+            }
+            None => cell_list.push(parse_number(part)?),
+        }
+    }
+
+    let (end_behavior, durations) = match rest.split_last() {
+        Some((&"loop", durations)) => (AnimationEndBehavior::Loop, durations),
+        Some((&"freeze", durations)) => (AnimationEndBehavior::Freeze, durations),
+        _ => (AnimationEndBehavior::Loop, rest),
+    };
+    let durations = durations
+        .iter()
+        .map(|ms| parse_number(ms).map(Duration::from_millis))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let duration_of = |i: usize| match durations.len() {
+        0 => Ok(None),
+        1 => Ok(Some(durations[0])),
+        n if n == cell_list.len() => Ok(Some(durations[i])),
+        n => Err(format!(
+            "{n} durations for {} cells, expected none, one or one per cell",
+            cell_list.len()
+        )),
+    };
+
+    Ok(Animation {
+        frames: cell_list
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                Ok(AnimationFrame {
+                    cell: *cell,
+                    duration: duration_of(i)?,
+                })
+            })
+            .collect::<Result<_, String>>()?,
+        end_behavior,
+    })
+}
+
+/// an animation of an image together with how to cut the image, what texture::Builder needs
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    pub image: TextureHandle,
+    pub grid: Grid,
+    pub pivot: (f32, f32),
+    pub animation: Animation,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::assets::AssetError;
+    use crate::gui::engine::components::texture::AnimationEndBehavior;
+    use crate::gui::engine::sprite_sheet::{AnimationFrame, Grid, HitboxKind, SpriteSheet};
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[test]
+    fn sheet_lists_grid_pivot_hitbox_and_animations() {
+        let sheet = SpriteSheet::parse(
+            "# a test\ngrid 2 3\npivot 0.5 1\nhitbox alpha\n\nanimation walk 0-2,5 100\nanimation die 3,4 50 250 freeze\n",
+            Path::new("test.sheet"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            sheet.grid,
+            Grid {
+                columns: 2,
+                rows: 3
+            }
+        );
+        assert_eq!(sheet.grid.position(5), (1, 2));
+        assert_eq!(sheet.pivot, (0.5, 1.0));
+        assert_eq!(sheet.hitbox, HitboxKind::Alpha);
+
+        let walk = sheet.animation("walk").unwrap();
+        assert_eq!(
+            walk.frames
+                .iter()
+                .map(|frame| frame.cell)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 5]
+        );
+        assert!(
+            walk.frames
+                .iter()
+                .all(|frame| frame.duration == Some(Duration::from_millis(100)))
+        );
+        assert!(matches!(walk.end_behavior, AnimationEndBehavior::Loop));
+
+        let die = sheet.animation("die").unwrap();
+        assert_eq!(
+            die.frames,
+            vec![
+                AnimationFrame {
+                    cell: 3,
+                    duration: Some(Duration::from_millis(50))
+                },
+                AnimationFrame {
+                    cell: 4,
+                    duration: Some(Duration::from_millis(250))
+                },
+            ]
+        );
+        assert!(matches!(die.end_behavior, AnimationEndBehavior::Freeze));
+
+        assert_eq!(sheet.animation("still").unwrap().frames[0].duration, None);
+        assert!(sheet.animation("fly").is_none());
+    }
+
+    #[test]
+    fn invalid_sheets_are_reported_with_their_line() {
+        for (content, line) in [
+            ("grid 1 0\n", 1),
+            ("grid 1 4\nanimation a 0-4 100\n", 2),
+            ("animation a 0-2 10 20\n", 1),
+            ("animation a 3-1\n", 1),
+            ("hitbox hexagon\n", 1),
+            ("animation a 0\nanimation a 0\n", 2),
+            ("\nscale 2\n", 2),
+        ] {
+            let error = SpriteSheet::parse(content, Path::new("test.sheet")).unwrap_err();
+            assert!(
+                matches!(error, AssetError::InvalidSpriteSheet { line: l, .. } if l == line),
+                "{content:?} gave {error}"
+            );
+        }
+    }
+}
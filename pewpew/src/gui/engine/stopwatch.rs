@@ -32,6 +32,10 @@ impl Stopwatch {
         }
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.last_start.is_none()
+    }
+
     pub fn elapsed_ms(&self) -> u128 {
         (self.before_start
             + match self.last_start {
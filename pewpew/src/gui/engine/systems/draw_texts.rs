@@ -1,37 +1,58 @@
-use crate::gui::engine::resources::Resources;
 use hecs::World;
-use sdl2::render::{TextureCreator, TextureQuery, WindowCanvas};
 use crate::gui::engine::components::text::Text;
+use crate::gui::engine::render::Renderer;
 
-pub fn run<'a>(
-    canvas: &mut WindowCanvas,
-    world: &mut World,
-    resources: &mut Resources<'a>,
-    texture_creator: &'a TextureCreator<sdl2::video::WindowContext>,
-) {
+pub fn run(renderer: &mut impl Renderer, world: &mut World) {
     for (_id, text) in world.query_mut::<&mut Text>() {
-        // apparently rendering text is expensive af, so we cache rendered text
-        let texture = resources.get_cached_or_render_text_with(text, |resources: &Resources| {
-            let surface = resources
-                .default_font
-                .render(text.text.as_str())
-                .blended(text.color)
-                .unwrap();
-            texture_creator
-                .create_texture_from_surface(&surface)
-                .unwrap()
-        });
-
-        let TextureQuery { width, height, .. } = texture.query();
+        let (width, height) = renderer.text_size(text);
         let scaled_width = (width as f32 * text.scale()) as u32;
         let scaled_height = (height as f32 * text.scale()) as u32;
 
-        canvas
-            .copy(
-                texture,
-                None,
-                text.position.align_rect(scaled_width, scaled_height),
+        renderer.draw_text(text, text.position.align_rect(scaled_width, scaled_height));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::components::point_with_alignment::{
+        HAlign, PointWithAlignment, VAlign,
+    };
+    use crate::gui::engine::components::{Point, text};
+    use crate::gui::engine::render::golden::assert_golden;
+    use crate::gui::engine::render::software::SoftwareRenderer;
+    use crate::gui::engine::systems::draw_texts;
+    use hecs::World;
+    use sdl2::pixels::Color;
+
+    #[test]
+    fn texts_are_scaled_aligned_and_colored() {
+        let mut renderer = SoftwareRenderer::new(160, 90);
+        let mut world = World::new();
+        let text = |content: &str, x, y, v_align, h_align| {
+            text::Builder::new(
+                content.to_string(),
+                PointWithAlignment {
+                    point: Point { x, y },
+                    v_align,
+                    h_align,
+                },
             )
-            .unwrap();
+        };
+
+        world.spawn((text("Score: 12", 0, 0, VAlign::Top, HAlign::Left)
+            .with_scale(1, 9)
+            .build(),));
+        world.spawn((text("Moorhuhn", 80, 45, VAlign::Center, HAlign::Center)
+            .with_color(Color::RED)
+            .with_scale(1, 5)
+            .build(),));
+        world.spawn((text("a b", 160, 90, VAlign::Bottom, HAlign::Right)
+            .with_color(Color::GREEN)
+            .with_scale(1, 6)
+            .build(),));
+
+        draw_texts::run(&mut renderer, &mut world);
+
+        assert_golden("draw_texts", renderer.frame());
     }
 }
@@ -1,10 +1,10 @@
 use crate::gui::engine::components::texture::Texture;
-use crate::gui::engine::resources::Resources;
+use crate::gui::engine::region_grid;
+use crate::gui::engine::render::{ImageCopy, Renderer};
 use hecs::World;
-use sdl2::rect::Rect;
-use sdl2::render::WindowCanvas;
+use sdl2::rect::{Point as SdlPoint, Rect};
 
-pub fn run(canvas: &mut WindowCanvas, world: &mut World, resources: &mut Resources) {
+pub fn run(renderer: &mut impl Renderer, world: &mut World) {
     let mut textures = Vec::new();
 
     for (_, pair) in world.query_mut::<(Option<&mut Texture>, Option<&mut Vec<Texture>>)>() {
@@ -21,23 +21,23 @@ pub fn run(canvas: &mut WindowCanvas, world: &mut World, resources: &mut Resourc
     }
 
     // textures with lower z_index must be drawn first
-    textures.sort_by(|texture1, texture2| texture1.z_index.cmp(&texture2.z_index));
+    textures.sort_by_key(|texture| texture.z_index);
 
     let viewport_rect = {
-        let (x, y) = canvas.output_size().unwrap();
+        let (x, y) = renderer.output_size();
         Rect::new(0, 0, x, y)
     };
 
     // draw normal textures
     for texture in textures {
-        let sprite = &resources.images[texture.image_id];
-        let query = sprite.query();
-        let tile_size = (query.width, query.height / texture.num_frames);
+        let (width, height) = renderer.image_size(texture.image);
+        let tile_size = (width / texture.grid.columns, height / texture.grid.rows);
 
-        // set the current frame by 'scrolling' vertically
+        // cut the cell of the current keyframe out of the sprite sheet
+        let (column, row) = texture.grid.position(texture.current_cell());
         let source_rect = Rect::new(
-            0,
-            (texture.current_keyframe * tile_size.1) as i32,
+            (column * tile_size.0) as i32,
+            (row * tile_size.1) as i32,
             tile_size.0,
             tile_size.1,
         );
@@ -48,27 +48,143 @@ pub fn run(canvas: &mut WindowCanvas, world: &mut World, resources: &mut Resourc
         );
 
         // trigger texture position events
-        let viewport_intersect = dest_rect.intersection(viewport_rect);
-        if let Some(viewport_intersect) = viewport_intersect {
-            if viewport_intersect != dest_rect
-                && let Some(event) = &mut texture.at_viewport_edge_event
-            {
+        if !region_grid::overlaps(dest_rect, viewport_rect) {
+            if let Some(event) = &mut texture.outside_viewport_event {
                 event.trigger();
             }
-        } else if let Some(event) = &mut texture.outside_viewport_event {
+        } else if !contains(viewport_rect, dest_rect)
+            && let Some(event) = &mut texture.at_viewport_edge_event
+        {
             event.trigger();
         }
 
-        canvas
-            .copy_ex(
-                sprite,
-                Some(source_rect),
-                Some(dest_rect),
-                texture.rotation_deg,
-                None,
-                texture.flip_horizontally,
-                texture.flip_vertically,
-            )
-            .unwrap();
+        renderer.draw_image(
+            texture.image,
+            &ImageCopy {
+                source: source_rect,
+                destination: dest_rect,
+                rotation_deg: texture.rotation_deg,
+                pivot: SdlPoint::new(
+                    (texture.pivot.0 * dest_rect.width() as f32) as i32,
+                    (texture.pivot.1 * dest_rect.height() as f32) as i32,
+                ),
+                flip_horizontally: texture.flip_horizontally,
+                flip_vertically: texture.flip_vertically,
+            },
+        );
+    }
+}
+
+fn contains(outer: Rect, inner: Rect) -> bool {
+    outer.left() <= inner.left()
+        && inner.right() <= outer.right()
+        && outer.top() <= inner.top()
+        && inner.bottom() <= outer.bottom()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::assets::TextureHandle;
+    use crate::gui::engine::components::Point;
+    use crate::gui::engine::components::point_with_alignment::{
+        HAlign, PointWithAlignment, VAlign,
+    };
+    use crate::gui::engine::components::texture;
+    use crate::gui::engine::components::texture::AnimationEndBehavior;
+    use crate::gui::engine::event::Event;
+    use crate::gui::engine::render::golden::assert_golden;
+    use crate::gui::engine::render::software::{Bitmap, SoftwareRenderer};
+    use crate::gui::engine::sprite_sheet::{Animation, AnimationFrame, Grid, Sprite};
+    use crate::gui::engine::systems::draw_textures;
+    use hecs::World;
+    use sdl2::pixels::Color;
+
+    /// a 2x2 sheet of 8x8 cells in red, green, blue and yellow, the top left corner of every
+    /// cell is white, so that flips and rotations show
+    fn sheet(image: TextureHandle, cell: u32) -> Sprite {
+        Sprite {
+            image,
+            grid: Grid {
+                columns: 2,
+                rows: 2,
+            },
+            pivot: (0.5, 0.5),
+            animation: Animation {
+                frames: (0..4)
+                    .map(|cell| AnimationFrame {
+                        cell,
+                        duration: None,
+                    })
+                    .cycle()
+                    .skip(cell as usize)
+                    .take(4)
+                    .collect(),
+                end_behavior: AnimationEndBehavior::Loop,
+            },
+        }
+    }
+
+    fn top_left(x: i32, y: i32) -> PointWithAlignment {
+        PointWithAlignment {
+            point: Point { x, y },
+            v_align: VAlign::Top,
+            h_align: HAlign::Left,
+        }
+    }
+
+    #[test]
+    fn cells_are_cut_scaled_flipped_rotated_and_blended() {
+        let mut renderer = SoftwareRenderer::new(48, 32);
+        let image = renderer.add_image(Bitmap::from_fn(16, 16, |x, y| {
+            if x % 8 < 2 && y % 8 < 2 {
+                return Color::WHITE;
+            }
+            match (x / 8, y / 8) {
+                (0, 0) => Color::RED,
+                (1, 0) => Color::GREEN,
+                (0, _) => Color::BLUE,
+                _ => Color::YELLOW,
+            }
+        }));
+        let glass = renderer.add_image(Bitmap::new(4, 4, Color::RGBA(255, 255, 255, 128)));
+
+        let mut at_edge = Event::default();
+        let mut outside = Event::default();
+        let mut world = World::new();
+        world.spawn((texture::Builder::animated(&sheet(image, 0), top_left(0, 0)).build(),));
+        world.spawn((
+            texture::Builder::animated(&sheet(image, 3), top_left(10, 0))
+                .with_scale(2.0)
+                .with_horizontal_flip()
+                .build(),
+        ));
+        world.spawn((
+            texture::Builder::animated(&sheet(image, 1), top_left(30, 4))
+                .with_scale(1.5)
+                .with_vertical_flip()
+                .with_rotation_deg(90.0)
+                .build(),
+        ));
+        // in front of the first cell, although it is spawned after it
+        world.spawn((texture::Builder::new(glass, top_left(4, 4))
+            .with_scale(3.0)
+            .with_z_index(1)
+            .build(),));
+        world.spawn((
+            texture::Builder::animated(&sheet(image, 2), top_left(44, 20))
+                .on_at_viewport_edge(at_edge.clone())
+                .build(),
+        ));
+        world.spawn((
+            texture::Builder::animated(&sheet(image, 0), top_left(60, 0))
+                .on_outside_viewport(outside.clone())
+                .build(),
+        ));
+
+        draw_textures::run(&mut renderer, &mut world);
+
+        assert_golden("draw_textures", renderer.frame());
+        assert_eq!(at_edge.consume_all(), 1);
+        assert_eq!(outside.consume_all(), 1);
     }
 }
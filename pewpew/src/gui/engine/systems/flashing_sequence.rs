@@ -1,12 +1,29 @@
 use crate::comm::message::{GuiToHitreg, HitregToGui};
+use crate::gui::engine::components::Point;
 use crate::gui::engine::components::hitbox::Hitbox;
-use crate::gui::engine::stopwatch::Stopwatch;
+use crate::gui::engine::components::occluder::Occluder;
+use crate::gui::engine::components::texture::Texture;
+use crate::gui::engine::event::Event;
+use crate::gui::engine::flash_mask::FlashMask;
+use crate::gui::engine::flash_style::FlashStyle;
+use crate::gui::engine::region_grid;
+use crate::gui::engine::region_grid::RegionGrid;
+use crate::gui::engine::render::DrawTarget;
 use crate::gui::gui_context::GuiContext;
+use crate::hitreg::capture;
+use crate::hitreg::decoder::num_frames_for;
+use crate::hitreg::grey_levels;
+use crate::hitreg::structured_light::{Axis, StripeLayout};
 use hecs::{Entity, World};
-use log::debug;
-use sdl2::pixels::Color;
-use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::{debug, warn};
+use sdl2::image::SaveSurface;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use sdl2::surface::Surface;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 fn usize_to_vec_bool(value: usize, max_idx: u32) -> Vec<bool> {
     let mut result = Vec::new();
@@ -19,120 +36,1034 @@ fn usize_to_vec_bool(value: usize, max_idx: u32) -> Vec<bool> {
     result
 }
 
-pub fn run(
-    gui_context: &mut GuiContext,
-    world: &mut World,
-    show_frames: bool,
-    game_time: &mut Stopwatch,
+/// how long to wait for hitreg after the last frame
+/// hitreg answers right after the last frame end, unless it is stuck or gone
+const HITREG_ANSWER_TIMEOUT: Duration = Duration::from_millis(1000);
+
+// a flashing sequence takes several frames and hitreg needs some time to answer,
+// blocking the gui thread for all of that would also block sdl events and the packets of the
+// other players
+// instead a FlashingSequence is advanced once per iteration of the game loop, and draws and
+// presents exactly one frame every time
+// the time the next frame becomes visible is sent to hitreg as the end of the frame before,
+// see PresentClock
+// when it is done, the finished event is triggered and the result can be taken
+
+#[derive(Debug)]
+pub enum HitregError {
+    /// hitreg could not decode the sequence, or did not answer in time
+    TimedOut,
+
+    /// the hitreg thread does not exist anymore
+    Disconnected,
+}
+
+/// what to find out about a shot
+#[derive(Debug, Clone, Copy)]
+pub enum Target {
+    /// which hitbox the sensortag points at
+    ///
+    /// with coarse_to_fine, first finds out which screen region the sensortag points at and then
+    /// only flashes the hitboxes in that region, if that takes fewer frames on average,
+    /// see gui::engine::region_grid
+    Hitbox { levels: u32, coarse_to_fine: bool },
+
+    /// the point the sensortag points at, by flashing gray coded stripes across the whole
+    /// viewport instead of hitboxes, see hitreg::structured_light
+    AimPoint { stripe_size: u32 },
+}
+
+#[derive(Clone, Copy)]
+pub enum Outcome {
+    /// the hitbox that was hit, its hit event is already triggered
+    Hit(Option<Entity>),
+
+    AimPoint(Option<Point>),
+}
+
+pub struct FlashingSequence {
     sensortag_id: u16,
-) -> Option<Entity> {
-    game_time.pause();
-    debug!(target: "Gui Thread", "starting flashing sequence");
-
-    let time_per_frame = Duration::from_millis(220);
-    let all_hitboxes = {
-        // sort hitboxes here in an extra scope
-        // this way, all_hitboxes does not need to be mutable
-        let mut tmp = world.query_mut::<&Hitbox>().into_iter().collect::<Vec<_>>();
-        tmp.sort_by(|(_, hitbox1), (_, hitbox2)| hitbox1.z_index.cmp(&hitbox2.z_index));
-        tmp
-    };
+    show_frames: bool,
+    occluders: Vec<(i32, Rect)>,
+    stage: Stage,
+    finished: Event,
+    result: Option<Result<Outcome, HitregError>>,
 
-    let num_frames = ((all_hitboxes.len() + 1) as f32).log2().ceil() as u32;
+    /// the bundle of this shot and the number of the next sequence in it, see hitreg::capture
+    capture: Option<(PathBuf, u32)>,
+}
 
-    let sequences = all_hitboxes
-        .iter()
-        .enumerate()
-        .map(|(i, (entity, _hitbox))| (*entity, usize_to_vec_bool(i + 1, num_frames)))
-        .collect::<Vec<_>>();
+enum Stage {
+    /// frames are on screen one after the other
+    Playing(Playback),
 
-    gui_context
-        .comm()
-        .send(GuiToHitreg::FlashingSequenceStart {
+    /// every frame was shown, hitreg is decoding
+    WaitingForAnswer {
+        pattern: Pattern,
+        since: Instant,
+    },
+
+    /// the hitbox that was hit is shown in the highlight color for one frame
+    Highlighting {
+        victim: Entity,
+        spans: Vec<Rect>,
+        until: Option<Instant>,
+    },
+
+    Done,
+}
+
+impl FlashingSequence {
+    /// sends the start of the sequence to hitreg, the first frame is shown by advance
+    ///
+    /// the finished event is triggered once the result is there, which may already be the case
+    /// when this returns
+    pub fn start(
+        gui_context: &mut GuiContext,
+        world: &mut World,
+        target: Target,
+        sensortag_id: u16,
+        show_frames: bool,
+        style: &FlashStyle,
+        finished: Event,
+    ) -> Self {
+        drop_late_answers(gui_context);
+
+        let mut sequence = FlashingSequence {
             sensortag_id,
-            num_frames,
-            sequences,
-        })
-        .unwrap();
+            show_frames,
+            occluders: occluder_layers(world),
+            stage: Stage::Done,
+            finished,
+            result: None,
+            capture: None,
+        };
+        if gui_context.capture_hitreg() {
+            sequence.capture = start_capture(gui_context, sensortag_id);
+        }
 
-    gui_context.canvas().set_draw_color(Color::BLACK);
-    gui_context.canvas().clear();
-    gui_context.canvas().present();
+        let playback = match target {
+            Target::Hitbox {
+                levels,
+                coarse_to_fine,
+            } => {
+                debug!(target: "Gui Thread", "starting flashing sequence");
+                sequence.hitbox_or_region_playback(
+                    gui_context,
+                    world,
+                    levels,
+                    coarse_to_fine,
+                    style,
+                )
+            }
+            Target::AimPoint { stripe_size } => {
+                debug!(target: "Gui Thread", "starting aim point sequence");
+                let (width, height) = gui_context.canvas().output_size().unwrap();
+                let layout = StripeLayout::new(width, height, stripe_size);
+                let mask =
+                    FlashMask::new(&hitbox_layers(&sorted_hitboxes(world)), &sequence.occluders);
+                sequence.playback(gui_context, Pattern::Stripes { layout, mask }, style)
+            }
+        };
+
+        match playback {
+            Ok(playback) => sequence.stage = Stage::Playing(playback),
+            Err(e) => sequence.finish(Err(e)),
+        }
+        sequence
+    }
 
-    thread::sleep(time_per_frame);
+    /// draws and presents the next frame of the sequence, or whatever the current stage shows,
+    /// must be called once per frame until the finished event was triggered
+    pub fn advance(&mut self, gui_context: &mut GuiContext, world: &mut World, style: &FlashStyle) {
+        let time_per_frame = style.time_per_frame(gui_context.flash_timing());
 
-    gui_context
-        .comm()
-        .send(GuiToHitreg::FlashBlackFrameEnd(SystemTime::now()))
-        .unwrap();
+        match std::mem::replace(&mut self.stage, Stage::Done) {
+            Stage::Playing(mut playback) => {
+                let frame_end = playback.show(gui_context, style, time_per_frame, self.show_frames);
+                if let Some(frame_end) = frame_end
+                    && gui_context.comm().send(frame_end).is_err()
+                {
+                    self.finish(Err(HitregError::Disconnected));
+                    return;
+                }
+
+                self.stage = if playback.is_over() {
+                    Stage::WaitingForAnswer {
+                        pattern: playback.pattern,
+                        since: Instant::now(),
+                    }
+                } else {
+                    Stage::Playing(playback)
+                };
+            }
+            Stage::WaitingForAnswer { pattern, since } => {
+                show_background(gui_context, style);
+
+                match gui_context.comm().try_recv_from_hitreg() {
+                    Some(answer) => self.answered(gui_context, world, pattern, answer, style),
+                    None if since.elapsed() < HITREG_ANSWER_TIMEOUT => {
+                        self.stage = Stage::WaitingForAnswer { pattern, since };
+                    }
+                    None => self.finish(Err(HitregError::TimedOut)),
+                }
+            }
+            Stage::Highlighting {
+                victim,
+                spans,
+                until,
+            } => {
+                style.draw_background(gui_context.canvas());
+                gui_context.canvas().set_draw_color(style.highlight());
+                gui_context.canvas().fill_rects(&spans).unwrap();
+                gui_context.present();
+
+                let until = until.unwrap_or_else(|| Instant::now() + time_per_frame);
+                if Instant::now() < until {
+                    self.stage = Stage::Highlighting {
+                        victim,
+                        spans,
+                        until: Some(until),
+                    };
+                } else {
+                    self.finish(Ok(Outcome::Hit(Some(victim))));
+                }
+            }
+            Stage::Done => {}
+        }
+    }
 
-    for frame in 0..num_frames {
-        let frame_start = SystemTime::now();
+    /// the result of the sequence, once the finished event was triggered
+    pub fn take_result(&mut self) -> Option<Result<Outcome, HitregError>> {
+        self.result.take()
+    }
 
-        gui_context.canvas().set_draw_color(Color::BLACK);
-        gui_context.canvas().clear();
+    fn finish(&mut self, result: Result<Outcome, HitregError>) {
+        self.stage = Stage::Done;
+        self.result = Some(result);
+        self.finished.trigger();
+    }
 
-        let frame_bitmask = 1_usize << frame;
+    fn answered(
+        &mut self,
+        gui_context: &mut GuiContext,
+        world: &mut World,
+        pattern: Pattern,
+        answer: HitregToGui,
+        style: &FlashStyle,
+    ) {
+        match (pattern, answer) {
+            (
+                Pattern::Hitboxes { .. } | Pattern::GreyHitboxes { .. },
+                HitregToGui::Result(victim),
+            ) => match victim.and_then(|victim| trigger_hit(world, victim)) {
+                Some((victim, spans)) => {
+                    self.stage = Stage::Highlighting {
+                        victim,
+                        spans,
+                        until: None,
+                    }
+                }
+                None => self.finish(Ok(Outcome::Hit(None))),
+            },
+            (
+                Pattern::Regions {
+                    grid,
+                    hitboxes,
+                    levels,
+                    ..
+                },
+                HitregToGui::Region(Some(region)),
+            ) => {
+                let region = grid.region(region);
+                let hitboxes_in_region = hitboxes
+                    .into_iter()
+                    .filter(|hitbox| region_grid::overlaps(hitbox.bounds(), region))
+                    .collect::<Vec<_>>();
 
-        for (index, (_entity_id, hitbox)) in all_hitboxes.iter().enumerate() {
-            if (index + 1) & frame_bitmask != 0 {
-                gui_context.canvas().set_draw_color(Color::WHITE);
-                gui_context
-                    .canvas()
-                    .fill_rect(hitbox.position.align_rect(hitbox.width, hitbox.height))
-                    .unwrap();
+                match self.hitbox_playback(gui_context, hitboxes_in_region, levels, style) {
+                    Ok(playback) => self.stage = Stage::Playing(playback),
+                    Err(e) => self.finish(Err(e)),
+                }
             }
-            if show_frames {
-                gui_context.canvas().set_draw_color(Color::CYAN);
-                gui_context
-                    .canvas()
-                    .draw_rect(hitbox.position.align_rect(hitbox.width, hitbox.height))
-                    .unwrap();
+            // the sensortag did not point at the screen at all
+            (Pattern::Regions { .. }, HitregToGui::Region(None)) => {
+                self.finish(Ok(Outcome::Hit(None)))
             }
+            (Pattern::Stripes { .. }, HitregToGui::AimPoint(aim_point)) => self.finish(Ok(
+                Outcome::AimPoint(aim_point.map(|(x, y)| Point { x, y })),
+            )),
+            (_, answer) => self.finish(Err(unexpected_answer(answer))),
         }
+    }
+
+    /// flashes the visible hitboxes, or the screen regions first if that is faster
+    fn hitbox_or_region_playback(
+        &mut self,
+        gui_context: &mut GuiContext,
+        world: &mut World,
+        levels: u32,
+        coarse_to_fine: bool,
+        style: &FlashStyle,
+    ) -> Result<Playback, HitregError> {
+        let all_hitboxes = {
+            // hitboxes that are completely hidden behind something do not need a code
+            let all_hitboxes = sorted_hitboxes(world);
+            let visible =
+                FlashMask::new(&hitbox_layers(&all_hitboxes), &self.occluders).visible_hitboxes();
+            all_hitboxes
+                .into_iter()
+                .zip(visible)
+                .filter_map(|(hitbox, visible)| visible.then_some(hitbox))
+                .collect::<Vec<_>>()
+        };
 
-        gui_context.canvas().present();
+        let grid = if coarse_to_fine {
+            let (width, height) = gui_context.canvas().output_size().unwrap();
+            let hitbox_rects = all_hitboxes
+                .iter()
+                .map(ShapedHitbox::bounds)
+                .collect::<Vec<_>>();
+            region_grid::plan(Rect::new(0, 0, width, height), &hitbox_rects, levels)
+        } else {
+            None
+        };
 
-        let last_frame_duration = SystemTime::now().duration_since(frame_start).unwrap();
-        let wait_duration = time_per_frame.saturating_sub(last_frame_duration);
+        match grid {
+            Some(grid) => {
+                debug!(target: "Gui Thread", "flashing {}x{} regions", grid.columns, grid.rows);
+                self.playback(
+                    gui_context,
+                    Pattern::Regions {
+                        grid,
+                        mask: FlashMask::new(&hitbox_layers(&all_hitboxes), &self.occluders),
+                        hitboxes: all_hitboxes,
+                        levels,
+                    },
+                    style,
+                )
+            }
+            None => self.hitbox_playback(gui_context, all_hitboxes, levels, style),
+        }
+    }
 
-        thread::sleep(wait_duration);
+    /// flashes every hitbox with its own code
+    fn hitbox_playback(
+        &mut self,
+        gui_context: &mut GuiContext,
+        hitboxes: Vec<ShapedHitbox>,
+        levels: u32,
+        style: &FlashStyle,
+    ) -> Result<Playback, HitregError> {
+        let mask = FlashMask::new(&hitbox_layers(&hitboxes), &self.occluders);
 
-        let now = SystemTime::now();
-        debug!(target: "Gui Thread", "flashing frame end at t={}", now.duration_since(UNIX_EPOCH).unwrap().as_millis());
+        // every frame shows one digit of the code as a grey level, see hitreg::grey_levels
+        let pattern = if levels > 2 && !hitboxes.is_empty() {
+            let num_frames = grey_levels::num_frames_for(hitboxes.len() as u32, levels);
+            let codes = (0..hitboxes.len())
+                .map(|i| grey_levels::digits(i as u32 + 1, levels, num_frames))
+                .collect();
+
+            Pattern::GreyHitboxes {
+                hitboxes,
+                mask,
+                codes,
+                levels,
+            }
+        } else {
+            Pattern::Hitboxes { hitboxes, mask }
+        };
+
+        self.playback(gui_context, pattern, style)
+    }
+
+    /// tells hitreg what is going to be flashed
+    fn playback(
+        &mut self,
+        gui_context: &mut GuiContext,
+        pattern: Pattern,
+        style: &FlashStyle,
+    ) -> Result<Playback, HitregError> {
+        let sensortag_id = self.sensortag_id;
+        let start = match &pattern {
+            Pattern::Hitboxes { hitboxes, .. } => {
+                let num_frames = num_frames_for(hitboxes.len() as u32);
+                GuiToHitreg::FlashingSequenceStart {
+                    sensortag_id,
+                    num_frames,
+                    sequences: hitboxes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, hitbox)| (hitbox.entity, usize_to_vec_bool(i + 1, num_frames)))
+                        .collect(),
+                }
+            }
+            Pattern::GreyHitboxes {
+                hitboxes,
+                codes,
+                levels,
+                ..
+            } => GuiToHitreg::GreyLevelSequenceStart {
+                sensortag_id,
+                levels: *levels,
+                num_frames: pattern.num_frames(),
+                sequences: hitboxes
+                    .iter()
+                    .map(|hitbox| hitbox.entity)
+                    .zip(codes.iter().cloned())
+                    .collect(),
+            },
+            Pattern::Regions { grid, .. } => GuiToHitreg::RegionSequenceStart {
+                sensortag_id,
+                num_regions: grid.num_regions(),
+            },
+            Pattern::Stripes { layout, .. } => GuiToHitreg::AimSequenceStart {
+                sensortag_id,
+                layout: *layout,
+            },
+        };
 
         gui_context
             .comm()
-            .send(GuiToHitreg::FlashFrameEnd(now))
-            .unwrap();
+            .send(start)
+            .map_err(|_| HitregError::Disconnected)?;
+
+        // with reduced contrast the white reference frame is always shown, see FlashStyle
+        let with_white_reference =
+            matches!(pattern, Pattern::GreyHitboxes { .. }) || style.needs_white_reference();
+
+        let capture = self.capture.as_mut().map(|(bundle, sequence)| {
+            *sequence += 1;
+            (bundle.clone(), *sequence - 1)
+        });
+
+        Ok(Playback::new(pattern, with_white_reference, capture))
+    }
+}
+
+/// creates the bundle for a shot and tells hitreg to save into it as well
+fn start_capture(gui_context: &mut GuiContext, sensortag_id: u16) -> Option<(PathBuf, u32)> {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let bundle =
+        Path::new(capture::CAPTURE_DIR).join(format!("{started}-sensortag-{sensortag_id}"));
+
+    if let Err(e) = fs::create_dir_all(&bundle) {
+        warn!(target: "Gui Thread", "failed to create capture bundle {}: {e}", bundle.display());
+        return None;
+    }
+    // if hitreg is gone, the start of the sequence fails right after this
+    let _ = gui_context
+        .comm()
+        .send(GuiToHitreg::CaptureInto(bundle.clone()));
+
+    debug!(target: "Gui Thread", "capturing shot into {}", bundle.display());
+    Some((bundle, 0))
+}
+
+/// reads back what was drawn so far and saves it as png
+fn save_frame(canvas: &WindowCanvas, path: &Path) -> Result<(), String> {
+    let (width, height) = canvas.output_size()?;
+    let mut pixels = canvas.read_pixels(None, PixelFormatEnum::RGB24)?;
+    let surface = Surface::from_data(
+        &mut pixels,
+        width,
+        height,
+        width * 3,
+        PixelFormatEnum::RGB24,
+    )?;
+    surface.save(path)
+}
+
+/// triggers the hit event of the victim, returns it with the spans to highlight
+fn trigger_hit(world: &mut World, victim: Entity) -> Option<(Entity, Vec<Rect>)> {
+    let Ok((hitbox, texture)) = world.query_one_mut::<(&mut Hitbox, Option<&Texture>)>(victim)
+    else {
+        warn!(target: "Gui Thread", "the hitbox that was hit does not exist anymore");
+        return None;
+    };
+
+    if let Some(event) = &mut hitbox.hit_event {
+        event.trigger();
+    }
+
+    Some((victim, hitbox.spans(texture)))
+}
+
+/// triggers the hit event of the hitbox that is visible at the point, like a flashing sequence
+/// does for the hitbox it decoded
+pub fn trigger_hit_at(world: &mut World, point: Point) -> Option<Entity> {
+    let occluders = occluder_layers(world);
+    let victim = {
+        let all_hitboxes = sorted_hitboxes(world);
+        let mask = FlashMask::new(&hitbox_layers(&all_hitboxes), &occluders);
+        all_hitboxes[mask.hitbox_at(point)?].entity
+    };
+
+    let hitbox = world.query_one_mut::<&mut Hitbox>(victim).unwrap();
+    if let Some(event) = &mut hitbox.hit_event {
+        event.trigger();
     }
 
-    gui_context.canvas().set_draw_color(Color::BLACK);
-    gui_context.canvas().clear();
-    gui_context.canvas().present();
+    Some(victim)
+}
+
+/// what the frames of one sequence show
+enum Pattern {
+    Hitboxes {
+        hitboxes: Vec<ShapedHitbox>,
+        mask: FlashMask,
+    },
 
-    // wait for answer from hitreg
-    if let HitregToGui::Result(Some(victim)) = gui_context.comm().recv_from_hitreg().unwrap() {
-        let hitbox = world.query_one_mut::<&mut Hitbox>(victim).unwrap();
+    GreyHitboxes {
+        hitboxes: Vec<ShapedHitbox>,
+        mask: FlashMask,
+        /// per hitbox, one digit per frame
+        codes: Vec<Vec<u8>>,
+        levels: u32,
+    },
+
+    /// first stage of the coarse to fine flashing sequence, the hitboxes are flashed in the
+    /// second stage
+    Regions {
+        grid: RegionGrid,
+        /// of all hitboxes, for styles that only flash the hitboxes
+        mask: FlashMask,
+        hitboxes: Vec<ShapedHitbox>,
+        levels: u32,
+    },
+
+    Stripes {
+        layout: StripeLayout,
+        /// for styles that only flash the hitboxes
+        mask: FlashMask,
+    },
+}
 
-        if let Some(event) = &mut hitbox.hit_event {
-            event.trigger();
+impl Pattern {
+    fn num_frames(&self) -> u32 {
+        match self {
+            Pattern::Hitboxes { hitboxes, .. } => num_frames_for(hitboxes.len() as u32),
+            Pattern::GreyHitboxes { codes, .. } => {
+                codes.first().map_or(0, |code| code.len() as u32)
+            }
+            Pattern::Regions { grid, .. } => grid.num_frames(),
+            Pattern::Stripes { layout, .. } => layout.num_frames(),
         }
+    }
 
-        gui_context.canvas().set_draw_color(Color::BLACK);
-        gui_context.canvas().clear();
-        gui_context.canvas().set_draw_color(Color::RED);
-        gui_context
-            .canvas()
-            .fill_rect(hitbox.position.align_rect(hitbox.width, hitbox.height))
-            .unwrap();
-        gui_context.canvas().present();
-        thread::sleep(time_per_frame);
-
-        game_time.resume();
-        Some(victim)
-    } else {
-        game_time.resume();
-        None
+    fn draw(
+        &self,
+        target: &mut impl DrawTarget,
+        style: &FlashStyle,
+        frame: Frame,
+        show_frames: bool,
+    ) {
+        match self {
+            Pattern::Hitboxes { hitboxes, mask } => {
+                mask.draw(target, style.color(false), |index| {
+                    style.color(frame.is_on(index + 1))
+                });
+                if show_frames {
+                    draw_outlines(target, hitboxes);
+                }
+            }
+            Pattern::GreyHitboxes {
+                hitboxes,
+                mask,
+                codes,
+                levels,
+            } => {
+                mask.draw(target, style.color(false), |index| match frame {
+                    Frame::BlackReference => style.color(false),
+                    Frame::WhiteReference => style.color(true),
+                    Frame::Code(frame) => style.grey(codes[index][frame as usize], *levels),
+                });
+                if show_frames {
+                    draw_outlines(target, hitboxes);
+                }
+            }
+            Pattern::Regions { grid, mask, .. } => {
+                let regions = (0..grid.num_regions())
+                    .map(|index| grid.region(index))
+                    .collect::<Vec<_>>();
+                let color_of = |index: usize| style.color(frame.is_on(index + 1));
+
+                if style.only_hitboxes {
+                    // the hitboxes show the code of the region they are in, a sensortag that
+                    // points next to them sees no region at all, which is a miss anyway
+                    mask.draw_in_areas(target, style.color(false), &regions, color_of);
+                } else {
+                    for (index, region) in regions.into_iter().enumerate() {
+                        target.set_draw_color(color_of(index));
+                        target.fill_rect(region);
+                    }
+                }
+            }
+            Pattern::Stripes { layout, mask } => {
+                let stripes = match frame {
+                    Frame::BlackReference => vec![],
+                    Frame::WhiteReference => {
+                        vec![(Rect::new(0, 0, layout.width, layout.height), true)]
+                    }
+                    Frame::Code(frame) => {
+                        let (axis, _) = layout.frame(frame);
+                        let stripe_size = layout.stripe_size;
+
+                        (0..layout.num_stripes(axis))
+                            .map(|stripe| {
+                                let offset = (stripe * stripe_size) as i32;
+                                let rect = match axis {
+                                    Axis::X => Rect::new(offset, 0, stripe_size, layout.height),
+                                    Axis::Y => Rect::new(0, offset, layout.width, stripe_size),
+                                };
+                                (rect, layout.is_white(frame, stripe))
+                            })
+                            .collect()
+                    }
+                };
+
+                if style.only_hitboxes {
+                    // the stripes only show on the hitboxes, a sensortag that points next to
+                    // them sees no stripes and finds no aim point, which is a miss anyway
+                    let areas = stripes.iter().map(|(rect, _)| *rect).collect::<Vec<_>>();
+                    mask.draw_in_areas(target, style.color(false), &areas, |index| {
+                        style.color(stripes[index].1)
+                    });
+                } else {
+                    target.set_draw_color(style.color(true));
+                    for (rect, white) in stripes {
+                        if white {
+                            target.fill_rect(rect);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// the frames of one sequence on their way to the screen
+struct Playback {
+    pattern: Pattern,
+
+    /// the black reference frame, the white reference frame if it is needed, and then every
+    /// code frame
+    frames: Vec<Frame>,
+    current: usize,
+
+    /// when the current frame was presented for the first time
+    shown_at: Option<Instant>,
+
+    /// bundle and number of this sequence in it, if the shot is captured
+    capture: Option<(PathBuf, u32)>,
+}
+
+impl Playback {
+    fn new(pattern: Pattern, with_white_reference: bool, capture: Option<(PathBuf, u32)>) -> Self {
+        let frames = frames(pattern.num_frames(), with_white_reference);
+        Playback {
+            pattern,
+            frames,
+            current: 0,
+            shown_at: None,
+            capture,
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.current == self.frames.len()
+    }
+
+    /// presents the current frame, or the next one if the current one was on screen long
+    /// enough, returns the message for hitreg if that ended a frame
+    fn show(
+        &mut self,
+        gui_context: &mut GuiContext,
+        style: &FlashStyle,
+        time_per_frame: Duration,
+        show_frames: bool,
+    ) -> Option<GuiToHitreg> {
+        let ended = match self.shown_at {
+            Some(shown_at) if shown_at.elapsed() >= time_per_frame => {
+                let ended = self.frames[self.current];
+                self.current += 1;
+                self.shown_at = None;
+                Some(ended)
+            }
+            _ => None,
+        };
+
+        style.draw_background(gui_context.canvas());
+        if let Some(frame) = self.frames.get(self.current) {
+            self.pattern
+                .draw(gui_context.canvas(), style, *frame, show_frames);
+
+            if self.shown_at.is_none()
+                && let Some((bundle, sequence)) = &self.capture
+            {
+                let path = capture::frame_file(bundle, *sequence, self.current);
+                if let Err(e) = save_frame(gui_context.canvas(), &path) {
+                    warn!(target: "Gui Thread", "failed to save flash frame to {}: {e}", path.display());
+                }
+            }
+        }
+        // the frame before ended when this one becomes visible
+        let visible_at = gui_context.present();
+        if self.shown_at.is_none() {
+            self.shown_at = Some(Instant::now());
+        }
+
+        ended.map(|frame| match frame {
+            Frame::BlackReference => GuiToHitreg::FlashBlackFrameEnd(visible_at),
+            Frame::WhiteReference => GuiToHitreg::FlashWhiteFrameEnd(visible_at),
+            Frame::Code(_) => {
+                debug!(target: "Gui Thread", "flashing frame end at t={}", visible_at.duration_since(UNIX_EPOCH).unwrap().as_millis());
+                GuiToHitreg::FlashFrameEnd(visible_at)
+            }
+        })
+    }
+}
+
+/// the order in which the frames of a sequence are shown
+fn frames(num_frames: u32, with_white_reference: bool) -> Vec<Frame> {
+    let mut frames = vec![Frame::BlackReference];
+    if with_white_reference {
+        frames.push(Frame::WhiteReference);
+    }
+    frames.extend((0..num_frames).map(Frame::Code));
+    frames
+}
+
+fn show_background(gui_context: &mut GuiContext, style: &FlashStyle) {
+    style.draw_background(gui_context.canvas());
+    gui_context.present();
+}
+
+fn draw_outlines(target: &mut impl DrawTarget, hitboxes: &[ShapedHitbox]) {
+    target.set_draw_color(Color::CYAN);
+    for hitbox in hitboxes {
+        target.draw_rects(&hitbox.spans);
+    }
+}
+
+/// a hitbox together with its shape as it is on screen right now
+struct ShapedHitbox {
+    entity: Entity,
+    z_index: i32,
+    spans: Vec<Rect>,
+}
+
+impl ShapedHitbox {
+    /// smallest rect around the shape
+    fn bounds(&self) -> Rect {
+        let (left, top, right, bottom) = self.spans.iter().fold(
+            (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+            |(left, top, right, bottom), span| {
+                (
+                    left.min(span.left()),
+                    top.min(span.top()),
+                    right.max(span.right()),
+                    bottom.max(span.bottom()),
+                )
+            },
+        );
+
+        if self.spans.is_empty() {
+            Rect::new(0, 0, 0, 0)
+        } else {
+            Rect::new(left, top, (right - left) as u32, (bottom - top) as u32)
+        }
+    }
+}
+
+/// all hitboxes, back to front
+fn sorted_hitboxes(world: &mut World) -> Vec<ShapedHitbox> {
+    let mut hitboxes = world
+        .query_mut::<(&Hitbox, Option<&Texture>)>()
+        .into_iter()
+        .map(|(entity, (hitbox, texture))| ShapedHitbox {
+            entity,
+            z_index: hitbox.z_index,
+            spans: hitbox.spans(texture),
+        })
+        .collect::<Vec<_>>();
+    hitboxes.sort_by_key(|hitbox| hitbox.z_index);
+    hitboxes
+}
+
+fn hitbox_layers(hitboxes: &[ShapedHitbox]) -> Vec<(i32, Vec<Rect>)> {
+    hitboxes
+        .iter()
+        .map(|hitbox| (hitbox.z_index, hitbox.spans.clone()))
+        .collect()
+}
+
+fn occluder_layers(world: &mut World) -> Vec<(i32, Rect)> {
+    let mut occluders = Vec::new();
+
+    for (_, pair) in world.query_mut::<(Option<&Occluder>, Option<&Vec<Occluder>>)>() {
+        match pair {
+            (Some(_), Some(_)) => panic!("do not use Occluder and Vec<Occluder> in one entity"),
+            (Some(occluder), None) => occluders.push(occluder),
+            (None, Some(vec)) => occluders.extend(vec),
+            (None, None) => {}
+        }
+    }
+
+    occluders
+        .into_iter()
+        .map(|occluder| {
+            (
+                occluder.z_index,
+                occluder
+                    .position
+                    .align_rect(occluder.width, occluder.height),
+            )
+        })
+        .collect()
+}
+
+/// which frame of a flashing sequence is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    /// everything dark, hitreg compares the other frames to it
+    BlackReference,
+
+    /// everything bright, for grey levels and for flashing at reduced contrast
+    WhiteReference,
+
+    /// frame i of the codes
+    Code(u32),
+}
+
+impl Frame {
+    /// whether something with this code is bright in a black and white sequence
+    fn is_on(self, code: usize) -> bool {
+        match self {
+            Frame::BlackReference => false,
+            Frame::WhiteReference => true,
+            Frame::Code(frame) => code & (1 << frame) != 0,
+        }
+    }
+}
+
+/// an answer to an earlier sequence that came in after we stopped waiting for it
+/// must not be mistaken for the answer to the next one
+fn drop_late_answers(gui_context: &mut GuiContext) {
+    while let Some(late) = gui_context.comm().try_recv_from_hitreg() {
+        warn!(target: "Gui Thread", "dropping late answer from hitreg: {late:?}");
+    }
+}
+
+/// warns about answers that do not fit the sequence, a TimedOut answer was already logged
+/// by hitreg
+fn unexpected_answer(answer: HitregToGui) -> HitregError {
+    if answer != HitregToGui::TimedOut {
+        warn!(target: "Gui Thread", "hitreg answered with something that does not fit the sequence: {answer:?}");
+    }
+    HitregError::TimedOut
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::flash_mask::FlashMask;
+    use crate::gui::engine::flash_style::{FlashStyle, dim_backdrop};
+    use crate::gui::engine::region_grid::RegionGrid;
+    use crate::gui::engine::render::DrawTarget;
+    use crate::gui::engine::render::golden::assert_golden;
+    use crate::gui::engine::render::software::{Bitmap, SoftwareRenderer};
+    use crate::gui::engine::systems::flashing_sequence::{
+        Frame, Pattern, ShapedHitbox, frames, hitbox_layers,
+    };
+    use crate::hitreg::structured_light::{Axis, StripeLayout};
+    use hecs::Entity;
+    use sdl2::pixels::Color;
+    use sdl2::rect::Rect;
+
+    /// a frame as Playback::show draws it, without backdrop
+    fn draw(pattern: &Pattern, style: &FlashStyle, frame: Frame, show_frames: bool) -> Bitmap {
+        let mut renderer = SoftwareRenderer::new(64, 48);
+        renderer.set_draw_color(style.color(false));
+        renderer.clear();
+        pattern.draw(&mut renderer, style, frame, show_frames);
+        renderer.frame().clone()
+    }
+
+    /// a frame as Playback::show draws it with a backdrop, sky and grass stand in for the scene,
+    /// None draws only the dimmed backdrop
+    fn draw_on_backdrop(pattern: &Pattern, style: &FlashStyle, frame: Option<Frame>) -> Bitmap {
+        let mut renderer = SoftwareRenderer::new(64, 48);
+        renderer.set_draw_color(Color::RGB(90, 160, 230));
+        renderer.clear();
+        renderer.set_draw_color(Color::RGB(60, 180, 60));
+        renderer.fill_rect(Rect::new(0, 36, 64, 12));
+        dim_backdrop(&mut renderer);
+
+        if let Some(frame) = frame {
+            pattern.draw(&mut renderer, style, frame, false);
+        }
+        renderer.frame().clone()
+    }
+
+    /// fails the test if `flashed` changed the dimmed scene somewhere else than on the hitboxes
+    fn assert_only_hitboxes_flash(flashed: &Bitmap, backdrop: &Bitmap) {
+        // next to the hitboxes and in the sky and the grass, the dimmed scene is untouched
+        for (x, y) in [(60, 2), (60, 44), (2, 44)] {
+            assert_eq!(flashed.pixel(x, y), backdrop.pixel(x, y));
+        }
+    }
+
+    /// a square in front of a tree and an L shape partly behind it
+    fn hitboxes() -> (Vec<ShapedHitbox>, FlashMask) {
+        let hitboxes = vec![
+            ShapedHitbox {
+                entity: Entity::DANGLING,
+                z_index: 0,
+                spans: vec![Rect::new(4, 4, 24, 8), Rect::new(4, 12, 8, 20)],
+            },
+            ShapedHitbox {
+                entity: Entity::DANGLING,
+                z_index: 0,
+                spans: vec![Rect::new(36, 8, 20, 20)],
+            },
+            ShapedHitbox {
+                entity: Entity::DANGLING,
+                z_index: 2,
+                spans: vec![Rect::new(16, 30, 12, 12)],
+            },
+        ];
+        let mask = FlashMask::new(&hitbox_layers(&hitboxes), &[(1, Rect::new(20, 0, 8, 48))]);
+        (hitboxes, mask)
+    }
+
+    #[test]
+    fn references_come_before_the_codes() {
+        assert_eq!(
+            frames(2, false),
+            vec![Frame::BlackReference, Frame::Code(0), Frame::Code(1)]
+        );
+        assert_eq!(
+            frames(1, true),
+            vec![Frame::BlackReference, Frame::WhiteReference, Frame::Code(0)]
+        );
+    }
+
+    #[test]
+    fn hitbox_frames() {
+        let (hitboxes, mask) = hitboxes();
+        let pattern = Pattern::Hitboxes { hitboxes, mask };
+        let style = FlashStyle::default();
+
+        assert_eq!(pattern.num_frames(), 2);
+        for frame in 0..2 {
+            assert_golden(
+                &format!("flash_hitboxes_code_{frame}"),
+                &draw(&pattern, &style, Frame::Code(frame), false),
+            );
+        }
+        assert_golden(
+            "flash_hitboxes_outlined",
+            &draw(&pattern, &style, Frame::Code(0), true),
+        );
+    }
+
+    #[test]
+    fn grey_hitbox_frames_in_the_safe_style() {
+        let (hitboxes, mask) = hitboxes();
+        let pattern = Pattern::GreyHitboxes {
+            hitboxes,
+            mask,
+            codes: vec![vec![0], vec![1], vec![2]],
+            levels: 3,
+        };
+        let style = FlashStyle::safe(None);
+
+        assert_golden(
+            "flash_grey_hitboxes_white_reference",
+            &draw(&pattern, &style, Frame::WhiteReference, false),
+        );
+        assert_golden(
+            "flash_grey_hitboxes_code_0",
+            &draw(&pattern, &style, Frame::Code(0), false),
+        );
+    }
+
+    #[test]
+    fn region_frames() {
+        let (hitboxes, mask) = hitboxes();
+        let pattern = Pattern::Regions {
+            grid: RegionGrid::new(Rect::new(0, 0, 64, 48), 4, 3),
+            mask,
+            hitboxes,
+            levels: 2,
+        };
+        let style = FlashStyle::default();
+
+        for frame in 0..pattern.num_frames() {
+            assert_golden(
+                &format!("flash_regions_code_{frame}"),
+                &draw(&pattern, &style, Frame::Code(frame), false),
+            );
+        }
+    }
+
+    #[test]
+    fn region_frames_in_the_safe_style_only_flash_the_hitboxes() {
+        let (hitboxes, mask) = hitboxes();
+        let pattern = Pattern::Regions {
+            grid: RegionGrid::new(Rect::new(0, 0, 64, 48), 4, 3),
+            mask,
+            hitboxes,
+            levels: 2,
+        };
+        let style = FlashStyle::safe(None);
+        let backdrop = draw_on_backdrop(&pattern, &style, None);
+
+        for (name, frame) in [
+            ("white_reference", Frame::WhiteReference),
+            ("code_0", Frame::Code(0)),
+        ] {
+            let flashed = draw_on_backdrop(&pattern, &style, Some(frame));
+            assert_golden(&format!("flash_safe_regions_{name}"), &flashed);
+            assert_only_hitboxes_flash(&flashed, &backdrop);
+        }
+    }
+
+    #[test]
+    fn stripe_frames() {
+        let layout = StripeLayout::new(64, 48, 8);
+        let (_, mask) = hitboxes();
+        let pattern = Pattern::Stripes { layout, mask };
+        let style = FlashStyle::default();
+
+        // the first and the last frame of the vertical stripes and of the horizontal ones
+        let first_y_frame = layout.num_frames() - layout.num_frames_for(Axis::Y);
+        for frame in [0, first_y_frame - 1, first_y_frame, layout.num_frames() - 1] {
+            assert_golden(
+                &format!("flash_stripes_code_{frame}"),
+                &draw(&pattern, &style, Frame::Code(frame), false),
+            );
+        }
+    }
+
+    #[test]
+    fn stripe_frames_in_the_safe_style_only_flash_the_hitboxes() {
+        let layout = StripeLayout::new(64, 48, 8);
+        let (_, mask) = hitboxes();
+        let pattern = Pattern::Stripes { layout, mask };
+        let style = FlashStyle::safe(None);
+        let backdrop = draw_on_backdrop(&pattern, &style, None);
+
+        for (name, frame) in [
+            ("white_reference", Frame::WhiteReference),
+            ("code_0", Frame::Code(0)),
+        ] {
+            let flashed = draw_on_backdrop(&pattern, &style, Some(frame));
+            assert_golden(&format!("flash_safe_stripes_{name}"), &flashed);
+            assert_only_hitboxes_flash(&flashed, &backdrop);
+        }
     }
 }
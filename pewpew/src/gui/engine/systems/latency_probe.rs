@@ -0,0 +1,152 @@
+use crate::comm::message::{GuiToHitreg, HitregToGui};
+use crate::gui::engine::gui_context::GuiContext;
+use crate::gui::engine::systems::flashing_sequence::HitregError;
+use crate::hitreg::latency_probe::PROBE_WINDOW;
+use log::{debug, warn};
+use std::time::{Duration, Instant};
+
+/// every round measures one change from dark to bright and one back
+const ROUNDS: u32 = 3;
+
+/// time for the light sensor to settle after the previous change
+const SETTLE_TIME: Duration = Duration::from_millis(700);
+
+// flips the whole screen between dark and bright (in the colors of the flash style) and
+// measures how long the sensortags take to report every change, see hitreg::latency_probe
+// that takes several seconds, so the probe does not block the gui thread, it is advanced once per
+// frame of the calibration scene and says whether the screen has to be bright in that frame
+// the probe for a change is sent to hitreg once the frame with the change was presented
+
+pub struct LatencyProbe {
+    sensortag_ids: Vec<u16>,
+    stage: Stage,
+
+    /// whether the screen has to be bright
+    on: bool,
+    changes_left: u32,
+    slowest: Duration,
+    result: Option<Result<Option<Duration>, HitregError>>,
+}
+
+enum Stage {
+    /// the screen stays as it is until the light sensors settled
+    Settling {
+        until: Instant,
+    },
+
+    /// the screen changes with the next present
+    Changing,
+
+    /// the probe was sent, hitreg answers once every sensortag reacted or the window is over
+    WaitingForAnswer {
+        deadline: Instant,
+    },
+
+    Done,
+}
+
+impl LatencyProbe {
+    /// starts on a dark screen, the first change comes once the sensors settled
+    pub fn start(gui_context: &mut GuiContext, sensortag_ids: Vec<u16>) -> Self {
+        while let Some(late) = gui_context.comm().try_recv_from_hitreg() {
+            warn!(target: "Gui Thread", "dropping late answer from hitreg: {late:?}");
+        }
+
+        LatencyProbe {
+            sensortag_ids,
+            stage: Stage::Settling {
+                until: Instant::now() + SETTLE_TIME,
+            },
+            on: false,
+            changes_left: ROUNDS * 2,
+            slowest: Duration::ZERO,
+            result: None,
+        }
+    }
+
+    /// whether the screen has to be bright in this frame
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.stage, Stage::Done)
+    }
+
+    /// the slowest latency of all sensortags, or None if a sensortag did not react
+    /// to every change, there once the probe is done
+    pub fn take_result(&mut self) -> Option<Result<Option<Duration>, HitregError>> {
+        self.result.take()
+    }
+
+    /// must be called once per frame, before the frame is drawn
+    pub fn advance(&mut self, gui_context: &mut GuiContext) {
+        match self.stage {
+            Stage::Settling { until } if Instant::now() >= until => {
+                if self.changes_left == 0 {
+                    self.finish(Ok(Some(self.slowest)));
+                } else {
+                    self.on = !self.on;
+                    self.changes_left -= 1;
+                    self.stage = Stage::Changing;
+                }
+            }
+            Stage::WaitingForAnswer { deadline } => {
+                match gui_context.comm().try_recv_from_hitreg() {
+                    Some(HitregToGui::ProbeLatencies(latencies)) => self.measured(latencies),
+                    Some(answer) => {
+                        warn!(target: "Gui Thread", "hitreg answered with something that does not fit the latency probe: {answer:?}");
+                        self.finish(Err(HitregError::TimedOut));
+                    }
+                    None if Instant::now() >= deadline => self.finish(Err(HitregError::TimedOut)),
+                    None => {}
+                }
+            }
+            Stage::Settling { .. } | Stage::Changing | Stage::Done => {}
+        }
+    }
+
+    /// must be called right after every present, a change is only measured from the moment
+    /// it is on the screen
+    pub fn presented(&mut self, gui_context: &mut GuiContext) {
+        if !matches!(self.stage, Stage::Changing) {
+            return;
+        }
+
+        let sent = gui_context.comm().send(GuiToHitreg::LatencyProbe {
+            sensortag_ids: self.sensortag_ids.clone(),
+        });
+        match sent {
+            Ok(()) => {
+                self.stage = Stage::WaitingForAnswer {
+                    deadline: Instant::now() + PROBE_WINDOW * 2,
+                }
+            }
+            Err(_) => self.finish(Err(HitregError::Disconnected)),
+        }
+    }
+
+    fn measured(&mut self, latencies: Vec<(u16, Option<Duration>)>) {
+        debug!(target: "Gui Thread", "latency probe: {latencies:?}");
+
+        for (sensortag_id, latency) in latencies {
+            match latency {
+                Some(latency) => self.slowest = self.slowest.max(latency),
+                None => {
+                    warn!(target: "Gui Thread", "sensortag {sensortag_id} did not see the screen change");
+                    self.finish(Ok(None));
+                    return;
+                }
+            }
+        }
+
+        self.stage = Stage::Settling {
+            until: Instant::now() + SETTLE_TIME,
+        };
+    }
+
+    fn finish(&mut self, result: Result<Option<Duration>, HitregError>) {
+        self.result = Some(result);
+        self.stage = Stage::Done;
+    }
+}
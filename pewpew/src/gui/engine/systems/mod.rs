@@ -1,5 +1,7 @@
 pub mod draw_textures;
 pub mod flashing_sequence;
+pub mod latency_probe;
+pub mod play_sounds;
 pub mod update_animated_textures;
 pub mod update_movements;
 pub mod draw_texts;
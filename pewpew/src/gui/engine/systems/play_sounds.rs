@@ -0,0 +1,42 @@
+use crate::gui::engine::audio::Audio;
+use crate::gui::engine::components::play_sound::PlaySound;
+use hecs::World;
+
+pub fn run(world: &mut World, audio: &mut Audio) {
+    let played = world
+        .query_mut::<&PlaySound>()
+        .into_iter()
+        .map(|(entity, play_sound)| {
+            audio.play(play_sound.sound, play_sound.volume);
+            entity
+        })
+        .collect::<Vec<_>>();
+
+    for entity in played {
+        world.despawn(entity).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::assets::Manifest;
+    use crate::gui::engine::audio::Audio;
+    use crate::gui::engine::components::play_sound::PlaySound;
+    use crate::gui::engine::systems::play_sounds;
+    use hecs::World;
+    use std::path::Path;
+
+    #[test]
+    fn sounds_are_played_once() {
+        let manifest = Manifest::parse("sound shot_1 shot.mp3\n", Path::new("res")).unwrap();
+        let mut audio = Audio::silent(manifest);
+        let shot = audio.sound("shot_1").unwrap();
+
+        let mut world = World::new();
+        world.spawn((PlaySound::new(shot),));
+        world.spawn((PlaySound::new(shot).with_volume(0.5),));
+        play_sounds::run(&mut world, &mut audio);
+
+        assert_eq!(world.len(), 0);
+    }
+}
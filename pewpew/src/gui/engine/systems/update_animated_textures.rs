@@ -10,10 +10,8 @@ pub fn run(world: &mut World, game_time: &mut Stopwatch) {
         match texture.next_keyframe_switch_at_elapsed_game_time {
             Some(next_keyframe_switch_time) if next_keyframe_switch_time <= game_elapsed => {
                 texture.current_keyframe += 1;
-                texture.next_keyframe_switch_at_elapsed_game_time =
-                    Some(next_keyframe_switch_time + texture.keyframe_duration.as_millis());
 
-                if texture.current_keyframe == texture.num_frames
+                if texture.current_keyframe == texture.num_keyframes()
                     && let Some(event) = &mut texture.animation_end_event
                 {
                     event.trigger();
@@ -21,21 +19,31 @@ pub fn run(world: &mut World, game_time: &mut Stopwatch) {
 
                 match texture.animation_end_behavior {
                     AnimationEndBehavior::Freeze
-                        if texture.current_keyframe == texture.num_frames =>
+                        if texture.current_keyframe == texture.num_keyframes() =>
                     {
                         texture.current_keyframe -= 1;
                     }
                     AnimationEndBehavior::Loop => {
-                        texture.current_keyframe %= texture.num_frames;
+                        texture.current_keyframe %= texture.num_keyframes();
                     }
                     _ => {}
                 }
+
+                texture.next_keyframe_switch_at_elapsed_game_time =
+                    Some(switch_time(texture, next_keyframe_switch_time));
             }
             None => {
                 texture.next_keyframe_switch_at_elapsed_game_time =
-                    Some(game_elapsed + texture.keyframe_duration.as_millis())
+                    Some(switch_time(texture, game_elapsed))
             }
             _ => {}
         }
     }
 }
+
+/// when the current keyframe of `texture`, shown from `shown_at`, is over
+fn switch_time(texture: &Texture, shown_at: u128) -> u128 {
+    texture
+        .keyframe_duration()
+        .map_or(u128::MAX, |duration| shown_at + duration.as_millis())
+}
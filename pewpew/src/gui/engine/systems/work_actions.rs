@@ -37,9 +37,9 @@ pub fn run(world: &mut World) {
     // handle all entities with a single action
     for (entity_id, n, action) in entities_with_action {
         {
-            let mut locked_action = action.lock().unwrap();
+            let mut borrowed_action = action.borrow_mut();
             for _ in 0..n {
-                locked_action(entity_id, world);
+                borrowed_action(entity_id, world);
             }
         }
 
@@ -52,9 +52,9 @@ pub fn run(world: &mut World) {
     // handle all entities with a vector of actions
     for (entity_id, idx, n, action) in entities_with_action_vec {
         {
-            let mut locked_action = action.lock().unwrap();
+            let mut borrowed_action = action.borrow_mut();
             for _ in 0..n {
-                locked_action(entity_id, world);
+                borrowed_action(entity_id, world);
             }
         }
 
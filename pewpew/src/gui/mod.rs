@@ -3,51 +3,43 @@ mod scenes;
 
 use crate::common::cancel_token::CancelToken;
 use crate::comm::gui::GuiComm;
+use crate::gui::engine::assets::{Assets, MANIFEST_FILE, Manifest};
+use crate::gui::engine::audio::Audio;
 use crate::gui::engine::gui_context;
 use crate::gui::engine::gui_context::GuiContext;
-use sdl2::mixer::{InitFlag, AUDIO_S16LSB, DEFAULT_CHANNELS};
+use crate::gui::engine::resources::Resources;
+use crate::gui::scenes::photosensitivity_warning::PhotosensitivityWarning;
+use crate::gui::scenes::scene::SceneManager;
+use crate::user_settings;
+use log::error;
+use std::path::Path;
 
 pub fn run(comm: GuiComm, cancel_token: CancelToken) {
     let mut gui_context = GuiContext::new(gui_context::Settings::default(), cancel_token, comm);
 
-    /*
-    let sdl = sdl2::init().unwrap();
-    let _audio = sdl.audio().unwrap();
-    // Initialize mixer with MP3 support
-    sdl2::mixer::init(InitFlag::MP3).unwrap();
-    sdl2::mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 1024).unwrap();
-
-    // Load and play mp3
-    let mut chunk = Chunk::from_file("res/glock19-18535.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&chunk, 0).unwrap();
-    thread::sleep(Duration::from_secs(2));
-    chunk = Chunk::from_file("res/gun-shot-359196.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&chunk, 0).unwrap();
-    thread::sleep(Duration::from_secs(2));
-    chunk = Chunk::from_file("res/ak47_boltpull.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&chunk, 0).unwrap();
-    thread::sleep(Duration::from_secs(2));
-    chunk = Chunk::from_file("res/glock-reload.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&chunk, 0).unwrap();
-    thread::sleep(Duration::from_secs(2));
-    chunk = Chunk::from_file("res/ahhhh.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&chunk, 0).unwrap();
-    thread::sleep(Duration::from_secs(2));
-    chunk = Chunk::from_file("res/wilhelm_scream.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&chunk, 0).unwrap();
-    thread::sleep(Duration::from_secs(2));
-*/
-
-    let _audio = gui_context.sdl().audio().unwrap();
-
-    // Initialize mixer with MP3 support
-    sdl2::mixer::init(InitFlag::MP3).unwrap();
-    sdl2::mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 1024).unwrap();
-
-    scenes::intro::run(&mut gui_context);
-    loop {
-        let player_datas = scenes::pregame::run(&mut gui_context);
-        let player_datas = scenes::game::run(&mut gui_context, player_datas);
-        scenes::scoreboard::run(&mut gui_context, player_datas);
+    // warning, intro, then pregame, game and scoreboard over and over, every scene says which
+    // one comes next, see scenes::scene
+    {
+        let texture_creator = gui_context.canvas().texture_creator();
+        let ttf_context = sdl2::ttf::init().unwrap();
+        // without a sound device, the audio stays silent and the game goes on
+        let resources = Manifest::load(Path::new(MANIFEST_FILE)).and_then(|manifest| {
+            let audio = Audio::open(gui_context.sdl(), manifest.clone(), user_settings::VOLUME);
+            Resources::new(Assets::new(manifest, &texture_creator, &ttf_context), audio)
+        });
+        match resources {
+            Ok(mut resources) => SceneManager::new(&mut gui_context, &texture_creator).run(
+                &mut gui_context,
+                &mut resources,
+                Box::new(PhotosensitivityWarning::default()),
+            ),
+            Err(e) => {
+                error!(target: "Gui Thread", "{e}");
+                gui_context.cancel_token().cancel();
+            }
+        }
     }
+
+    // dropping the context closes the window, which gives the display back to the desktop
+    drop(gui_context);
 }
@@ -0,0 +1,143 @@
+use crate::gui::engine::assets::AssetError;
+use crate::gui::engine::flash_timing::FlashTiming;
+use crate::gui::engine::gui_context::GuiContext;
+use crate::gui::engine::systems::flashing_sequence::HitregError;
+use crate::gui::engine::systems::latency_probe::LatencyProbe;
+use crate::gui::scenes::common::PlayerData;
+use crate::gui::scenes::common::message::Message;
+use crate::gui::scenes::game::Game;
+use crate::gui::scenes::scene::{Scene, SceneContext, Transition};
+use log::{error, info, warn};
+use sdl2::event::Event;
+use std::time::{Duration, Instant};
+
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+/// measures how long the flashing sequence has to show every frame with the guns of all players,
+/// and stores the result for the next start, then the game starts
+pub struct Calibration {
+    player_datas: Vec<PlayerData>,
+
+    /// set when the scene is entered
+    stage: Option<Stage>,
+}
+
+enum Stage {
+    /// the players point their guns at the screen
+    Preparing {
+        message: Message,
+        until: Instant,
+    },
+
+    /// the screen changes between dark and bright, see LatencyProbe
+    Probing(LatencyProbe),
+
+    ShowingResult {
+        message: Message,
+        until: Instant,
+    },
+}
+
+impl Calibration {
+    pub fn new(player_datas: Vec<PlayerData>) -> Self {
+        Calibration {
+            player_datas,
+            stage: None,
+        }
+    }
+}
+
+impl<'sdl> Scene<'sdl> for Calibration {
+    fn enter(&mut self, ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        self.stage = Some(Stage::Preparing {
+            message: Message::new(&["Point all guns at the screen!"], ctx.viewport),
+            until: Instant::now() + MESSAGE_DURATION,
+        });
+        Ok(())
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext<'_, 'sdl>, _events: &[Event]) -> Transition<'sdl> {
+        let gui_context = &mut *ctx.gui_context;
+        let now = Instant::now();
+
+        let next = match self.stage.as_mut().unwrap() {
+            Stage::Preparing { until, .. } if now >= *until => {
+                let sensortag_ids = self
+                    .player_datas
+                    .iter()
+                    .map(|data| data.sensortag_id)
+                    .collect::<Vec<_>>();
+                Some(Stage::Probing(LatencyProbe::start(
+                    gui_context,
+                    sensortag_ids,
+                )))
+            }
+            Stage::Probing(probe) => {
+                probe.advance(gui_context);
+                probe.is_done().then(|| {
+                    let result = apply(gui_context, probe.take_result().unwrap());
+                    Stage::ShowingResult {
+                        message: Message::new(&[&result], ctx.viewport),
+                        until: now + MESSAGE_DURATION,
+                    }
+                })
+            }
+            Stage::ShowingResult { until, .. } if now >= *until => {
+                let player_datas = std::mem::take(&mut self.player_datas);
+                return Transition::Switch(Box::new(Game::new(player_datas)));
+            }
+            Stage::Preparing { .. } | Stage::ShowingResult { .. } => None,
+        };
+
+        if next.is_some() {
+            self.stage = next;
+        }
+        Transition::Stay
+    }
+
+    fn draw(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        match self.stage.as_mut().unwrap() {
+            Stage::Preparing { message, .. } | Stage::ShowingResult { message, .. } => {
+                message.draw(ctx)
+            }
+            Stage::Probing(probe) => {
+                let color = ctx.gui_context.flash_style().color(probe.is_on());
+                ctx.gui_context.canvas().set_draw_color(color);
+                ctx.gui_context.canvas().clear();
+            }
+        }
+    }
+
+    fn presented(&mut self, ctx: &mut SceneContext<'_, 'sdl>, _at: Instant) {
+        if let Some(Stage::Probing(probe)) = &mut self.stage {
+            probe.presented(ctx.gui_context);
+        }
+    }
+}
+
+/// uses the measured latency from now on, returns what to tell the players
+fn apply(gui_context: &mut GuiContext, result: Result<Option<Duration>, HitregError>) -> String {
+    match result {
+        Ok(Some(measured_latency)) => {
+            let flash_timing = FlashTiming {
+                measured_latency: Some(measured_latency),
+            };
+            info!(target: "Gui Thread", "measured flash latency of {}ms, using {}ms per frame", measured_latency.as_millis(), flash_timing.time_per_frame().as_millis());
+
+            if let Err(e) = flash_timing.save() {
+                error!(target: "Gui Thread", "failed to store measured flash latency: {e}");
+            }
+            gui_context.set_flash_timing(flash_timing);
+
+            format!("Latency: {}ms", measured_latency.as_millis())
+        }
+        Ok(None) => {
+            warn!(target: "Gui Thread", "not every gun saw the screen, keeping the old flash timing");
+            "Not every gun saw the screen".to_string()
+        }
+        Err(e) => {
+            warn!(target: "Gui Thread", "measuring the flash latency failed: {e:?}");
+            "Measuring failed".to_string()
+        }
+    }
+}
@@ -2,11 +2,10 @@ use crate::gui::engine::components::action::Action;
 use crate::gui::engine::components::movement::Movement;
 use crate::gui::engine::components::point_with_alignment::{HAlign, PointWithAlignment};
 use crate::gui::engine::components::texture::Texture;
-use crate::gui::engine::components::{texture, Point};
+use crate::gui::engine::components::{Point, texture};
 use crate::gui::engine::event::Event;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use crate::gui::scenes::common::PlayerData;
+use crate::gui::engine::sprite_sheet::Sprite;
+use crate::gui::scenes::common::MagazineChanged;
 
 #[derive(hecs::Bundle)]
 pub struct Magazine {
@@ -14,31 +13,24 @@ pub struct Magazine {
 }
 
 impl Magazine {
-    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        shoot_event: Event,
-        reload_event: Event,
-        player_datas: Arc<Mutex<Vec<PlayerData>>>,
+        magazine_changed: Event<MagazineChanged>,
         player_id: usize,
         position: PointWithAlignment,
         scale: f32,
-        ammo_virgin_texture_id: usize,
-        ammo_used_texture_id: usize,
+        ammo_virgin_sprite: &Sprite,
+        ammo_used_sprite: &Sprite,
     ) -> Magazine {
-        let shell_used_texture = texture::Builder::new(ammo_used_texture_id, position)
-            .with_num_frames(21)
+        let shell_used_texture = texture::Builder::animated(ammo_used_sprite, position)
             .with_vertical_flip()
             .with_scale(scale)
             .with_z_index(10)
-            .with_frame_advance_interval(Duration::from_millis(66))
             .build();
 
-        let shell_virgin_texture = texture::Builder::new(ammo_virgin_texture_id, position)
-            .with_num_frames(21)
+        let shell_virgin_texture = texture::Builder::animated(ammo_virgin_sprite, position)
             .with_vertical_flip()
             .with_scale(scale)
             .with_z_index(10)
-            .with_frame_advance_interval(Duration::MAX)
             .build();
 
         let shell_arch_movement = Movement::new(move |t| {
@@ -51,9 +43,7 @@ impl Magazine {
 
         Magazine {
             actions: Action::update_ammo_info_when(
-                shoot_event,
-                reload_event,
-                player_datas,
+                magazine_changed,
                 player_id,
                 position,
                 scale,
@@ -67,134 +57,206 @@ impl Magazine {
 
 impl MakeAmmoWork for Action {}
 pub trait MakeAmmoWork {
-    #[allow(clippy::too_many_arguments)]
     fn update_ammo_info_when(
-        shoot_event: Event,
-        reload_event: Event,
-        player_datas: Arc<Mutex<Vec<PlayerData>>>,
+        magazine_changed: Event<MagazineChanged>,
         player_id: usize,
         start_position: PointWithAlignment,
         scale: f32,
-        ammo_used_texture: Texture,
-        ammo_virgin_texture: Texture,
+        mut ammo_used_texture: Texture,
+        mut ammo_virgin_texture: Texture,
         shot_movement: Movement,
     ) -> Vec<Action> {
-        let shell_entities = Arc::new(Mutex::new(Vec::new()));
-        let shell_entities_clone1 = shell_entities.clone();
-        let shell_entities_clone2 = shell_entities.clone();
-        let mut ammo_virgin_texture_clone1 = ammo_virgin_texture.clone();
-        let mut ammo_used_texture_clone1 = ammo_used_texture.clone();
-        let mut ammo_virgin_texture_clone2 = ammo_virgin_texture.clone();
-        let player_datas_clone = player_datas.clone();
-        vec![Action::when(shoot_event, move |_, world| {
-            let magazine_status = player_datas_clone.lock().unwrap()[player_id].magazine_status;
-
-            for entity in shell_entities_clone1.lock().unwrap().iter() {
-                let _ = world.despawn(*entity);
-            }
-
-            ammo_virgin_texture_clone1.position = match start_position.h_align {
-                HAlign::Left => start_position,
-                HAlign::Center => {
-                    let mut pos = start_position;
-                    pos.point.x -= (magazine_status.ammo_max as i32 * (50.0 * scale) as i32) / 2;
-                    pos
+        let mut shell_entities = Vec::new();
+        vec![Action::when_with(
+            magazine_changed,
+            move |change: MagazineChanged, _, world| {
+                if change.player != player_id {
+                    return;
                 }
-                HAlign::Right => {
-                    let mut pos = start_position;
-                    pos.point.x -= magazine_status.ammo_max as i32 * (50.0 * scale) as i32;
-                    pos
+                let magazine_status = change.magazine_status;
+
+                for entity in shell_entities.drain(..) {
+                    let _ = world.despawn(entity);
                 }
-            };
 
-            // do one more and turn it into a used shell
-            for _ in 0..=magazine_status.ammo {
-                shell_entities_clone1.lock().unwrap().push(world.spawn((ammo_virgin_texture_clone1.clone(),)));
+                ammo_virgin_texture.position = match start_position.h_align {
+                    HAlign::Left => start_position,
+                    HAlign::Center => {
+                        let mut pos = start_position;
+                        pos.point.x -=
+                            (magazine_status.ammo_max as i32 * (50.0 * scale) as i32) / 2;
+                        pos
+                    }
+                    HAlign::Right => {
+                        let mut pos = start_position;
+                        pos.point.x -= magazine_status.ammo_max as i32 * (50.0 * scale) as i32;
+                        pos
+                    }
+                };
 
-                ammo_virgin_texture_clone1.position.point.x += (50.0 * scale) as i32;
-            }
-            {
-                let last_shell_entity_id = shell_entities_clone1.lock().unwrap().pop().unwrap();
-                let last_shell_entity = world
-                    .get::<&mut Texture>(last_shell_entity_id)
-                    .unwrap()
-                    .clone();
-
-                ammo_used_texture_clone1.position = last_shell_entity.position;
-                ammo_used_texture_clone1.original_point = last_shell_entity.position.point;
-                world.despawn(last_shell_entity_id).unwrap();
-            }
-
-            let shell_gone_event = Event::default();
-            ammo_used_texture_clone1.outside_viewport_event = Some(shell_gone_event.clone());
-            world.spawn((
-                ammo_used_texture_clone1.clone(),
-                shot_movement.clone(),
-                Action::despawn_self_when(shell_gone_event),
-            ));
-        }), Action::when(reload_event, move |_, world| {
-            let magazine_status = player_datas.lock().unwrap()[player_id].magazine_status;
-
-            for entity in shell_entities_clone2.lock().unwrap().iter() {
-                let _ = world.despawn(*entity);
-            }
+                if !change.by_shot {
+                    for _ in 0..magazine_status.ammo {
+                        shell_entities.push(world.spawn((ammo_virgin_texture.clone(),)));
 
-            ammo_virgin_texture_clone2.position = match start_position.h_align {
-                HAlign::Left => start_position,
-                HAlign::Center => {
-                    let mut pos = start_position;
-                    pos.point.x -= (magazine_status.ammo_max as i32 * (50.0 * scale) as i32) / 2;
-                    pos
+                        ammo_virgin_texture.position.point.x += (50.0 * scale) as i32;
+                    }
+                    return;
                 }
-                HAlign::Right => {
-                    let mut pos = start_position;
-                    pos.point.x -= magazine_status.ammo_max as i32 * (50.0 * scale) as i32;
-                    pos
+
+                // do one more and turn it into a used shell
+                for _ in 0..=magazine_status.ammo {
+                    shell_entities.push(world.spawn((ammo_virgin_texture.clone(),)));
+
+                    ammo_virgin_texture.position.point.x += (50.0 * scale) as i32;
                 }
-            };
+                {
+                    let last_shell_entity_id = shell_entities.pop().unwrap();
+                    let last_shell_entity = world
+                        .get::<&mut Texture>(last_shell_entity_id)
+                        .unwrap()
+                        .clone();
 
-            for _ in 0..magazine_status.ammo {
-                shell_entities_clone2.lock().unwrap().push(world.spawn((ammo_virgin_texture_clone2.clone(),)));
+                    ammo_used_texture.position = last_shell_entity.position;
+                    ammo_used_texture.original_point = last_shell_entity.position.point;
+                    world.despawn(last_shell_entity_id).unwrap();
+                }
 
-                ammo_virgin_texture_clone2.position.point.x += (50.0 * scale) as i32;
-            }
-        })]
+                let shell_gone_event = Event::default();
+                ammo_used_texture.outside_viewport_event = Some(shell_gone_event.clone());
+                world.spawn((
+                    ammo_used_texture.clone(),
+                    shot_movement.clone(),
+                    Action::despawn_self_when(shell_gone_event),
+                ));
+            },
+        )]
     }
 }
 
-
 pub trait SpawnMagazineAction {
-    #[allow(clippy::too_many_arguments)]
+    /// spawns the magazine of the player with their first shot, and then despawns itself
     fn spawn_magazine_when(
-        shoot_event: Event,
-        reload_event: Event,
-        player_datas: Arc<Mutex<Vec<PlayerData>>>,
+        magazine_changed: Event<MagazineChanged>,
         player_id: usize,
         position: PointWithAlignment,
         scale: f32,
-        ammo_virgin_texture_id: usize,
-        ammo_used_texture_id: usize,
+        ammo_virgin_sprite: Sprite,
+        ammo_used_sprite: Sprite,
     ) -> Action {
-        let mut shoot_event_clone = shoot_event.clone();
-        let reload_event_clone = reload_event.clone();
-        let player_datas_clone = player_datas.clone();
-        Action::when_oneshot(shoot_event, move |_, world| {
-            let magazine = Magazine::new(
-                shoot_event_clone.clone(),
-                reload_event_clone.clone(),
-                player_datas_clone.clone(),
-                player_id,
-                position,
-                scale,
-                ammo_virgin_texture_id,
-                ammo_used_texture_id,
-            );
-            world.spawn(magazine);
-
-            // trigger shot event again, so that the magazine gets a chance to draw itself
-            shoot_event_clone.trigger();
-        })
+        let mut magazine_changed_clone = magazine_changed.clone();
+        let mut spawned = false;
+        Action::when_with(
+            magazine_changed,
+            move |change: MagazineChanged, my_entity_id, world| {
+                if spawned || change.player != player_id || !change.by_shot {
+                    return;
+                }
+
+                let magazine = Magazine::new(
+                    magazine_changed_clone.clone(),
+                    player_id,
+                    position,
+                    scale,
+                    &ammo_virgin_sprite,
+                    &ammo_used_sprite,
+                );
+                world.spawn(magazine);
+                let _ = world.despawn(my_entity_id);
+                spawned = true;
+
+                // trigger the change again, so that the magazine gets a chance to draw itself
+                magazine_changed_clone.trigger_with(change);
+            },
+        )
     }
 }
 
 impl SpawnMagazineAction for Action {}
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::components::Point;
+    use crate::gui::engine::components::point_with_alignment::{
+        HAlign, PointWithAlignment, VAlign,
+    };
+    use crate::gui::engine::event::Event;
+    use crate::gui::engine::render::DrawTarget;
+    use crate::gui::engine::render::golden::assert_golden;
+    use crate::gui::engine::render::software::{Bitmap, SoftwareRenderer};
+    use crate::gui::engine::sprite_sheet::{Animation, Grid, Sprite};
+    use crate::gui::engine::systems;
+    use crate::gui::scenes::common::MagazineChanged;
+    use crate::gui::scenes::common::magazine::Magazine;
+    use crate::serial::packet::MagazineStatus;
+    use hecs::World;
+    use sdl2::pixels::Color;
+
+    #[test]
+    fn shells_line_up_at_the_position_and_the_last_one_is_ejected() {
+        let mut renderer = SoftwareRenderer::new(160, 60);
+        // the tip is red, so that the flip shows
+        let shell = |body: Color| {
+            Bitmap::from_fn(20, 40, move |_, y| if y < 10 { Color::RED } else { body })
+        };
+        let still = |image| Sprite {
+            image,
+            grid: Grid {
+                columns: 1,
+                rows: 1,
+            },
+            pivot: (0.5, 0.5),
+            animation: Animation::still(0),
+        };
+        let full_shell = still(renderer.add_image(shell(Color::YELLOW)));
+        let used_shell = still(renderer.add_image(shell(Color::GREY)));
+
+        let mut magazine_changed = Event::new();
+        let mut world = World::new();
+        world.spawn(Magazine::new(
+            magazine_changed.clone(),
+            0,
+            PointWithAlignment {
+                point: Point { x: 150, y: 50 },
+                v_align: VAlign::Bottom,
+                h_align: HAlign::Right,
+            },
+            0.5,
+            &full_shell,
+            &used_shell,
+        ));
+
+        magazine_changed.trigger_with(MagazineChanged {
+            player: 0,
+            magazine_status: MagazineStatus {
+                ammo: 3,
+                ammo_max: 5,
+            },
+            by_shot: false,
+        });
+        systems::work_actions::run(&mut world);
+        systems::draw_textures::run(&mut renderer, &mut world);
+        assert_golden("magazine_reloaded", renderer.frame());
+
+        // the magazine of another player does not change this one
+        magazine_changed.trigger_with(MagazineChanged {
+            player: 1,
+            magazine_status: MagazineStatus {
+                ammo: 0,
+                ammo_max: 5,
+            },
+            by_shot: true,
+        });
+        magazine_changed.trigger_with(MagazineChanged {
+            player: 0,
+            magazine_status: MagazineStatus {
+                ammo: 2,
+                ammo_max: 5,
+            },
+            by_shot: true,
+        });
+        systems::work_actions::run(&mut world);
+        renderer.clear();
+        systems::draw_textures::run(&mut renderer, &mut world);
+        assert_golden("magazine_after_shot", renderer.frame());
+    }
+}
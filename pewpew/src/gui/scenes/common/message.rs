@@ -0,0 +1,49 @@
+use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
+use crate::gui::engine::components::{Point, text};
+use crate::gui::engine::render::DrawTarget;
+use crate::gui::engine::systems;
+use crate::gui::scenes::scene::SceneContext;
+use hecs::World;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+// white lines of text on black, centered on the screen
+// a scene that shows a message keeps it in its state and draws it in Scene::draw,
+// how long it is shown is up to the scene
+
+pub struct Message {
+    world: World,
+}
+
+impl Message {
+    pub fn new(lines: &[&str], viewport: Rect) -> Self {
+        // a single line is large, several lines have to fit on the screen
+        let scale_denominator = if lines.len() == 1 { 1440 } else { 2880 };
+        let line_height = (180 * viewport.height() / scale_denominator) as i32;
+        let first_line_y =
+            (viewport.height() / 2) as i32 - line_height * (lines.len() as i32 - 1) / 2;
+
+        let mut world = World::new();
+        for (i, line) in lines.iter().enumerate() {
+            world.spawn((text::Builder::new(
+                line.to_string(),
+                PointWithAlignment::new_center(Point {
+                    x: (viewport.width() / 2) as i32,
+                    y: first_line_y + line_height * i as i32,
+                }),
+            )
+            .with_color(Color::WHITE)
+            .with_scale(viewport.height(), scale_denominator)
+            .build(),));
+        }
+
+        Message { world }
+    }
+
+    pub fn draw<'sdl>(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        let mut renderer = ctx.renderer();
+        renderer.set_draw_color(Color::BLACK);
+        renderer.clear();
+        systems::draw_texts::run(&mut renderer, &mut self.world);
+    }
+}
@@ -1,10 +1,42 @@
 use crate::serial::packet::MagazineStatus;
+use hecs::Entity;
 
 pub mod magazine;
+pub mod message;
+pub mod oscilloscope;
+pub mod pause;
 pub mod scenery;
 
+// the players are ids into the Vec<PlayerData> of the scene, in the order they joined
+// each scene owns that Vec and hands it to the next one, what happens to a player in between
+// goes to the world with the events below, one event for all players
+
 pub struct PlayerData {
     pub sensortag_id: u16,
     pub magazine_status: MagazineStatus,
     pub score: u32,
-}
\ No newline at end of file
+}
+
+/// a shot of a player that hit registration is done with, `victim` is None for a miss
+#[derive(Debug, Clone, Copy)]
+pub struct ShotFired {
+    pub shooter: usize,
+    pub victim: Option<Entity>,
+}
+
+/// points a player got for a hit
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreDelta {
+    pub player: usize,
+    pub points: u32,
+}
+
+/// what the gun of a player reported about its magazine, right when it did
+#[derive(Debug, Clone, Copy)]
+pub struct MagazineChanged {
+    pub player: usize,
+    pub magazine_status: MagazineStatus,
+
+    /// a shot ejects a shell, otherwise the magazine was reloaded
+    pub by_shot: bool,
+}
@@ -0,0 +1,340 @@
+use crate::comm::message::SerialToHitReg;
+use crate::common::latency;
+use crate::gui::engine::components::point_with_alignment::{HAlign, PointWithAlignment, VAlign};
+use crate::gui::engine::components::{Point, text};
+use crate::gui::engine::gui_context::GuiContext;
+use crate::gui::engine::render::sdl::SdlRenderer;
+use crate::gui::engine::resources::Resources;
+use crate::gui::engine::systems;
+use crate::hitreg::ambient::AmbientTracker;
+use hecs::World;
+use sdl2::pixels::Color;
+use sdl2::rect;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, TextureCreator};
+use sdl2::video::WindowContext;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// debug overlay for setting up the guns: the brightness every sensortag reports as a scrolling
+// graph, one per sensortag, the newest sample on the right
+// the samples come from a tap on the serial to hitreg stream (see comm::new) and are timed
+// by when the gui got them, so with the resolution of one gui frame
+// the ambient light is tracked like hitreg tracks it between shots, so the dashed lines are
+// the black level hitreg would see and how much brighter a frame must be to count as white
+// above the graphs are the latency histograms of the shots so far, see common::latency
+
+/// how much of the past the graphs show
+const WINDOW: Duration = Duration::from_secs(5);
+
+const COLORS: [Color; 4] = [Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW];
+const LEVEL_COLOR: Color = Color::RGB(200, 200, 200);
+const MARGIN: i32 = 10;
+
+#[derive(Default)]
+pub struct Oscilloscope {
+    visible: bool,
+
+    /// (received at, brightness) per sensortag, oldest first
+    traces: BTreeMap<u16, VecDeque<(Instant, u16)>>,
+    ambient: AmbientTracker,
+}
+
+impl Oscilloscope {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// takes everything from the tap, must be called every frame, also while hidden,
+    /// otherwise the graphs start with a burst of old samples
+    pub fn update(&mut self, gui_context: &mut GuiContext) {
+        let now = Instant::now();
+        while let Some(sample) = gui_context.comm().try_recv_brightness() {
+            self.observe(&sample, now);
+        }
+    }
+
+    fn observe(&mut self, sample: &SerialToHitReg, now: Instant) {
+        self.ambient.observe(sample);
+
+        let trace = self.traces.entry(sample.sensortag_id).or_default();
+        trace.push_back((now, sample.value_raw));
+
+        // the sensortag only sends when the brightness changes, so the newest sample older
+        // than the window is still the brightness at the start of the window
+        while trace
+            .get(1)
+            .is_some_and(|(at, _)| now.duration_since(*at) > WINDOW)
+        {
+            trace.pop_front();
+        }
+    }
+
+    /// (brightness, label) of the dashed lines of a sensortag
+    fn levels(&self, sensortag_id: u16) -> Vec<(u16, String)> {
+        let Some(ambient) = self.ambient.get(sensortag_id) else {
+            return vec![];
+        };
+
+        let black = ambient.baseline.round() as u16;
+        let white_threshold = self.ambient.white_threshold(sensortag_id);
+        vec![
+            (black, format!("ambient {black}")),
+            (
+                black.saturating_add(white_threshold),
+                format!("white threshold +{white_threshold}"),
+            ),
+        ]
+    }
+
+    pub fn draw<'a>(
+        &self,
+        gui_context: &mut GuiContext,
+        resources: &mut Resources<'a>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        let now = Instant::now();
+        let (width, height) = gui_context.canvas().output_size().unwrap();
+        let panel = Rect::new(0, height as i32 / 2, width, height / 2);
+        let canvas = gui_context.canvas();
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+        canvas.fill_rect(panel).unwrap();
+        canvas.set_blend_mode(BlendMode::None);
+
+        let mut labels = World::new();
+        let label = |text: String, point: Point, h_align: HAlign, color: Color| {
+            text::Builder::new(
+                text,
+                PointWithAlignment {
+                    point,
+                    v_align: VAlign::Top,
+                    h_align,
+                },
+            )
+            .with_color(color)
+            .with_scale(height, 5760)
+            .build()
+        };
+
+        if self.traces.is_empty() {
+            labels.spawn((label(
+                "no brightness samples yet".to_string(),
+                Point {
+                    x: panel.x() + MARGIN,
+                    y: panel.y() + MARGIN,
+                },
+                HAlign::Left,
+                Color::WHITE,
+            ),));
+        }
+
+        let row_height = panel.height() / self.traces.len().max(1) as u32;
+        for (i, (sensortag_id, trace)) in self.traces.iter().enumerate() {
+            let area = Rect::new(
+                panel.x() + MARGIN,
+                panel.y() + (i as u32 * row_height) as i32 + MARGIN,
+                panel.width() - 2 * MARGIN as u32,
+                row_height.saturating_sub(2 * MARGIN as u32).max(1),
+            );
+            let color = COLORS[i % COLORS.len()];
+            let levels = self.levels(*sensortag_id);
+            let max_brightness = trace
+                .iter()
+                .map(|(_, value)| *value)
+                .chain(levels.iter().map(|(value, _)| *value))
+                .max()
+                .unwrap_or(0)
+                .max(1) as u32
+                * 11
+                / 10;
+
+            canvas.set_draw_color(Color::RGB(80, 80, 80));
+            canvas.draw_rect(area).unwrap();
+
+            canvas.set_draw_color(LEVEL_COLOR);
+            for (value, text) in &levels {
+                let y = y_of(*value, area, max_brightness);
+                for x in (area.left()..area.right()).step_by(16) {
+                    canvas
+                        .draw_line(
+                            rect::Point::new(x, y),
+                            rect::Point::new((x + 8).min(area.right()), y),
+                        )
+                        .unwrap();
+                }
+                labels.spawn((label(
+                    text.clone(),
+                    Point {
+                        x: area.right() - MARGIN,
+                        y: y + 2,
+                    },
+                    HAlign::Right,
+                    LEVEL_COLOR,
+                ),));
+            }
+
+            canvas.set_draw_color(color);
+            canvas
+                .draw_lines(points(trace, area, max_brightness, now).as_slice())
+                .unwrap();
+
+            labels.spawn((label(
+                format!(
+                    "sensortag {sensortag_id:#06x}: {}",
+                    trace.back().map_or(0, |(_, value)| *value)
+                ),
+                Point {
+                    x: area.left() + MARGIN,
+                    y: area.top() + MARGIN,
+                },
+                HAlign::Left,
+                color,
+            ),));
+        }
+
+        let report = latency::report();
+        if !report.is_empty() {
+            let line_height = height as i32 / 40;
+            let area = Rect::new(
+                0,
+                panel.y() - line_height * report.len() as i32 - 2 * MARGIN,
+                width,
+                (line_height * report.len() as i32 + 2 * MARGIN) as u32,
+            );
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+            canvas.fill_rect(area).unwrap();
+            canvas.set_blend_mode(BlendMode::None);
+
+            for (i, line) in report.into_iter().enumerate() {
+                labels.spawn((label(
+                    line,
+                    Point {
+                        x: area.x() + MARGIN,
+                        y: area.y() + MARGIN + line_height * i as i32,
+                    },
+                    HAlign::Left,
+                    Color::WHITE,
+                ),));
+            }
+        }
+
+        systems::draw_texts::run(
+            &mut SdlRenderer::new(canvas, resources, texture_creator),
+            &mut labels,
+        );
+    }
+}
+
+fn y_of(value: u16, area: Rect, max_brightness: u32) -> i32 {
+    area.bottom() - (value as u32 * area.height() / max_brightness.max(1)) as i32
+}
+
+/// the trace as a line through `area`, now at the right edge, brightness 0 at the bottom
+/// and max_brightness at the top
+/// a brightness holds until the next sample, so the line only has horizontal and vertical steps
+fn points(
+    trace: &VecDeque<(Instant, u16)>,
+    area: Rect,
+    max_brightness: u32,
+    now: Instant,
+) -> Vec<rect::Point> {
+    let x_of = |at: Instant| {
+        let age = now.duration_since(at).min(WINDOW);
+        area.right() - (age.as_secs_f32() / WINDOW.as_secs_f32() * area.width() as f32) as i32
+    };
+
+    let mut points = Vec::new();
+    for (i, (at, value)) in trace.iter().enumerate() {
+        if i > 0 {
+            points.push(rect::Point::new(
+                x_of(*at),
+                y_of(trace[i - 1].1, area, max_brightness),
+            ));
+        }
+        points.push(rect::Point::new(
+            x_of(*at),
+            y_of(*value, area, max_brightness),
+        ));
+    }
+    if let Some((_, value)) = trace.back() {
+        points.push(rect::Point::new(
+            area.right(),
+            y_of(*value, area, max_brightness),
+        ));
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comm::message::{SerialToHitReg, Stamps};
+    use crate::gui::scenes::common::oscilloscope::{Oscilloscope, WINDOW, points};
+    use sdl2::rect;
+    use sdl2::rect::Rect;
+    use std::time::{Duration, Instant};
+
+    fn sample(value_raw: u16) -> SerialToHitReg {
+        SerialToHitReg {
+            sensortag_id: 1,
+            timestamp: 1,
+            value_raw,
+            stamps: Stamps::now(),
+        }
+    }
+
+    #[test]
+    fn old_samples_leave_the_window() {
+        let mut oscilloscope = Oscilloscope::default();
+        let t0 = Instant::now();
+
+        oscilloscope.observe(&sample(10), t0);
+        oscilloscope.observe(&sample(20), t0 + Duration::from_secs(1));
+        oscilloscope.observe(&sample(30), t0 + WINDOW + Duration::from_secs(2));
+
+        // 20 is older than the window, but it is what the sensortag saw when the window starts
+        let values = oscilloscope.traces[&1]
+            .iter()
+            .map(|(_, value)| *value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![20, 30]);
+    }
+
+    #[test]
+    fn levels_follow_the_ambient_light() {
+        let mut oscilloscope = Oscilloscope::default();
+        let now = Instant::now();
+
+        assert!(oscilloscope.levels(1).is_empty());
+        for _ in 0..20 {
+            oscilloscope.observe(&sample(100), now);
+        }
+
+        let levels = oscilloscope.levels(1);
+        assert_eq!(levels[0].0, 100);
+        assert!(levels[1].0 > 100);
+    }
+
+    #[test]
+    fn trace_is_drawn_in_steps() {
+        let now = Instant::now();
+        let trace = [(now - WINDOW / 2, 0), (now, 100)].into_iter().collect();
+
+        assert_eq!(
+            points(&trace, Rect::new(0, 0, 100, 100), 100, now),
+            vec![
+                rect::Point::new(50, 100),
+                rect::Point::new(100, 100),
+                rect::Point::new(100, 0),
+                rect::Point::new(100, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use crate::gui::engine::stopwatch::Stopwatch;
+use crate::gui::scenes::common::message::Message;
+use crate::gui::scenes::scene::SceneContext;
+use sdl2::rect::Rect;
+
+// while the game is paused, the scene manager shows this instead of the scene, see scene
+// the time of the scene stands still meanwhile
+
+pub struct Pause {
+    message: Message,
+
+    /// the game pauses its time itself while flashing, that must stay so
+    time_was_running: bool,
+}
+
+impl Pause {
+    pub fn start(game_time: &mut Stopwatch, viewport: Rect) -> Self {
+        let time_was_running = !game_time.is_paused();
+        game_time.pause();
+
+        Pause {
+            message: Message::new(&["Paused", "Press P to continue"], viewport),
+            time_was_running,
+        }
+    }
+
+    pub fn end(self, game_time: &mut Stopwatch) {
+        if self.time_was_running {
+            game_time.resume();
+        }
+    }
+
+    pub fn draw<'sdl>(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        self.message.draw(ctx);
+    }
+}
@@ -1,66 +1,39 @@
-use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
-use crate::gui::engine::components::texture;
+use crate::gui::engine::assets::AssetError;
+use crate::gui::engine::components::occluder;
+use crate::gui::engine::components::occluder::Occluder;
+use crate::gui::engine::components::point_with_alignment::{HAlign, PointWithAlignment, VAlign};
 use crate::gui::engine::components::texture::Texture;
+use crate::gui::engine::components::{Point, texture};
 use crate::gui::engine::resources::Resources;
 use hecs::Bundle;
-use sdl2::image::LoadTexture;
-use sdl2::render::TextureCreator;
-use sdl2::video::WindowContext;
-use std::path::Path;
 
 #[derive(Bundle)]
 pub struct Scenery {
     textures: Vec<Texture>,
+    occluders: Vec<Occluder>,
 }
 
 impl Scenery {
-    pub fn new<'a>(
+    pub fn new(
         mut position: PointWithAlignment,
         scale: f32,
-        resources: &mut Resources<'a>,
-        texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Self {
-        let texture_index = resources.images.len();
+        resources: &mut Resources,
+    ) -> Result<Self, AssetError> {
+        let center = position.point;
 
-        // load
-        {
-            resources.images.push(
-                texture_creator
-                    .load_texture(Path::new("res/../res/images/sky.png"))
-                    .unwrap(),
-            );
-
-            resources.images.push(
-                texture_creator
-                    .load_texture(Path::new("res/../res/images/backdrop.png"))
-                    .unwrap(),
-            );
-
-            resources.images.push(
-                texture_creator
-                    .load_texture(Path::new("res/../res/images/castle.png"))
-                    .unwrap(),
-            );
-
-            resources.images.push(
-                texture_creator
-                    .load_texture(Path::new("res/../res/images/foreground.png"))
-                    .unwrap(),
-            );
-
-            resources.images.push(
-                texture_creator
-                    .load_texture(Path::new("res/../res/images/tree.png"))
-                    .unwrap(),
-            );
-        }
+        let assets = &mut resources.assets;
+        let sky = assets.texture("sky")?;
+        let backdrop = assets.texture("backdrop")?;
+        let castle = assets.texture("castle")?;
+        let foreground = assets.texture("foreground")?;
+        let tree = assets.texture("tree")?;
 
         // spawn
         let mut textures = Vec::new();
         {
             position.point.y -= (150.0 * scale) as i32;
             textures.push(
-                texture::Builder::new(texture_index, position)
+                texture::Builder::new(sky, position)
                     .with_z_index(-10)
                     .with_scale(scale)
                     .build(),
@@ -68,7 +41,7 @@ impl Scenery {
 
             position.point.y += (125.0 * scale) as i32;
             textures.push(
-                texture::Builder::new(texture_index + 1, position)
+                texture::Builder::new(backdrop, position)
                     .with_z_index(-9)
                     .with_scale(scale)
                     .build(),
@@ -76,7 +49,7 @@ impl Scenery {
 
             position.point.y += (125.0 * scale) as i32;
             textures.push(
-                texture::Builder::new(texture_index + 2, position)
+                texture::Builder::new(castle, position)
                     .with_z_index(-8)
                     .with_scale(scale)
                     .build(),
@@ -84,12 +57,44 @@ impl Scenery {
 
             position.point.y += (72.5 * scale) as i32;
             textures.push(
-                texture::Builder::new(texture_index + 3, position)
+                texture::Builder::new(foreground, position)
                     .with_z_index(-7)
                     .with_scale(scale)
                     .build(),
             );
         }
-        Scenery { textures }
+
+        // the tree stands in front of the chickens, so it hides them from the flashing sequence too
+        let mut occluders = Vec::new();
+        {
+            let tree_position = PointWithAlignment {
+                point: Point {
+                    x: center.x + (600.0 * scale) as i32,
+                    y: center.y + (360.0 * scale) as i32,
+                },
+                v_align: VAlign::Bottom,
+                h_align: HAlign::Right,
+            };
+            textures.push(
+                texture::Builder::new(tree, tree_position)
+                    .with_z_index(5)
+                    .with_scale(scale)
+                    .build(),
+            );
+            occluders.push(
+                occluder::Builder::new(
+                    tree_position,
+                    (245.0 * scale) as u32,
+                    (597.0 * scale) as u32,
+                )
+                .with_z_index(5)
+                .build(),
+            );
+        }
+
+        Ok(Scenery {
+            textures,
+            occluders,
+        })
     }
 }
@@ -1,54 +1,152 @@
 use crate::comm::message::SerialToGuiKind;
+use crate::common::latency::ShotTrace;
+use crate::gui::engine::assets::AssetError;
+use crate::gui::engine::audio::GunSoundAssignment;
 use crate::gui::engine::components::action::Action;
 use crate::gui::engine::components::hitbox::Hitbox;
+use crate::gui::engine::components::hitbox_shape;
+use crate::gui::engine::components::hitbox_shape::HitboxShape;
 use crate::gui::engine::components::movement::Movement;
+use crate::gui::engine::components::play_sound::PlaySound;
 use crate::gui::engine::components::point_with_alignment::{HAlign, PointWithAlignment, VAlign};
-use crate::gui::engine::components::texture::{AnimationEndBehavior, Texture};
+use crate::gui::engine::components::texture::Texture;
 use crate::gui::engine::components::{Point, hitbox, text, texture, timer};
 use crate::gui::engine::event::Event;
-use crate::gui::engine::gui_context::GuiContext;
-use crate::gui::engine::resources::Resources;
+use crate::gui::engine::flash_style::FlashStyle;
+use crate::gui::engine::render::Renderer;
+use crate::gui::engine::render::sdl::SdlRenderer;
+use crate::gui::engine::sprite_sheet::Sprite;
 use crate::gui::engine::stopwatch::Stopwatch;
 use crate::gui::engine::systems;
-use crate::gui::scenes::common::PlayerData;
+use crate::gui::engine::systems::flashing_sequence::{
+    FlashingSequence, HitregError, Outcome, Target,
+};
 use crate::gui::scenes::common::magazine::Magazine;
+use crate::gui::scenes::common::oscilloscope::Oscilloscope;
 use crate::gui::scenes::common::scenery::Scenery;
-use crate::gui::scenes::load_all_textures;
+use crate::gui::scenes::common::{MagazineChanged, PlayerData, ScoreDelta, ShotFired};
+use crate::gui::scenes::scene::{Scene, SceneContext, Transition};
+use crate::gui::scenes::scoreboard::Scoreboard;
 use crate::serial::packet::MagazineStatus;
+use crate::user_settings;
 use hecs::World;
-use log::trace;
+use log::{error, info, warn};
 use rand::Rng;
+use sdl2::event::Event as SdlEvent;
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
+use sdl2::render::Texture as SdlTexture;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
-use std::{thread, vec};
-use sdl2::mixer::Chunk;
+use std::time::{Duration, Instant};
+use std::vec;
 
 const GAME_DURATION_SEC: u64 = 20;
 
-pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>>>) -> Arc<Mutex<Vec<PlayerData>>> {
-    let viewport = {
-        let (width, height) = gui_context.canvas().output_size().unwrap();
-        Rect::new(0, 0, width, height)
-    };
-    let texture_creator = gui_context.canvas().texture_creator();
-    let ttf_context = sdl2::ttf::init().unwrap();
-
-    // Load and play mp3
-    let shoot_sounds = [Chunk::from_file("res/audio/gun-shot-359196.mp3").unwrap(), Chunk::from_file("res/audio/glock19-18535.mp3").unwrap()];
-    let reload_sounds = [Chunk::from_file("res/audio/ak47_boltpull.mp3").unwrap(), Chunk::from_file("res/audio/_en_sound_glock18-slideforward_.mp3").unwrap()];
-    let death_sounds = [Chunk::from_file("res/audio/wilhelm_scream.mp3").unwrap(), Chunk::from_file("res/audio/ahhhh.mp3").unwrap()];
-    let dry_shot_sound = Chunk::from_file("res/../res/audio/dry-fire-364846.mp3").unwrap();
-    {
-        let default_font = ttf_context
-            .load_font("res/fonts/Walter_Turncoat/WalterTurncoat-Regular.ttf", 128)
-            .unwrap();
-        let mut resources = Resources::new(default_font);
-        let texture_id_map = load_all_textures(&mut resources, &texture_creator).unwrap();
-
-        let mut world = World::new();
-        let mut game_time = Stopwatch::new_paused();
+// width of the stripes the screen is cut into with user_settings::AIM_POINT_MODE
+const AIM_POINT_STRIPE_SIZE: u32 = 16;
+const BULLET_HOLE_DURATION: Duration = Duration::from_secs(2);
+
+pub struct Game<'sdl> {
+    player_datas: Vec<PlayerData>,
+    world: World,
+    game_time: Stopwatch,
+    game_end_event: Event,
+    shot_fired: Event<ShotFired>,
+    score_changed: Event<ScoreDelta>,
+    magazine_changed: Event<MagazineChanged>,
+    gun_sounds: Option<GunSoundAssignment>,
+    hitreg_dead_notice_shown: bool,
+
+    /// with safe flashing, the scene is drawn into this first, so that it can be shown
+    /// dimmed behind the flash frames
+    scene_snapshot: Option<SdlTexture<'sdl>>,
+
+    /// only one shot can be flashed at a time, the others wait here
+    pending_shots: VecDeque<(usize, u16, ShotTrace)>,
+    flashing: Option<(usize, ShotTrace, FlashingSequence)>,
+    flashing_finished: Event,
+    flash_target: Target,
+
+    /// shots whose result is on the screen with the next present
+    shown_shots: Vec<(usize, ShotTrace)>,
+
+    // F3 shows what the sensortags see and how long shots take, see common::latency
+    oscilloscope: Oscilloscope,
+}
+
+/// what every chicken of the game looks like
+#[derive(Clone)]
+struct Chicken {
+    flying: Sprite,
+    dying: Sprite,
+    shape: HitboxShape,
+}
+
+impl Game<'_> {
+    pub fn new(player_datas: Vec<PlayerData>) -> Self {
+        let flash_target = if user_settings::AIM_POINT_MODE {
+            Target::AimPoint {
+                stripe_size: AIM_POINT_STRIPE_SIZE,
+            }
+        } else {
+            Target::Hitbox {
+                levels: user_settings::BRIGHTNESS_LEVELS,
+                coarse_to_fine: user_settings::COARSE_TO_FINE_MODE,
+            }
+        };
+
+        Game {
+            player_datas,
+            world: World::new(),
+            game_time: Stopwatch::new_paused(),
+            game_end_event: Event::default(),
+            shot_fired: Event::new(),
+            score_changed: Event::new(),
+            magazine_changed: Event::new(),
+            gun_sounds: None,
+            hitreg_dead_notice_shown: false,
+            scene_snapshot: None,
+            pending_shots: VecDeque::new(),
+            flashing: None,
+            flashing_finished: Event::default(),
+            flash_target,
+            shown_shots: Vec::new(),
+            oscilloscope: Oscilloscope::default(),
+        }
+    }
+}
+
+impl<'sdl> Scene<'sdl> for Game<'sdl> {
+    fn enter(&mut self, ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        let viewport = ctx.viewport;
+        let texture_creator = ctx.texture_creator;
+        let resources = &mut *ctx.resources;
+
+        self.gun_sounds = Some(resources.audio.gun_sound_assignment()?);
+        let assets = &mut resources.assets;
+        let full_shell = assets.sprite("ammo", "full")?;
+        let ejected_shell = assets.sprite("ammo", "eject")?;
+
+        // the sprite sheet of the chicken says that only the chicken itself counts as a hit,
+        // not the transparent pixels around it
+        let flying = assets.sprite("chicken", "fly")?;
+        let chicken = Chicken {
+            flying,
+            dying: assets.sprite("chicken_dying", "fall")?,
+            shape: match assets.hitbox_shape("chicken") {
+                Ok(shape) => shape,
+                Err(e) => {
+                    warn!(target: "Gui Thread", "failed to load the hitbox shape of the chicken, using rectangular hitboxes: {e}");
+                    HitboxShape::Rectangle
+                }
+            },
+        };
+
+        let world = &mut self.world;
+        let player_datas = &self.player_datas;
+        let gun_sounds = self.gun_sounds.as_ref().unwrap();
 
         let scenery_scale = viewport.height() as f32 / 720.0;
         world.spawn(Scenery::new(
@@ -57,12 +155,11 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                 y: (viewport.height() / 2) as i32,
             }),
             scenery_scale,
-            &mut resources,
-            &texture_creator,
-        ));
+            resources,
+        )?);
 
         // game end event
-        let mut game_end_event = Event::default();
+        let game_end_event = self.game_end_event.clone();
         let seconds_left = Arc::new(Mutex::new(GAME_DURATION_SEC));
         let mut game_countdown_tick = Event::default();
 
@@ -70,7 +167,6 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
             Duration::from_secs(*seconds_left.lock().unwrap()),
             game_end_event.clone(),
         )
-
         .build(),));
         world.spawn((
             timer::Builder::new(Duration::from_secs(1), game_countdown_tick.clone())
@@ -95,8 +191,8 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                             h_align: HAlign::Center,
                         },
                     )
-                        .with_color(Color::BLACK)
-                        .with_scale(viewport.height(), 1080)
+                    .with_color(Color::BLACK)
+                    .with_scale(viewport.height(), 1080)
                     .build(),
                     Action::despawn_self_when(game_countdown_tick_clone.clone()),
                 ));
@@ -116,6 +212,7 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                 spawn_new_chicken_event.clone(),
                 viewport,
                 spawn_new_chicken_event.clone(),
+                chicken,
             ),));
 
             for _ in 0..10 {
@@ -123,20 +220,11 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
             }
         }
 
-        let mut shoot_events = Vec::new();
-        let mut reload_events = Vec::new();
-        let mut score_changed_events = Vec::new();
-
-        let ammo_width = resources.images[texture_id_map["ammo.png"]].query().width;
+        let ammo_width = resources.assets.image(full_shell.image).query().width;
         let magazine_scale = 0.15 * viewport.height() as f32 / ammo_width as f32;
-        let amount_of_players = player_datas.lock().unwrap().len();
         // spawn players
         {
-            for i in 0..amount_of_players {
-                let shoot_event = Event::default();
-                let mut reload_event = Event::default();
-                let mut score_changed = Event::default();
-
+            for (i, player_data) in player_datas.iter().enumerate() {
                 let position = match i {
                     0 => PointWithAlignment {
                         point: Point { x: 0, y: 0 },
@@ -173,14 +261,12 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                 // spawn magazine
                 // and despawn spawner, so that spawn magazine action only runs once
                 let magazine = Magazine::new(
-                    shoot_event.clone(),
-                    reload_event.clone(),
-                    player_datas.clone(),
+                    self.magazine_changed.clone(),
                     i,
                     position,
                     magazine_scale,
-                    texture_id_map["ammo.png"],
-                    texture_id_map["ammo.png"],
+                    &full_shell,
+                    &ejected_shell,
                 );
                 world.spawn(magazine);
 
@@ -196,170 +282,359 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                     }
                     _ => unreachable!(),
                 }
-                let score_changed_clone = score_changed.clone();
-                let player_datas_clone = player_datas.clone();
-                world.spawn((vec![Action::when(
-                    score_changed.clone(),
-                    move |_, world| {
-                        let text = text::Builder::new(
-                            format!("score: {}", player_datas_clone.lock().unwrap()[i].score),
-                            score_position,
-                        )
-                        .with_color(Color::BLACK)
-                        .with_scale(viewport.height(), 2160)
-                        .build();
+                let mut score = player_data.score;
+                let mut shown_score = None;
+                world.spawn((vec![Action::when_with(
+                    self.score_changed.clone(),
+                    move |delta: ScoreDelta, _, world| {
+                        if delta.player != i {
+                            return;
+                        }
+                        score += delta.points;
 
-                        world.spawn((text, Action::despawn_self_when(score_changed_clone.clone())));
+                        if let Some(text) = shown_score.take() {
+                            let _ = world.despawn(text);
+                        }
+                        let text = text::Builder::new(format!("score: {score}"), score_position)
+                            .with_color(Color::BLACK)
+                            .with_scale(viewport.height(), 2160)
+                            .build();
+                        shown_score = Some(world.spawn((text,)));
                     },
                 )],));
 
-                // trigger shot event again, so that the magazine gets a chance to draw itself
-                reload_event.trigger();
+                // the gun has a death sound of its own, it plays when the player hits
+                let death = gun_sounds.of_player(i).death;
+                world.spawn((Action::when_with(
+                    self.shot_fired.clone(),
+                    move |shot: ShotFired, _, world| {
+                        if shot.shooter == i && shot.victim.is_some() {
+                            world.spawn((PlaySound::new(death),));
+                        }
+                    },
+                ),));
+
+                // trigger once, so that the magazine draws itself
+                self.magazine_changed.trigger_with(MagazineChanged {
+                    player: i,
+                    magazine_status: player_data.magazine_status,
+                    by_shot: false,
+                });
 
                 // trigger once so score is visible at the beginning
-                score_changed.trigger();
-
-                shoot_events.push(shoot_event.clone());
-                reload_events.push(reload_event.clone());
-                score_changed_events.push(score_changed.clone());
+                self.score_changed.trigger_with(ScoreDelta {
+                    player: i,
+                    points: 0,
+                });
             }
         }
 
-        game_time.resume();
-
-        loop {
-            if game_end_event.consume_all() > 0 {
-                return player_datas;
-            }
-
-            let mut shooter = None;
-            if let Ok(message) = gui_context.comm().try_recv_from_serial() {
-                let mut lock = player_datas.lock().unwrap();
-                let player_id = lock
-                    .iter_mut()
-                    .enumerate()
-                    .find(|(_, data)| data.sensortag_id == message.sensortag_id);
-                if let Some((player_id, data)) = player_id {
-                    match message.kind {
-                        SerialToGuiKind::Reload => {
-                            sdl2::mixer::Channel::all().play(&reload_sounds[player_id], 0).unwrap();
-
-                            data.magazine_status = MagazineStatus {
-                                ammo: message.ammo,
-                                ammo_max: message.ammo_max,
-                            };
-                            reload_events[player_id].trigger();
-                        }
-                        SerialToGuiKind::Shot => {
-                            let is_dry_shot = message.ammo == 0 && lock[player_id].magazine_status.ammo == 0;
-
-                            lock[player_id].magazine_status = MagazineStatus {
-                                ammo: message.ammo,
-                                ammo_max: message.ammo_max,
-                            };
+        self.scene_snapshot = ctx.gui_context.safe_flashing().then(|| {
+            texture_creator
+                .create_texture_target(None, viewport.width(), viewport.height())
+                .unwrap()
+        });
 
-                            if is_dry_shot {
-                                sdl2::mixer::Channel::all().play(&dry_shot_sound, 0).unwrap();
-                            } else  {
-                                sdl2::mixer::Channel::all().play(&shoot_sounds[player_id], 0).unwrap();
+        self.game_time.resume();
+        Ok(())
+    }
 
-                                shooter = Some((player_id, message.sensortag_id));
-                            }
+    fn update(
+        &mut self,
+        ctx: &mut SceneContext<'_, 'sdl>,
+        events: &[SdlEvent],
+    ) -> Transition<'sdl> {
+        if self.game_end_event.consume_all() > 0 {
+            let player_datas = std::mem::take(&mut self.player_datas);
+            return Transition::Switch(Box::new(Scoreboard::new(player_datas)));
+        }
 
-                            shoot_events[player_id].trigger();
+        for event in events {
+            if let SdlEvent::KeyDown {
+                keycode: Some(Keycode::F3),
+                repeat: false,
+                ..
+            } = event
+            {
+                self.oscilloscope.toggle();
+            }
+        }
+        self.oscilloscope.update(ctx.gui_context);
+
+        let gui_context = &mut *ctx.gui_context;
+        let viewport = ctx.viewport;
+        let Game {
+            player_datas,
+            world,
+            game_time,
+            shot_fired,
+            score_changed,
+            magazine_changed,
+            gun_sounds,
+            hitreg_dead_notice_shown,
+            scene_snapshot,
+            pending_shots,
+            flashing,
+            flashing_finished,
+            flash_target,
+            shown_shots,
+            ..
+        } = self;
+        let gun_sounds = gun_sounds.as_ref().unwrap();
+
+        // shots that come in while another one is flashed wait for their turn
+        while let Some(message) = gui_context.comm().try_recv_from_serial() {
+            let player_id = player_datas
+                .iter_mut()
+                .enumerate()
+                .find(|(_, data)| data.sensortag_id == message.sensortag_id);
+            if let Some((player_id, data)) = player_id {
+                let magazine_status = MagazineStatus {
+                    ammo: message.ammo,
+                    ammo_max: message.ammo_max,
+                };
+                match message.kind {
+                    SerialToGuiKind::Reload => {
+                        world.spawn((PlaySound::new(gun_sounds.of_player(player_id).reload),));
+
+                        data.magazine_status = magazine_status;
+                        magazine_changed.trigger_with(MagazineChanged {
+                            player: player_id,
+                            magazine_status,
+                            by_shot: false,
+                        });
+                    }
+                    SerialToGuiKind::Shot => {
+                        let is_dry_shot = message.ammo == 0 && data.magazine_status.ammo == 0;
+
+                        data.magazine_status = magazine_status;
+
+                        if is_dry_shot {
+                            world
+                                .spawn((PlaySound::new(gun_sounds.of_player(player_id).dry_shot),));
+                            shot_fired.trigger_with(ShotFired {
+                                shooter: player_id,
+                                victim: None,
+                            });
+                        } else {
+                            world.spawn((PlaySound::new(gun_sounds.of_player(player_id).shot),));
+
+                            pending_shots.push_back((
+                                player_id,
+                                message.sensortag_id,
+                                ShotTrace::taken(message.stamps, Instant::now()),
+                            ));
                         }
+
+                        magazine_changed.trigger_with(MagazineChanged {
+                            player: player_id,
+                            magazine_status,
+                            by_shot: true,
+                        });
                     }
                 }
             }
+        }
+
+        if flashing.is_none()
+            && let Some((player_id, sensortag_id, mut shot_trace)) = pending_shots.pop_front()
+        {
+            shot_trace.flash_started(Instant::now());
+            let style = FlashStyle {
+                backdrop: scene_snapshot.as_ref(),
+                ..gui_context.flash_style()
+            };
+
+            game_time.pause();
+            let sequence = FlashingSequence::start(
+                gui_context,
+                world,
+                *flash_target,
+                sensortag_id,
+                true,
+                &style,
+                flashing_finished.clone(),
+            );
+            *flashing = Some((player_id, shot_trace, sequence));
+        }
 
-            if let Some((player_id, sensortag_id)) = shooter {
-                if let Some(victim_id) = systems::flashing_sequence::run(
-                    gui_context,
-                    &mut world,
-                    true,
-                    &mut game_time,
-                    sensortag_id,
-                ) {
-                    sdl2::mixer::Channel::all().play(&death_sounds[player_id], 0).unwrap();
+        if let Some((_, _, sequence)) = flashing {
+            let style = FlashStyle {
+                backdrop: scene_snapshot.as_ref(),
+                ..gui_context.flash_style()
+            };
+            sequence.advance(gui_context, world, &style);
+        }
 
+        if flashing_finished.consume_all() > 0
+            && let Some((player_id, mut shot_trace, mut sequence)) = flashing.take()
+        {
+            shot_trace.result_arrived(Instant::now());
+            game_time.resume();
+
+            let hit = match sequence.take_result().unwrap() {
+                Ok(Outcome::Hit(victim_id)) => Ok(victim_id.map(|victim_id| (victim_id, None))),
+                Ok(Outcome::AimPoint(aim_point)) => Ok(aim_point.and_then(|aim_point| {
+                    spawn_bullet_hole(world, aim_point, viewport);
+                    systems::flashing_sequence::trigger_hit_at(world, aim_point)
+                        .map(|victim_id| (victim_id, Some(aim_point)))
+                })),
+                Err(e) => Err(e),
+            };
+
+            let victim = match &hit {
+                Ok(Some((victim_id, _))) => Some(*victim_id),
+                _ => None,
+            };
+            shot_fired.trigger_with(ShotFired {
+                shooter: player_id,
+                victim,
+            });
+
+            match hit {
+                Ok(Some((victim_id, aim_point))) => {
                     let victim = world.entity(victim_id).unwrap();
                     let hitbox = victim.get::<&Hitbox>().unwrap();
+                    let texture = victim.get::<&Texture>();
+
+                    let points = match aim_point {
+                        // the closer to the center of the chicken, the more points
+                        Some(aim_point) => {
+                            // measured on the shape, so a chicken with its wings spread
+                            // has its center in the body and not somewhere in the air
+                            let spans = hitbox.spans(texture.as_deref());
+                            let (center_x, center_y) = hitbox_shape::centroid(&spans)
+                                .unwrap_or((aim_point.x as f32, aim_point.y as f32));
+                            let dx = aim_point.x as f32 - center_x;
+                            let dy = aim_point.y as f32 - center_y;
+                            // radius of a circle with the same area as the shape
+                            let radius = (hitbox_shape::area(&spans).max(1) as f32
+                                / std::f32::consts::PI)
+                                .sqrt();
+                            let closeness = 1.0 - (dx * dx + dy * dy).sqrt() / radius;
+
+                            5 + (15.0 * closeness.clamp(0.0, 1.0)) as u32
+                        }
+                        // the smaller the chicken, the more points
+                        None => {
+                            let score = (hitbox.width as f32 / 200.0)
+                                / (viewport.height() as f32 / 1440.0)
+                                - 0.5;
+                            20_u32.saturating_sub((score * 5.0) as u32)
+                        }
+                    };
 
-                    let score =
-                        (hitbox.width as f32 / 200.0) / (viewport.height() as f32 / 1440.0) - 0.5;
-
-                    player_datas.lock().unwrap()[player_id].score +=
-                        20_u32.saturating_sub((score * 5.0) as u32);
-                    score_changed_events[player_id].trigger();
+                    player_datas[player_id].score += points;
+                    score_changed.trigger_with(ScoreDelta {
+                        player: player_id,
+                        points,
+                    });
                 }
-
-                while let Ok(message) = gui_context.comm().try_recv_from_serial() {
-                    let mut lock = player_datas.lock().unwrap();
-                    let player_id = lock
-                        .iter_mut()
-                        .enumerate()
-                        .find(|(_, data)| data.sensortag_id == message.sensortag_id);
-                    if let Some((player_id, data)) = player_id {
-                        match message.kind {
-                            SerialToGuiKind::Reload => {
-                                sdl2::mixer::Channel::all().play(&reload_sounds[player_id], 0).unwrap();
-
-                                data.magazine_status = MagazineStatus {
-                                    ammo: message.ammo,
-                                    ammo_max: message.ammo_max,
-                                };
-                                reload_events[player_id].trigger();
-                            }
-                            SerialToGuiKind::Shot => {
-                                let mut locked = player_datas.lock().unwrap();
-                                let is_dry_shot = message.ammo == 0 && locked[player_id].magazine_status.ammo == 0;
-
-                                locked[player_id].magazine_status = MagazineStatus {
-                                    ammo: message.ammo,
-                                    ammo_max: message.ammo_max,
-                                };
-
-                                if is_dry_shot {
-                                    sdl2::mixer::Channel::all().play(&dry_shot_sound, 0).unwrap();
-                                } else  {
-                                    sdl2::mixer::Channel::all().play(&shoot_sounds[player_id], 0).unwrap();
-                                }
-
-                                shoot_events[player_id].trigger();
-                            }
-                        }
+                Ok(None) => {}
+                Err(HitregError::TimedOut) => {
+                    warn!(target: "Gui Thread", "hit registration timed out, counting the shot as a miss");
+                }
+                Err(HitregError::Disconnected) => {
+                    if !*hitreg_dead_notice_shown {
+                        error!(target: "Gui Thread", "hit registration is not running anymore");
+                        world.spawn((text::Builder::new(
+                            "Hit registration stopped working!".to_string(),
+                            PointWithAlignment {
+                                point: Point {
+                                    x: (viewport.width() / 2) as i32,
+                                    y: viewport.height() as i32,
+                                },
+                                v_align: VAlign::Bottom,
+                                h_align: HAlign::Center,
+                            },
+                        )
+                        .with_color(Color::RED)
+                        .with_scale(viewport.height(), 2160)
+                        .build(),));
+                        *hitreg_dead_notice_shown = true;
                     }
                 }
             }
 
-            let frame_start = SystemTime::now();
-
-            gui_context.canvas().set_draw_color(Color::BLACK);
-            gui_context.canvas().clear();
-
-            systems::work_actions::run(&mut world);
-            systems::work_timers::run(&mut world, &mut game_time);
-            systems::update_movements::run(&mut world, &mut game_time);
-            systems::update_animated_textures::run(&mut world, &mut game_time);
-            systems::draw_textures::run(gui_context.canvas(), &mut world, &mut resources);
-            systems::draw_texts::run(
-                gui_context.canvas(),
-                &mut world,
-                &mut resources,
-                &texture_creator,
-            );
+            shown_shots.push((player_id, shot_trace));
+        }
 
-            gui_context.canvas().present();
+        if flashing.is_none() {
+            systems::work_actions::run(world);
+            systems::work_timers::run(world, game_time);
+            systems::update_movements::run(world, game_time);
+            systems::update_animated_textures::run(world, game_time);
+        }
+        // sounds do not wait for the flashing, the shot is heard right when it is fired
+        systems::play_sounds::run(world, &mut ctx.resources.audio);
+        Transition::Stay
+    }
 
-            let frame_end = SystemTime::now();
-            let frame_duration = frame_end.duration_since(frame_start).unwrap();
-            let wait_duration =
-                Duration::from_millis(33_u128.saturating_sub(frame_duration.as_millis()) as u64);
+    fn draw(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        match &mut self.scene_snapshot {
+            Some(scene_snapshot) => {
+                ctx.gui_context
+                    .canvas()
+                    .with_texture_canvas(scene_snapshot, |canvas| {
+                        draw_scene(
+                            &mut SdlRenderer::new(canvas, ctx.resources, ctx.texture_creator),
+                            &mut self.world,
+                        )
+                    })
+                    .unwrap();
+                ctx.gui_context
+                    .canvas()
+                    .copy(scene_snapshot, None, None)
+                    .unwrap();
+            }
+            None => draw_scene(&mut ctx.renderer(), &mut self.world),
+        }
+
+        self.oscilloscope
+            .draw(ctx.gui_context, ctx.resources, ctx.texture_creator);
+    }
 
-            trace!(target: "Gui Thread", "frame took {}ms", frame_duration.as_millis());
-            thread::sleep(wait_duration);
+    fn presented(&mut self, _ctx: &mut SceneContext<'_, 'sdl>, at: Instant) {
+        for (player_id, shot_trace) in self.shown_shots.drain(..) {
+            let took = shot_trace.shown(at);
+            info!(target: "Gui Thread", "shot of player {} took {}ms from trigger to result on screen", player_id, took.as_millis());
         }
     }
+
+    fn game_time(&mut self) -> Option<&mut Stopwatch> {
+        // a flashing sequence cannot wait, hitreg would time out
+        self.flashing.is_none().then_some(&mut self.game_time)
+    }
+
+    fn receives_from_serial(&self) -> bool {
+        true
+    }
+
+    fn presents_itself(&self) -> bool {
+        // the flashing sequence has the screen, present waits for vsync so there is
+        // no need to sleep
+        self.flashing.is_some()
+    }
+}
+
+fn spawn_bullet_hole(world: &mut World, aim_point: Point, viewport: Rect) {
+    let bullet_hole_gone = Event::default();
+    world.spawn((bullet_hole_gone.clone().trigger_after(BULLET_HOLE_DURATION),));
+    world.spawn((
+        text::Builder::new("x".to_string(), PointWithAlignment::new_center(aim_point))
+            .with_color(Color::BLACK)
+            .with_scale(viewport.height(), 2160)
+            .build(),
+        Action::despawn_self_when(bullet_hole_gone),
+    ));
+}
+
+fn draw_scene(renderer: &mut impl Renderer, world: &mut World) {
+    renderer.set_draw_color(Color::BLACK);
+    renderer.clear();
+
+    systems::draw_textures::run(renderer, world);
+    systems::draw_texts::run(renderer, world);
 }
 
 impl SpawnChickenAction for Action {}
@@ -368,14 +643,27 @@ trait SpawnChickenAction {
         event: Event,
         viewport: Rect,
         out_of_viewport_event: Event,
+        chicken: Chicken,
     ) -> Action {
         Action::when(event, move |_entity_id, world| {
-            spawn_random_chickens(viewport, 1, world, out_of_viewport_event.clone());
+            spawn_random_chickens(
+                viewport,
+                1,
+                world,
+                out_of_viewport_event.clone(),
+                chicken.clone(),
+            );
         })
     }
 }
 
-fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewport_event: Event) {
+fn spawn_random_chickens(
+    viewport: Rect,
+    n: u32,
+    world: &mut World,
+    out_of_viewport_event: Event,
+    chicken: Chicken,
+) {
     for _ in 0..n {
         let rand_big_range = rand::rng().random_range(-5..=5);
         let rand_small_neg_range = rand::rng().random_range(-5..=-1);
@@ -450,10 +738,9 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
 
         let my_out_of_viewport_event = Event::default();
 
-        let mut texture_builder = texture::Builder::new(0, position)
+        let mut texture_builder = texture::Builder::animated(&chicken.flying, position)
             .with_scale(rand_scale)
-            .with_num_frames(13)
-            .with_frame_advance_interval(Duration::from_millis(rand::rng().random_range(50..150)))
+            .with_playback_speed(rand::rng().random_range(0.67..2.0))
             .on_outside_viewport(my_out_of_viewport_event.clone());
 
         if (movement.f)(10000).x > 0 {
@@ -463,6 +750,7 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
         let hit_event = Event::default();
         let texture = texture_builder.build();
         let out_of_viewport_event_clone = out_of_viewport_event.clone();
+        let chicken_clone = chicken.clone();
         world.spawn((
             movement,
             texture,
@@ -471,6 +759,7 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
                 (200.0 * rand_scale) as u32,
                 (200.0 * rand_scale) as u32,
             )
+            .with_shape(chicken.shape.clone())
             .on_hit(hit_event.clone())
             .build(),
             vec![
@@ -486,11 +775,8 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
                             let entity = world.entity(entity_id).unwrap();
                             let old_texture = entity.get::<&Texture>().unwrap();
 
-                            texture::Builder::new(2, old_texture.position)
+                            texture::Builder::animated(&chicken_clone.dying, old_texture.position)
                                 .with_scale(old_texture.scale)
-                                .with_num_frames(8)
-                                .with_animation_end_behavior(AnimationEndBehavior::Freeze)
-                                .with_frame_advance_interval(Duration::from_millis(99))
                                 .on_outside_viewport(out_of_frame_event.clone())
                                 .build()
                         };
@@ -510,6 +796,7 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
                                     out_of_frame_event,
                                     viewport,
                                     out_of_viewport_event_clone.clone(),
+                                    chicken_clone.clone(),
                                 ),
                             ],
                         ));
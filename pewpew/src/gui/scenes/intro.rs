@@ -1,60 +1,56 @@
+use crate::gui::engine::assets::AssetError;
+use crate::gui::engine::components::play_sound::PlaySound;
 use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
 use crate::gui::engine::components::{Point, text, texture};
 use crate::gui::engine::event::Event;
-use crate::gui::engine::gui_context::GuiContext;
-use crate::gui::engine::resources::Resources;
+use crate::gui::engine::render::DrawTarget;
 use crate::gui::engine::stopwatch::Stopwatch;
 use crate::gui::engine::systems;
+use crate::gui::scenes::pregame::Pregame;
+use crate::gui::scenes::scene::{Scene, SceneContext, Transition};
 use hecs::World;
-use sdl2::image::LoadTexture;
+use sdl2::event::Event as SdlEvent;
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use std::path::Path;
-use std::thread;
-use std::time::{Duration, SystemTime};
-use sdl2::mixer::Chunk;
 
-pub fn run(gui_context: &mut GuiContext) {
-    let viewport = {
-        let (width, height) = gui_context.canvas().output_size().unwrap();
-        Rect::new(0, 0, width, height)
-    };
-    let texture_creator = gui_context.canvas().texture_creator();
-    let ttf_context = sdl2::ttf::init().unwrap();
+pub struct Intro {
+    world: World,
+    game_time: Stopwatch,
+    intro_done_event: Event,
+}
+
+impl Default for Intro {
+    fn default() -> Self {
+        Intro {
+            world: World::new(),
+            game_time: Stopwatch::new_paused(),
+            intro_done_event: Event::default(),
+        }
+    }
+}
 
-    let intro = Chunk::from_file("res/audio/valve_intro.mp3").unwrap();
-    sdl2::mixer::Channel::all().play(&intro, 0).unwrap();
-    {
-        let default_font = ttf_context
-            .load_font("res/fonts/Walter_Turncoat/WalterTurncoat-Regular.ttf", 128)
-            .unwrap();
-        let mut resources = Resources::new(default_font);
+impl<'sdl> Scene<'sdl> for Intro {
+    fn enter(&mut self, ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        let viewport = ctx.viewport;
 
-        resources.images.push(
-            texture_creator
-                .load_texture(Path::new("res/images/intro_huhn_in_hole.png"))
-                .unwrap(),
-        ); // https://onlinetools.com/image/remove-specific-color-from-image
+        let music = ctx.resources.audio.sound("intro")?;
+        self.world.spawn((PlaySound::new(music),));
 
-        let mut world = World::new();
-        let mut game_time = Stopwatch::new_paused();
+        // https://onlinetools.com/image/remove-specific-color-from-image
+        let chicken = ctx.resources.assets.sprite("intro_chicken", "pop_up")?;
 
         let position = PointWithAlignment::new_center(Point {
             x: (viewport.width() / 2) as i32,
             y: (viewport.height() / 2) as i32,
         });
-        let mut intro_done_event = Event::default();
-        let texture = texture::Builder::new(0, position)
-            .with_num_frames(14)
+        let texture = texture::Builder::animated(&chicken, position)
             .with_vertical_flip()
             .with_scale(viewport.height() as f32 / 360.0)
-            .with_frame_advance_interval(Duration::from_millis(600))
-            .on_animation_end(intro_done_event.clone())
+            .on_animation_end(self.intro_done_event.clone())
             .build();
 
-        world.spawn((texture,));
+        self.world.spawn((texture,));
 
-        world.spawn((text::Builder::new(
+        self.world.spawn((text::Builder::new(
             "Moorhuhn".to_string(),
             PointWithAlignment::new_center(Point {
                 x: (viewport.width() / 2) as i32,
@@ -64,43 +60,39 @@ pub fn run(gui_context: &mut GuiContext) {
         .with_scale(viewport.height(), 1440)
         .build(),));
 
-        game_time.resume();
-
-        gui_context.canvas().set_draw_color(Color::BLACK);
-        gui_context.canvas().clear();
-        gui_context.canvas().present();
+        self.game_time.resume();
 
-        {
-            loop {
-                if intro_done_event.consume_all() > 0 {
-                    break;
-                }
-
-                let frame_start = SystemTime::now();
+        ctx.gui_context.canvas().set_draw_color(Color::BLACK);
+        ctx.gui_context.canvas().clear();
+        ctx.gui_context.canvas().present();
+        Ok(())
+    }
 
-                gui_context.canvas().set_draw_color(Color::BLACK);
-                gui_context.canvas().clear();
+    fn update(
+        &mut self,
+        ctx: &mut SceneContext<'_, 'sdl>,
+        _events: &[SdlEvent],
+    ) -> Transition<'sdl> {
+        if self.intro_done_event.consume_all() > 0 {
+            return Transition::Switch(Box::new(Pregame::default()));
+        }
 
-                systems::work_timers::run(&mut world, &mut game_time);
-                systems::update_animated_textures::run(&mut world, &mut game_time);
-                systems::draw_textures::run(gui_context.canvas(), &mut world, &mut resources);
-                systems::draw_texts::run(
-                    gui_context.canvas(),
-                    &mut world,
-                    &mut resources,
-                    &texture_creator,
-                );
+        systems::work_timers::run(&mut self.world, &mut self.game_time);
+        systems::update_animated_textures::run(&mut self.world, &mut self.game_time);
+        systems::play_sounds::run(&mut self.world, &mut ctx.resources.audio);
+        Transition::Stay
+    }
 
-                gui_context.canvas().present();
+    fn draw(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        let mut renderer = ctx.renderer();
+        renderer.set_draw_color(Color::BLACK);
+        renderer.clear();
 
-                let frame_end = SystemTime::now();
-                let frame_duration = frame_end.duration_since(frame_start).unwrap();
-                let wait_duration = Duration::from_millis(
-                    33_u128.saturating_sub(frame_duration.as_millis()) as u64,
-                );
+        systems::draw_textures::run(&mut renderer, &mut self.world);
+        systems::draw_texts::run(&mut renderer, &mut self.world);
+    }
 
-                thread::sleep(wait_duration);
-            }
-        }
+    fn game_time(&mut self) -> Option<&mut Stopwatch> {
+        Some(&mut self.game_time)
     }
 }
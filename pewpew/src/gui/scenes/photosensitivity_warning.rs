@@ -0,0 +1,54 @@
+use crate::gui::engine::assets::AssetError;
+use crate::gui::scenes::common::message::Message;
+use crate::gui::scenes::intro::Intro;
+use crate::gui::scenes::scene::{Scene, SceneContext, Transition};
+use log::info;
+use sdl2::event::Event;
+use std::time::{Duration, Instant};
+
+const WARNING_DURATION: Duration = Duration::from_secs(8);
+
+/// shown once at startup, hit registration flashes the screen for every shot
+#[derive(Default)]
+pub struct PhotosensitivityWarning {
+    /// the warning and when it is over, set when the scene is entered
+    shown: Option<(Message, Instant)>,
+}
+
+impl<'sdl> Scene<'sdl> for PhotosensitivityWarning {
+    fn enter(&mut self, ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        let safe_flashing = ctx.gui_context.safe_flashing();
+        let flashing = if safe_flashing {
+            "Safe flashing is on: reduced contrast, at most 3 flashes per second."
+        } else {
+            "The whole screen flashes black and white for every shot."
+        };
+        info!(target: "Gui Thread", "showing photosensitivity warning, safe flashing is {}", if safe_flashing { "on" } else { "off" });
+
+        let message = Message::new(
+            &[
+                "Warning!",
+                "This game uses flashing lights to find out where a gun is aimed.",
+                flashing,
+                "It may trigger seizures in people with photosensitive epilepsy.",
+            ],
+            ctx.viewport,
+        );
+        self.shown = Some((message, Instant::now() + WARNING_DURATION));
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &mut SceneContext<'_, 'sdl>, _events: &[Event]) -> Transition<'sdl> {
+        let (_, until) = self.shown.as_ref().unwrap();
+        if Instant::now() >= *until {
+            Transition::Switch(Box::new(Intro::default()))
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        let (message, _) = self.shown.as_mut().unwrap();
+        message.draw(ctx);
+    }
+}
@@ -1,49 +1,85 @@
-use crate::comm::message::SerialToGuiKind;
+use crate::comm::message::{SerialToGuiKind, Worker, WorkerHealth};
+use crate::gui::engine::assets::AssetError;
+use crate::gui::engine::audio::GunSoundAssignment;
 use crate::gui::engine::components::action::Action;
 use crate::gui::engine::components::movement::Movement;
+use crate::gui::engine::components::play_sound::PlaySound;
 use crate::gui::engine::components::point_with_alignment::{HAlign, PointWithAlignment, VAlign};
 use crate::gui::engine::components::{Point, text, texture};
 use crate::gui::engine::event::Event;
-use crate::gui::engine::gui_context::GuiContext;
-use crate::gui::engine::resources::Resources;
+use crate::gui::engine::render::DrawTarget;
+use crate::gui::engine::sprite_sheet::Sprite;
 use crate::gui::engine::stopwatch::Stopwatch;
 use crate::gui::engine::systems;
-use crate::gui::scenes::common::PlayerData;
+use crate::gui::scenes::calibration::Calibration;
 use crate::gui::scenes::common::magazine::SpawnMagazineAction;
+use crate::gui::scenes::common::oscilloscope::Oscilloscope;
 use crate::gui::scenes::common::scenery::Scenery;
-use crate::gui::scenes::load_all_textures;
+use crate::gui::scenes::common::{MagazineChanged, PlayerData, ShotFired};
+use crate::gui::scenes::game::Game;
+use crate::gui::scenes::scene::{Scene, SceneContext, Transition};
 use crate::serial::packet::MagazineStatus;
-use hecs::World;
-use log::trace;
+use hecs::{Entity, World};
 use rand::Rng;
+use sdl2::event::Event as SdlEvent;
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::BlendMode;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::{Duration, Instant};
-use sdl2::mixer::Chunk;
-
-pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
-    let viewport = {
-        let (width, height) = gui_context.canvas().output_size().unwrap();
-        Rect::new(0, 0, width, height)
-    };
-    let texture_creator = gui_context.canvas().texture_creator();
-    let ttf_context = sdl2::ttf::init().unwrap();
-
-    let shoot_sounds = [Chunk::from_file("res/../res/audio/gun-shot-359196.mp3").unwrap(), Chunk::from_file("res/../res/audio/glock19-18535.mp3").unwrap()];
-    let reload_sounds = [Chunk::from_file("res/../res/audio/ak47_boltpull.mp3").unwrap(), Chunk::from_file("res/../res/audio/_en_sound_glock18-slideforward_.mp3").unwrap()];
-    let dry_shot_sound = Chunk::from_file("res/../res/audio/dry-fire-364846.mp3").unwrap();
-    {
-        let default_font = ttf_context
-            .load_font("res/fonts/Walter_Turncoat/WalterTurncoat-Regular.ttf", 128)
-            .unwrap();
-        let mut resources = Resources::new(default_font);
-        let texture_id_map = load_all_textures(&mut resources, &texture_creator).unwrap();
-
-        let mut world = World::new();
-        let mut game_time = Stopwatch::new_paused();
+use std::time::Duration;
+
+pub struct Pregame {
+    world: World,
+    game_time: Stopwatch,
+
+    /// the players in the order they joined, handed to the game
+    player_datas: Vec<PlayerData>,
+    shot_fired: Event<ShotFired>,
+    magazine_changed: Event<MagazineChanged>,
+    countdown_finished_event: Event,
+    gun_sounds: Option<GunSoundAssignment>,
+
+    // F3 shows what the sensortags see, for pointing the guns at the screen during setup
+    oscilloscope: Oscilloscope,
+
+    /// tells which worker threads are not running, see supervisor
+    worker_health_text: Option<Entity>,
+    /// what worker_health_text was made from
+    shown_worker_health: HashMap<Worker, WorkerHealth>,
+}
+
+impl Default for Pregame {
+    fn default() -> Self {
+        Pregame {
+            world: World::new(),
+            game_time: Stopwatch::new_paused(),
+            player_datas: Vec::new(),
+            shot_fired: Event::new(),
+            magazine_changed: Event::new(),
+            countdown_finished_event: Event::default(),
+            gun_sounds: None,
+            oscilloscope: Oscilloscope::default(),
+            worker_health_text: None,
+            shown_worker_health: HashMap::new(),
+        }
+    }
+}
+
+impl<'sdl> Scene<'sdl> for Pregame {
+    fn enter(&mut self, ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        let viewport = ctx.viewport;
+        let resources = &mut *ctx.resources;
+
+        self.gun_sounds = Some(resources.audio.gun_sound_assignment()?);
+        let assets = &mut resources.assets;
+        let chicken = assets.sprite("chicken", "fly")?;
+        let full_shell = assets.sprite("ammo", "full")?;
+        let ejected_shell = assets.sprite("ammo", "eject")?;
+        let ammo = full_shell.image;
+
+        let world = &mut self.world;
 
         let scenery_scale = viewport.height() as f32 / 720.0;
         world.spawn(Scenery::new(
@@ -52,9 +88,8 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                 y: (viewport.height() / 2) as i32,
             }),
             scenery_scale,
-            &mut resources,
-            &texture_creator,
-        ));
+            resources,
+        )?);
 
         // spawn chickens
         {
@@ -67,6 +102,7 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
             world.spawn((Action::spawn_random_chicken_when(
                 spawn_new_chicken_event.clone(),
                 viewport,
+                chicken,
                 spawn_new_chicken_event.clone(),
             ),));
 
@@ -75,9 +111,6 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
             }
         }
 
-        let player_datas = Arc::new(Mutex::new(Vec::new()));
-        let mut shoot_events = Vec::new();
-        let mut reload_events = Vec::new();
         let player_names = ["Player 1", "Player 2", "Player 3", "Player 4"];
         let player_colors = [Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW];
 
@@ -85,21 +118,18 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
         let some_player_joined_event = Event::default();
         let countdown_tick_event = Event::default();
         let start_countdown_event = Event::default();
-        let mut countdown_finished_event = Event::default();
+        let countdown_finished_event = self.countdown_finished_event.clone();
 
         let num_players = Arc::new(Mutex::new(0));
         let countdown_seconds_left = Arc::new(Mutex::new(COUNTDOWN_START_VALUE as i32));
 
         const COUNTDOWN_START_VALUE: u8 = 15;
-        let ammo_width = resources.images[texture_id_map["ammo.png"]].query().width;
+        let ammo_width = resources.assets.image(ammo).query().width;
         let magazine_scale = 0.15 * viewport.height() as f32 / ammo_width as f32;
 
         // spawn players
         {
             for i in 0..2 {
-                let shoot_event = Event::default();
-                let reload_event = Event::default();
-
                 let position = match i {
                     0 => PointWithAlignment {
                         point: Point { x: 0, y: 0 },
@@ -139,30 +169,51 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                         format!("{} missing", player_names[i]).to_string(),
                         position,
                     )
-                        .with_color(player_colors[i])
-                        .with_scale(viewport.height(), 1440)
-                        .build(),
-                    Action::despawn_self_when(shoot_event.clone()),
-                ));
-
-                // spawn magazine
-                // and despawn spawner, so that spawn magazine action only runs once
-                world.spawn((vec![
-                    Action::spawn_magazine_when(
-                        shoot_event.clone(),
-                        reload_event.clone(),
-                        player_datas.clone(),
-                        i,
-                        position,
-                        magazine_scale,
-                        texture_id_map["ammo.png"],
-                        texture_id_map["ammo.png"],
+                    .with_color(player_colors[i])
+                    .with_scale(viewport.height(), 1440)
+                    .build(),
+                    Action::when_with(
+                        self.shot_fired.clone(),
+                        move |shot: ShotFired, me, world| {
+                            if shot.shooter == i {
+                                let _ = world.despawn(me);
+                            }
+                        },
                     ),
-                    Action::despawn_self_when(shoot_event.clone()),
-                ],));
+                ));
 
-                shoot_events.push(shoot_event.clone());
-                reload_events.push(reload_event.clone());
+                // spawn magazine, the spawner despawns itself, so that it only runs once
+                world.spawn((Action::spawn_magazine_when(
+                    self.magazine_changed.clone(),
+                    i,
+                    position,
+                    magazine_scale,
+                    full_shell.clone(),
+                    ejected_shell.clone(),
+                ),));
+
+                // the first shot of the player joins the game
+                let mut some_player_joined_event = some_player_joined_event.clone();
+                let mut all_players_joined_event = all_players_joined_event.clone();
+                let num_players = num_players.clone();
+                let mut joined = false;
+                world.spawn((Action::when_with(
+                    self.shot_fired.clone(),
+                    move |shot: ShotFired, me, world| {
+                        if joined || shot.shooter != i {
+                            return;
+                        }
+                        joined = true;
+                        let _ = world.despawn(me);
+
+                        some_player_joined_event.trigger();
+                        let mut lock = num_players.lock().unwrap();
+                        *lock += 1;
+                        if *lock == 4 {
+                            all_players_joined_event.trigger();
+                        }
+                    },
+                ),));
             }
         }
 
@@ -174,31 +225,11 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                     y: viewport.height() as i32 / 2,
                 }),
             )
-                .with_scale(viewport.height(), 1440)
-                .build(),
+            .with_scale(viewport.height(), 1440)
+            .build(),
             Action::despawn_self_when(all_players_joined_event.clone()),
         ));
 
-        for shoot_event in &mut shoot_events {
-            let num_players_clone = num_players.clone();
-            let all_players_joined_event_clone = all_players_joined_event.clone();
-            world.spawn((vec![
-                Action::trigger_other_event_when(
-                    shoot_event.clone(),
-                    some_player_joined_event.clone(),
-                )
-                    .oneshot(),
-                Action::despawn_self_when(shoot_event.clone()),
-                Action::when_oneshot(shoot_event.clone(), move |_, _| {
-                    let mut lock = num_players_clone.lock().unwrap();
-                    *lock += 1;
-                    if *lock == 4 {
-                        all_players_joined_event_clone.clone().trigger();
-                    }
-                }),
-            ],));
-        }
-
         // manages countdown text
         {
             let start_game_countdown_tick_event = countdown_tick_event.clone();
@@ -217,7 +248,7 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                                 "Game starts in {}..",
                                 countdown_seconds_left.lock().unwrap()
                             )
-                                .to_string(),
+                            .to_string(),
                             PointWithAlignment {
                                 point: Point {
                                     x: (viewport.width() / 2) as i32,
@@ -227,9 +258,9 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                                 h_align: HAlign::Center,
                             },
                         )
-                            .with_color(Color::WHITE)
-                            .with_scale(viewport.height(), 1440)
-                            .build(),
+                        .with_color(Color::WHITE)
+                        .with_scale(viewport.height(), 1440)
+                        .build(),
                         Action::despawn_self_when(start_game_countdown_tick_event.clone()),
                     ));
 
@@ -285,7 +316,7 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                 some_player_joined_event.clone(),
                 start_countdown_event.clone(),
             )
-                .oneshot(),
+            .oneshot(),
             Action::despawn_self_when(some_player_joined_event.clone()),
             Action::when(some_player_joined_event.clone(), move |_entity, world| {
                 world.spawn((vec![
@@ -293,107 +324,184 @@ pub fn run(gui_context: &mut GuiContext) -> Arc<Mutex<Vec<PlayerData>>> {
                         some_player_joined_event.clone(),
                         start_countdown_event.clone(),
                     )
-                        .oneshot(),
+                    .oneshot(),
                     Action::despawn_self_when(some_player_joined_event.clone()),
                 ],));
             }),
         ],));
 
-        game_time.resume();
+        self.game_time.resume();
+        Ok(())
+    }
 
-        loop {
-            if countdown_finished_event.consume_all() > 0 {
-                return player_datas;
+    fn update(
+        &mut self,
+        ctx: &mut SceneContext<'_, 'sdl>,
+        events: &[SdlEvent],
+    ) -> Transition<'sdl> {
+        if self.countdown_finished_event.consume_all() > 0 {
+            let player_datas = std::mem::take(&mut self.player_datas);
+            if ctx.gui_context.flash_timing().measured_latency.is_none() {
+                // first game on this setup, see how fast the flashing sequence can be
+                return Transition::Switch(Box::new(Calibration::new(player_datas)));
             }
+            return Transition::Switch(Box::new(Game::new(player_datas)));
+        }
 
-            if let Ok(message) = gui_context.comm().try_recv_from_serial() {
-                let player_id = {
-                    let mut locked = player_datas.lock().unwrap();
-                    if let Some((idx, _)) = locked
-                        .iter()
-                        .enumerate()
-                        .find(|(_, data)| data.sensortag_id == message.sensortag_id) {
-                        idx
-                    } else {
-                        let new_player_id = locked.len();
-                        locked.push(PlayerData {
-                            sensortag_id: message.sensortag_id,
-                            magazine_status: MagazineStatus {
-                                ammo: message.ammo,
-                                ammo_max: message.ammo_max,
-                            },
-                            score: 0,
-                        });
-                        new_player_id
-                    }
-                };
+        for event in events {
+            if let SdlEvent::KeyDown {
+                keycode: Some(Keycode::F3),
+                repeat: false,
+                ..
+            } = event
+            {
+                self.oscilloscope.toggle();
+            }
+        }
 
-                match message.kind {
-                    SerialToGuiKind::Reload => {
-                        sdl2::mixer::Channel::all().play(&reload_sounds[player_id], 0).unwrap();
+        let gui_context = &mut *ctx.gui_context;
+        let viewport = ctx.viewport;
+        let world = &mut self.world;
+        let worker_health_text = &mut self.worker_health_text;
+        let shown_worker_health = &mut self.shown_worker_health;
+        let player_datas = &mut self.player_datas;
+        let shot_fired = &mut self.shot_fired;
+        let magazine_changed = &mut self.magazine_changed;
+        let gun_sounds = self.gun_sounds.as_ref().unwrap();
+
+        self.oscilloscope.update(gui_context);
+
+        if *gui_context.worker_health() != *shown_worker_health {
+            *shown_worker_health = gui_context.worker_health().clone();
+            if let Some(text) = worker_health_text.take() {
+                world.despawn(text).unwrap();
+            }
 
-                        player_datas.lock().unwrap()[player_id].magazine_status = MagazineStatus {
-                            ammo: message.ammo,
-                            ammo_max: message.ammo_max,
-                        };
-                        reload_events[player_id].trigger();
-                    }
-                    SerialToGuiKind::Shot => {
-                        let mut locked = player_datas.lock().unwrap();
-                        let is_dry_shot = message.ammo == 0 && locked[player_id].magazine_status.ammo == 0;
-
-                        locked[player_id].magazine_status = MagazineStatus {
-                            ammo: message.ammo,
-                            ammo_max: message.ammo_max,
-                        };
-
-                        if is_dry_shot {
-                            sdl2::mixer::Channel::all().play(&dry_shot_sound, 0).unwrap();
-                        } else  {
-                            sdl2::mixer::Channel::all().play(&shoot_sounds[player_id], 0).unwrap();
+            let lines = gui_context
+                .worker_health()
+                .iter()
+                .filter_map(|(worker, health)| match health {
+                    WorkerHealth::Running => None,
+                    WorkerHealth::Stopped { reason, .. } => Some(format!(
+                        "{} stopped ({reason}), restarting..",
+                        match worker {
+                            Worker::Serial => "Serial",
+                            Worker::Hitreg => "Hitreg",
                         }
+                    )),
+                })
+                .collect::<Vec<_>>();
+            if !lines.is_empty() {
+                *worker_health_text = Some(
+                    world.spawn((text::Builder::new(
+                        lines.join(", "),
+                        PointWithAlignment {
+                            point: Point {
+                                x: viewport.width() as i32 / 2,
+                                y: viewport.height() as i32,
+                            },
+                            v_align: VAlign::Bottom,
+                            h_align: HAlign::Center,
+                        },
+                    )
+                    .with_color(Color::RED)
+                    .with_scale(viewport.height(), 4320)
+                    .build(),)),
+                );
+            }
+        }
 
-                        shoot_events[player_id].trigger();
-                    }
+        if let Some(message) = gui_context.comm().try_recv_from_serial() {
+            let magazine_status = MagazineStatus {
+                ammo: message.ammo,
+                ammo_max: message.ammo_max,
+            };
+            let player_id = if let Some((idx, _)) = player_datas
+                .iter()
+                .enumerate()
+                .find(|(_, data)| data.sensortag_id == message.sensortag_id)
+            {
+                idx
+            } else {
+                player_datas.push(PlayerData {
+                    sensortag_id: message.sensortag_id,
+                    magazine_status,
+                    score: 0,
+                });
+                player_datas.len() - 1
+            };
+
+            match message.kind {
+                SerialToGuiKind::Reload => {
+                    world.spawn((PlaySound::new(gun_sounds.of_player(player_id).reload),));
+
+                    player_datas[player_id].magazine_status = magazine_status;
+                    magazine_changed.trigger_with(MagazineChanged {
+                        player: player_id,
+                        magazine_status,
+                        by_shot: false,
+                    });
                 }
-            }
+                SerialToGuiKind::Shot => {
+                    let is_dry_shot =
+                        message.ammo == 0 && player_datas[player_id].magazine_status.ammo == 0;
+
+                    player_datas[player_id].magazine_status = magazine_status;
 
-            let frame_start = Instant::now();
-
-            gui_context.canvas().set_draw_color(Color::BLACK);
-            gui_context.canvas().clear();
-
-            systems::work_actions::run(&mut world);
-            systems::work_timers::run(&mut world, &mut game_time);
-            systems::update_movements::run(&mut world, &mut game_time);
-            systems::update_animated_textures::run(&mut world, &mut game_time);
-            systems::draw_textures::run(gui_context.canvas(), &mut world, &mut resources);
-
-            // make everything drawn up to this point appear slightly darker
-            gui_context.canvas().set_blend_mode(BlendMode::Blend);
-            gui_context
-                .canvas()
-                .set_draw_color(Color::RGBA(0, 0, 0, 150));
-            gui_context.canvas().fill_rect(viewport).unwrap();
-            gui_context.canvas().set_blend_mode(BlendMode::None);
-
-            systems::draw_texts::run(
-                gui_context.canvas(),
-                &mut world,
-                &mut resources,
-                &texture_creator,
-            );
-
-            gui_context.canvas().present();
-
-            let frame_end = Instant::now();
-            let frame_duration = frame_end.duration_since(frame_start);
-            let wait_duration =
-                Duration::from_millis(33_u128.saturating_sub(frame_duration.as_millis()) as u64);
-
-            trace!(target: "Gui Thread", "frame took {}ms", frame_duration.as_millis());
-            thread::sleep(wait_duration);
+                    let gun_sounds = gun_sounds.of_player(player_id);
+                    world.spawn((PlaySound::new(if is_dry_shot {
+                        gun_sounds.dry_shot
+                    } else {
+                        gun_sounds.shot
+                    }),));
+
+                    magazine_changed.trigger_with(MagazineChanged {
+                        player: player_id,
+                        magazine_status,
+                        by_shot: true,
+                    });
+                    // nothing is hit before the game
+                    shot_fired.trigger_with(ShotFired {
+                        shooter: player_id,
+                        victim: None,
+                    });
+                }
+            }
         }
+
+        systems::work_actions::run(world);
+        systems::work_timers::run(world, &mut self.game_time);
+        systems::update_movements::run(world, &mut self.game_time);
+        systems::update_animated_textures::run(world, &mut self.game_time);
+        systems::play_sounds::run(world, &mut ctx.resources.audio);
+        Transition::Stay
+    }
+
+    fn draw(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        let viewport = ctx.viewport;
+        let mut renderer = ctx.renderer();
+        renderer.set_draw_color(Color::BLACK);
+        renderer.clear();
+
+        systems::draw_textures::run(&mut renderer, &mut self.world);
+
+        // make everything drawn up to this point appear slightly darker
+        renderer.set_blend_mode(BlendMode::Blend);
+        renderer.set_draw_color(Color::RGBA(0, 0, 0, 150));
+        renderer.fill_rect(viewport);
+        renderer.set_blend_mode(BlendMode::None);
+
+        systems::draw_texts::run(&mut renderer, &mut self.world);
+        self.oscilloscope
+            .draw(ctx.gui_context, ctx.resources, ctx.texture_creator);
+    }
+
+    fn game_time(&mut self) -> Option<&mut Stopwatch> {
+        Some(&mut self.game_time)
+    }
+
+    fn receives_from_serial(&self) -> bool {
+        true
     }
 }
 
@@ -402,15 +510,22 @@ trait SpawnChickenAction {
     fn spawn_random_chicken_when(
         event: Event,
         viewport: Rect,
+        chicken: Sprite,
         out_of_viewport_event: Event,
     ) -> Action {
         Action::when(event, move |_entity_id, world| {
-            spawn_random_chickens(viewport, 1, world, out_of_viewport_event.clone());
+            spawn_random_chickens(viewport, &chicken, 1, world, out_of_viewport_event.clone());
         })
     }
 }
 
-fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewport_event: Event) {
+fn spawn_random_chickens(
+    viewport: Rect,
+    chicken: &Sprite,
+    n: u32,
+    world: &mut World,
+    out_of_viewport_event: Event,
+) {
     for _ in 0..n {
         let rand_big_range = rand::rng().random_range(-5..=5);
         let rand_small_neg_range = rand::rng().random_range(-5..=-1);
@@ -485,10 +600,9 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
 
         let my_out_of_viewport_event = Event::default();
 
-        let mut texture_builder = texture::Builder::new(0, position)
+        let mut texture_builder = texture::Builder::animated(chicken, position)
             .with_scale(rand_scale)
-            .with_num_frames(13)
-            .with_frame_advance_interval(Duration::from_millis(rand::rng().random_range(50..150)))
+            .with_playback_speed(rand::rng().random_range(0.67..2.0))
             .on_outside_viewport(my_out_of_viewport_event.clone());
 
         if (movement.f)(10000).x > 0 {
@@ -0,0 +1,215 @@
+use crate::gui::engine::assets::AssetError;
+use crate::gui::engine::gui_context::GuiContext;
+use crate::gui::engine::render::sdl::SdlRenderer;
+use crate::gui::engine::resources::Resources;
+use crate::gui::engine::stopwatch::Stopwatch;
+use crate::gui::scenes::common::pause::Pause;
+use log::{error, trace};
+use sdl2::event::Event;
+use sdl2::rect::Rect;
+use sdl2::render::TextureCreator;
+use sdl2::video::WindowContext;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// every scene is a Scene that the SceneManager drives frame by frame: it enters it once, then
+// every frame handles the events, updates, draws and presents it and waits for the rest of
+// FRAME_TIME, and exits it when it switches to the next scene or the gui quits
+// scenes never wait or present in a loop of their own, what takes longer than a frame is a state
+// of the scene that update advances, so the window keeps handling its events
+// while the game is paused, the manager shows the pause instead of the scene, see common::pause
+// the inbox of the gui is not bounded, so every frame the manager also takes what no scene
+// would take: the reports of the supervisor go into the GuiContext, and the messages from
+// serial are dropped unless the scene receives them itself
+// all scenes share the Resources, so an asset is loaded once for the whole run, see engine::assets
+// a scene whose assets are missing cannot run, entering it fails and the gui quits
+// data like the players goes to the next scene by creating it with the data, see Transition
+
+/// how long a frame takes at least, the manager sleeps for what the scene did not need of it
+pub const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// what a scene gets to work with in its hooks
+pub struct SceneContext<'c, 'sdl> {
+    pub gui_context: &'c mut GuiContext,
+
+    /// shared by all scenes
+    pub resources: &'c mut Resources<'sdl>,
+    pub texture_creator: &'sdl TextureCreator<WindowContext>,
+    pub viewport: Rect,
+}
+
+impl<'sdl> SceneContext<'_, 'sdl> {
+    /// draws to the window
+    pub fn renderer(&mut self) -> SdlRenderer<'_, 'sdl> {
+        SdlRenderer::new(
+            self.gui_context.canvas(),
+            self.resources,
+            self.texture_creator,
+        )
+    }
+}
+
+pub enum Transition<'sdl> {
+    Stay,
+
+    /// exits the current scene and enters this one
+    Switch(Box<dyn Scene<'sdl> + 'sdl>),
+}
+
+pub trait Scene<'sdl> {
+    /// sets up the world, runs once before the first update
+    fn enter(&mut self, _ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        Ok(())
+    }
+
+    /// one frame of the scene, `events` are the ones GuiContext::handle_events did not handle
+    fn update(&mut self, ctx: &mut SceneContext<'_, 'sdl>, events: &[Event]) -> Transition<'sdl>;
+
+    /// draws the frame, the manager presents it
+    fn draw(&mut self, _ctx: &mut SceneContext<'_, 'sdl>) {}
+
+    /// the frame drawn by draw was presented `at`
+    fn presented(&mut self, _ctx: &mut SceneContext<'_, 'sdl>, _at: Instant) {}
+
+    /// runs once when the scene is left, also when the gui quits
+    fn exit(&mut self, _ctx: &mut SceneContext<'_, 'sdl>) {}
+
+    /// the time of the scene, it stands still while the game is paused
+    /// None if the scene cannot be paused right now
+    fn game_time(&mut self) -> Option<&mut Stopwatch> {
+        None
+    }
+
+    /// whether the last update presented everything itself, then the manager neither draws
+    /// nor waits, e.g. while a flashing sequence has the screen
+    fn presents_itself(&self) -> bool {
+        false
+    }
+
+    /// whether update takes the messages from serial, otherwise the manager drops them
+    fn receives_from_serial(&self) -> bool {
+        false
+    }
+}
+
+pub struct SceneManager<'sdl> {
+    texture_creator: &'sdl TextureCreator<WindowContext>,
+    viewport: Rect,
+}
+
+impl<'sdl> SceneManager<'sdl> {
+    pub fn new(
+        gui_context: &mut GuiContext,
+        texture_creator: &'sdl TextureCreator<WindowContext>,
+    ) -> Self {
+        let (width, height) = gui_context.canvas().output_size().unwrap();
+        SceneManager {
+            texture_creator,
+            viewport: Rect::new(0, 0, width, height),
+        }
+    }
+
+    /// runs `first` and every scene after it until the gui quits
+    pub fn run(
+        &self,
+        gui_context: &mut GuiContext,
+        resources: &mut Resources<'sdl>,
+        first: Box<dyn Scene<'sdl> + 'sdl>,
+    ) {
+        let mut scene = first;
+        if !Self::enter(scene.as_mut(), &mut self.context(gui_context, resources)) {
+            return;
+        }
+        let mut pause: Option<Pause> = None;
+
+        loop {
+            let events = gui_context.handle_events();
+            if gui_context.quit_requested() {
+                scene.exit(&mut self.context(gui_context, resources));
+                return;
+            }
+
+            let frame_start = Instant::now();
+
+            gui_context.update_worker_health();
+
+            // a scene that cannot be paused right now is paused once it can
+            if gui_context.paused() {
+                if pause.is_none()
+                    && let Some(game_time) = scene.game_time()
+                {
+                    pause = Some(Pause::start(game_time, self.viewport));
+                }
+            } else if let Some(ended) = pause.take() {
+                // the scene did not update since, so it can still be paused
+                ended.end(scene.game_time().unwrap());
+            }
+
+            if !scene.receives_from_serial() || pause.is_some() {
+                while gui_context.comm().try_recv_from_serial().is_some() {}
+            }
+
+            let mut ctx = self.context(gui_context, resources);
+
+            if let Some(pause) = &mut pause {
+                pause.draw(&mut ctx);
+                ctx.gui_context.present();
+                wait_for_next_frame(frame_start);
+                continue;
+            }
+
+            let transition = scene.update(&mut ctx, &events);
+
+            if let Transition::Switch(next) = transition {
+                scene.exit(&mut ctx);
+                scene = next;
+                if !Self::enter(scene.as_mut(), &mut ctx) {
+                    return;
+                }
+                continue;
+            }
+
+            if scene.presents_itself() {
+                continue;
+            }
+
+            scene.draw(&mut ctx);
+            ctx.gui_context.present();
+            scene.presented(&mut ctx, Instant::now());
+
+            wait_for_next_frame(frame_start);
+        }
+    }
+
+    /// enters `scene`, quits the gui if it cannot, returns whether it could
+    fn enter(scene: &mut (dyn Scene<'sdl> + 'sdl), ctx: &mut SceneContext<'_, 'sdl>) -> bool {
+        match scene.enter(ctx) {
+            Ok(()) => true,
+            Err(e) => {
+                error!(target: "Gui Thread", "cannot enter the next scene: {e}");
+                ctx.gui_context.cancel_token().cancel();
+                false
+            }
+        }
+    }
+
+    fn context<'c>(
+        &self,
+        gui_context: &'c mut GuiContext,
+        resources: &'c mut Resources<'sdl>,
+    ) -> SceneContext<'c, 'sdl> {
+        SceneContext {
+            gui_context,
+            resources,
+            texture_creator: self.texture_creator,
+            viewport: self.viewport,
+        }
+    }
+}
+
+/// sleeps for what is left of FRAME_TIME
+fn wait_for_next_frame(frame_start: Instant) {
+    let frame_duration = frame_start.elapsed();
+    trace!(target: "Gui Thread", "frame took {}ms", frame_duration.as_millis());
+    thread::sleep(FRAME_TIME.saturating_sub(frame_duration));
+}
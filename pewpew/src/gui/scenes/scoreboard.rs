@@ -1,41 +1,51 @@
+use crate::gui::engine::assets::AssetError;
 use crate::gui::engine::components::action::Action;
 use crate::gui::engine::components::movement::Movement;
 use crate::gui::engine::components::point_with_alignment::{HAlign, PointWithAlignment, VAlign};
 use crate::gui::engine::components::{Point, text, texture, timer};
 use crate::gui::engine::event::Event;
-use crate::gui::engine::gui_context::GuiContext;
-use crate::gui::engine::resources::Resources;
+use crate::gui::engine::render::Renderer;
+use crate::gui::engine::sprite_sheet::Sprite;
 use crate::gui::engine::stopwatch::Stopwatch;
 use crate::gui::engine::systems;
 use crate::gui::scenes::common::PlayerData;
 use crate::gui::scenes::common::scenery::Scenery;
-use crate::gui::scenes::load_all_textures;
+use crate::gui::scenes::pregame::Pregame;
+use crate::gui::scenes::scene::{Scene, SceneContext, Transition};
 use hecs::World;
-use log::trace;
 use rand::Rng;
+use sdl2::event::Event as SdlEvent;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
-use std::{thread, vec};
 use sdl2::render::BlendMode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::vec;
 
-pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>>>) {
-    let viewport = {
-        let (width, height) = gui_context.canvas().output_size().unwrap();
-        Rect::new(0, 0, width, height)
-    };
-    let texture_creator = gui_context.canvas().texture_creator();
-    let ttf_context = sdl2::ttf::init().unwrap();
-    {
-        let default_font = ttf_context
-            .load_font("res/fonts/Walter_Turncoat/WalterTurncoat-Regular.ttf", 128)
-            .unwrap();
-        let mut resources = Resources::new(default_font);
-        let _texture_id_map = load_all_textures(&mut resources, &texture_creator).unwrap();
+pub struct Scoreboard {
+    player_datas: Vec<PlayerData>,
+    world: World,
+    game_time: Stopwatch,
+    game_end_event: Event,
+}
 
-        let mut world = World::new();
-        let mut game_time = Stopwatch::new_paused();
+impl Scoreboard {
+    pub fn new(player_datas: Vec<PlayerData>) -> Self {
+        Scoreboard {
+            player_datas,
+            world: World::new(),
+            game_time: Stopwatch::new_paused(),
+            game_end_event: Event::default(),
+        }
+    }
+}
+
+impl<'sdl> Scene<'sdl> for Scoreboard {
+    fn enter(&mut self, ctx: &mut SceneContext<'_, 'sdl>) -> Result<(), AssetError> {
+        let viewport = ctx.viewport;
+        let resources = &mut *ctx.resources;
+        let chicken = resources.assets.sprite("chicken", "fly")?;
+        let world = &mut self.world;
 
         let scenery_scale = viewport.height() as f32 / 720.0;
         world.spawn(Scenery::new(
@@ -44,12 +54,11 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                 y: (viewport.height() / 2) as i32,
             }),
             scenery_scale,
-            &mut resources,
-            &texture_creator,
-        ));
+            resources,
+        )?);
 
         // game end event
-        let mut game_end_event = Event::default();
+        let game_end_event = self.game_end_event.clone();
         let seconds_left = Arc::new(Mutex::new(11));
         let mut game_countdown_tick = Event::default();
 
@@ -57,7 +66,6 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
             Duration::from_secs(*seconds_left.lock().unwrap()),
             game_end_event.clone(),
         )
-
         .build(),));
         world.spawn((
             timer::Builder::new(Duration::from_secs(1), game_countdown_tick.clone())
@@ -82,8 +90,8 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
                             h_align: HAlign::Center,
                         },
                     )
-                        .with_color(Color::WHITE)
-                        .with_scale(viewport.height(), 1080)
+                    .with_color(Color::WHITE)
+                    .with_scale(viewport.height(), 1080)
                     .build(),
                     Action::despawn_self_when(game_countdown_tick_clone.clone()),
                 ));
@@ -102,6 +110,7 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
             world.spawn((Action::spawn_random_chicken_when(
                 spawn_new_chicken_event.clone(),
                 viewport,
+                chicken,
                 spawn_new_chicken_event.clone(),
             ),));
 
@@ -110,91 +119,103 @@ pub fn run(gui_context: &mut GuiContext, player_datas: Arc<Mutex<Vec<PlayerData>
             }
         }
 
-        let num_players = player_datas.lock().unwrap().len();
-        for i in 0..num_players {
-            let position = match i {
-                0 => PointWithAlignment {
-                    point: Point { x: 0, y: 0 },
-                    v_align: VAlign::Top,
-                    h_align: HAlign::Left,
-                },
-                1 => PointWithAlignment {
-                    point: Point {
-                        x: viewport.width() as i32,
-                        y: 0,
-                    },
-                    v_align: VAlign::Top,
-                    h_align: HAlign::Right,
-                },
-                2 => PointWithAlignment {
-                    point: Point {
-                        x: 0,
-                        y: viewport.height() as i32,
-                    },
-                    v_align: VAlign::Bottom,
-                    h_align: HAlign::Left,
-                },
-                3 => PointWithAlignment {
-                    point: Point {
-                        x: viewport.width() as i32,
-                        y: viewport.height() as i32,
-                    },
-                    v_align: VAlign::Bottom,
-                    h_align: HAlign::Right,
-                },
-                _ => unreachable!(),
-            };
+        spawn_scores(world, viewport, &self.player_datas);
+
+        self.game_time.resume();
+        Ok(())
+    }
 
-            world.spawn((text::Builder::new(format!("Score: {}", player_datas.lock().unwrap()[i].score), position).with_scale(viewport.height(), 1080).build(),));
+    fn update(
+        &mut self,
+        _ctx: &mut SceneContext<'_, 'sdl>,
+        _events: &[SdlEvent],
+    ) -> Transition<'sdl> {
+        if self.game_end_event.consume_all() > 0 {
+            return Transition::Switch(Box::new(Pregame::default()));
         }
 
-        game_time.resume();
+        systems::work_actions::run(&mut self.world);
+        systems::work_timers::run(&mut self.world, &mut self.game_time);
+        systems::update_movements::run(&mut self.world, &mut self.game_time);
+        systems::update_animated_textures::run(&mut self.world, &mut self.game_time);
+        Transition::Stay
+    }
 
-        loop {
-            if game_end_event.consume_all() > 0 {
-                while gui_context.comm().try_recv_from_serial().is_ok() {
-                    // empty the buffers
-                }
-                return;
-            }
+    fn draw(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        let viewport = ctx.viewport;
+        draw_world(&mut ctx.renderer(), &mut self.world, viewport);
+    }
 
-            let frame_start = SystemTime::now();
-
-            gui_context.canvas().set_draw_color(Color::BLACK);
-            gui_context.canvas().clear();
-
-            systems::work_actions::run(&mut world);
-            systems::work_timers::run(&mut world, &mut game_time);
-            systems::update_movements::run(&mut world, &mut game_time);
-            systems::update_animated_textures::run(&mut world, &mut game_time);
-            systems::draw_textures::run(gui_context.canvas(), &mut world, &mut resources);
-
-            // make everything drawn up to this point appear slightly darker
-            gui_context.canvas().set_blend_mode(BlendMode::Blend);
-            gui_context
-                .canvas()
-                .set_draw_color(Color::RGBA(0, 0, 0, 150));
-            gui_context.canvas().fill_rect(viewport).unwrap();
-            gui_context.canvas().set_blend_mode(BlendMode::None);
-
-            systems::draw_texts::run(
-                gui_context.canvas(),
-                &mut world,
-                &mut resources,
-                &texture_creator,
-            );
-
-            gui_context.canvas().present();
-
-            let frame_end = SystemTime::now();
-            let frame_duration = frame_end.duration_since(frame_start).unwrap();
-            let wait_duration =
-                Duration::from_millis(33_u128.saturating_sub(frame_duration.as_millis()) as u64);
-
-            trace!(target: "Gui Thread", "frame took {}ms", frame_duration.as_millis());
-            thread::sleep(wait_duration);
+    fn exit(&mut self, ctx: &mut SceneContext<'_, 'sdl>) {
+        // shots at the scoreboard do not count for the next game
+        while ctx.gui_context.comm().try_recv_from_serial().is_some() {
+            // empty the buffers
         }
     }
+
+    fn game_time(&mut self) -> Option<&mut Stopwatch> {
+        Some(&mut self.game_time)
+    }
+}
+
+/// the score of every player in a corner of its own, in the order of the players
+fn spawn_scores(world: &mut World, viewport: Rect, player_datas: &[PlayerData]) {
+    for (i, player_data) in player_datas.iter().enumerate() {
+        let position = match i {
+            0 => PointWithAlignment {
+                point: Point { x: 0, y: 0 },
+                v_align: VAlign::Top,
+                h_align: HAlign::Left,
+            },
+            1 => PointWithAlignment {
+                point: Point {
+                    x: viewport.width() as i32,
+                    y: 0,
+                },
+                v_align: VAlign::Top,
+                h_align: HAlign::Right,
+            },
+            2 => PointWithAlignment {
+                point: Point {
+                    x: 0,
+                    y: viewport.height() as i32,
+                },
+                v_align: VAlign::Bottom,
+                h_align: HAlign::Left,
+            },
+            3 => PointWithAlignment {
+                point: Point {
+                    x: viewport.width() as i32,
+                    y: viewport.height() as i32,
+                },
+                v_align: VAlign::Bottom,
+                h_align: HAlign::Right,
+            },
+            _ => unreachable!(),
+        };
+
+        world.spawn((
+            text::Builder::new(format!("Score: {}", player_data.score), position)
+                .with_scale(viewport.height(), 1080)
+                .build(),
+        ));
+    }
+}
+
+/// everything in the world, the textures dimmed so that the texts stand out
+fn draw_world(renderer: &mut impl Renderer, world: &mut World, viewport: Rect) {
+    renderer.set_draw_color(Color::BLACK);
+    renderer.clear();
+
+    systems::draw_textures::run(renderer, world);
+
+    // make everything drawn up to this point appear slightly darker
+    renderer.set_blend_mode(BlendMode::Blend);
+    renderer.set_draw_color(Color::RGBA(0, 0, 0, 150));
+    renderer.fill_rect(viewport);
+    renderer.set_blend_mode(BlendMode::None);
+
+    systems::draw_texts::run(renderer, world);
 }
 
 impl SpawnChickenAction for Action {}
@@ -202,15 +223,22 @@ trait SpawnChickenAction {
     fn spawn_random_chicken_when(
         event: Event,
         viewport: Rect,
+        chicken: Sprite,
         out_of_viewport_event: Event,
     ) -> Action {
         Action::when(event, move |_entity_id, world| {
-            spawn_random_chickens(viewport, 1, world, out_of_viewport_event.clone());
+            spawn_random_chickens(viewport, &chicken, 1, world, out_of_viewport_event.clone());
         })
     }
 }
 
-fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewport_event: Event) {
+fn spawn_random_chickens(
+    viewport: Rect,
+    chicken: &Sprite,
+    n: u32,
+    world: &mut World,
+    out_of_viewport_event: Event,
+) {
     for _ in 0..n {
         let rand_big_range = rand::rng().random_range(-5..=5);
         let rand_small_neg_range = rand::rng().random_range(-5..=-1);
@@ -285,10 +313,9 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
 
         let my_out_of_viewport_event = Event::default();
 
-        let mut texture_builder = texture::Builder::new(0, position)
+        let mut texture_builder = texture::Builder::animated(chicken, position)
             .with_scale(rand_scale)
-            .with_num_frames(13)
-            .with_frame_advance_interval(Duration::from_millis(rand::rng().random_range(50..150)))
+            .with_playback_speed(rand::rng().random_range(0.67..2.0))
             .on_outside_viewport(my_out_of_viewport_event.clone());
 
         if (movement.f)(10000).x > 0 {
@@ -309,3 +336,56 @@ fn spawn_random_chickens(viewport: Rect, n: u32, world: &mut World, out_of_viewp
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::gui::engine::components::point_with_alignment::PointWithAlignment;
+    use crate::gui::engine::components::{Point, texture};
+    use crate::gui::engine::render::golden::assert_golden;
+    use crate::gui::engine::render::software::{Bitmap, SoftwareRenderer};
+    use crate::gui::scenes::common::PlayerData;
+    use crate::gui::scenes::scoreboard::{draw_world, spawn_scores};
+    use crate::serial::packet::MagazineStatus;
+    use hecs::World;
+    use sdl2::pixels::Color;
+    use sdl2::rect::Rect;
+
+    #[test]
+    fn scores_are_in_the_corners_over_the_dimmed_scene() {
+        let viewport = Rect::new(0, 0, 192, 108);
+        let mut renderer = SoftwareRenderer::new(viewport.width(), viewport.height());
+        // a checkerboard stands in for the scenery
+        let scenery = renderer.add_image(Bitmap::from_fn(12, 9, |x, y| {
+            if (x + y) % 2 == 0 {
+                Color::RGB(120, 200, 255)
+            } else {
+                Color::RGB(40, 160, 40)
+            }
+        }));
+
+        let mut world = World::new();
+        world.spawn((texture::Builder::new(
+            scenery,
+            PointWithAlignment::new_center(Point { x: 96, y: 54 }),
+        )
+        .with_scale(16.0)
+        .build(),));
+        let player_datas = [3, 12, 150, 0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, score)| PlayerData {
+                sensortag_id: i as u16,
+                magazine_status: MagazineStatus {
+                    ammo: 0,
+                    ammo_max: 5,
+                },
+                score,
+            })
+            .collect::<Vec<_>>();
+        spawn_scores(&mut world, viewport, &player_datas);
+
+        draw_world(&mut renderer, &mut world, viewport);
+
+        assert_golden("scoreboard", renderer.frame());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::comm::message::SerialToHitReg;
+use crate::hitreg::decoder::BRIGHTNESS_GRADIENT_THRESHOLD;
+use std::collections::HashMap;
+
+// the light a sensortag sees is never constant: lamps, sunlight, people walking by,
+// and the light sensor itself is noisy
+// between shots every sample updates a slowly moving baseline and a noise estimate per sensortag,
+// during a shot frames are classified relative to the black reference frame, with a threshold
+// that grows with the noise seen before the shot
+
+/// how fast baseline and noise follow new samples, between 0 and 1
+const SMOOTHING: f32 = 0.1;
+
+/// a frame only counts as white if it is this many standard deviations above the black reference
+const NOISE_FACTOR: f32 = 4.0;
+
+/// the noise estimate needs a few samples before it means anything
+const MIN_SAMPLES: u32 = 10;
+
+/// jumps larger than this are not drift or noise, the gun was moved to point somewhere else
+/// the baseline starts over at the new value and the noise estimate is left alone
+/// also the upper limit of the white threshold, a very noisy sensortag must still be able to hit
+pub const MAX_WHITE_THRESHOLD: u16 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ambient {
+    /// brightness the sensortag sees when nothing is flashing
+    pub baseline: f32,
+    pub variance: f32,
+    pub num_samples: u32,
+}
+
+impl Ambient {
+    /// standard deviation of the brightness around the baseline
+    pub fn noise(&self) -> f32 {
+        self.variance.sqrt()
+    }
+
+    /// how much brighter than the black reference a frame has to be to count as white
+    pub fn white_threshold(&self) -> u16 {
+        if self.num_samples < MIN_SAMPLES {
+            return BRIGHTNESS_GRADIENT_THRESHOLD;
+        }
+
+        ((NOISE_FACTOR * self.noise()).round() as u16)
+            .clamp(BRIGHTNESS_GRADIENT_THRESHOLD, MAX_WHITE_THRESHOLD)
+    }
+
+    fn observe(&mut self, value: u16) {
+        let diff = value as f32 - self.baseline;
+
+        if diff.abs() > MAX_WHITE_THRESHOLD as f32 {
+            self.baseline = value as f32;
+        } else {
+            // exponentially weighted mean and variance
+            self.baseline += SMOOTHING * diff;
+            self.variance = (1.0 - SMOOTHING) * (self.variance + SMOOTHING * diff * diff);
+        }
+        self.num_samples = self.num_samples.saturating_add(1);
+    }
+}
+
+#[derive(Default)]
+pub struct AmbientTracker {
+    sensortags: HashMap<u16, Ambient>,
+}
+
+impl AmbientTracker {
+    /// must only get samples from between shots, a flashing screen is not ambient light
+    pub fn observe(&mut self, sample: &SerialToHitReg) {
+        self.sensortags
+            .entry(sample.sensortag_id)
+            .or_insert(Ambient {
+                baseline: sample.value_raw as f32,
+                variance: 0.0,
+                num_samples: 0,
+            })
+            .observe(sample.value_raw);
+    }
+
+    pub fn get(&self, sensortag_id: u16) -> Option<Ambient> {
+        self.sensortags.get(&sensortag_id).copied()
+    }
+
+    pub fn white_threshold(&self, sensortag_id: u16) -> u16 {
+        self.get(sensortag_id)
+            .map_or(BRIGHTNESS_GRADIENT_THRESHOLD, |ambient| {
+                ambient.white_threshold()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comm::message::{SerialToHitReg, Stamps};
+    use crate::hitreg::ambient::{AmbientTracker, MAX_WHITE_THRESHOLD};
+    use crate::hitreg::decoder::BRIGHTNESS_GRADIENT_THRESHOLD;
+
+    fn feed(tracker: &mut AmbientTracker, sensortag_id: u16, values: impl Iterator<Item = u16>) {
+        for value_raw in values {
+            tracker.observe(&SerialToHitReg {
+                sensortag_id,
+                timestamp: 1,
+                value_raw,
+                stamps: Stamps::now(),
+            });
+        }
+    }
+
+    #[test]
+    fn baseline_follows_slow_drift() {
+        let mut tracker = AmbientTracker::default();
+
+        // somebody slowly turns up the lights
+        feed(&mut tracker, 1, (0..200).map(|i| 100 + i / 2));
+
+        let ambient = tracker.get(1).unwrap();
+        assert!((190.0..=200.0).contains(&ambient.baseline));
+        assert_eq!(tracker.white_threshold(1), BRIGHTNESS_GRADIENT_THRESHOLD);
+    }
+
+    #[test]
+    fn noisy_sensortag_needs_a_larger_step_for_white() {
+        let mut tracker = AmbientTracker::default();
+
+        feed(
+            &mut tracker,
+            1,
+            (0..100).map(|i| if i % 2 == 0 { 80 } else { 120 }),
+        );
+        feed(
+            &mut tracker,
+            2,
+            (0..100).map(|i| if i % 2 == 0 { 99 } else { 101 }),
+        );
+
+        // a noise of about 20 needs about 80 above black
+        assert!((70..=90).contains(&tracker.white_threshold(1)));
+        assert_eq!(tracker.white_threshold(2), BRIGHTNESS_GRADIENT_THRESHOLD);
+        // unknown sensortags get the default
+        assert_eq!(tracker.white_threshold(3), BRIGHTNESS_GRADIENT_THRESHOLD);
+    }
+
+    #[test]
+    fn pointing_somewhere_else_is_not_noise() {
+        let mut tracker = AmbientTracker::default();
+
+        feed(&mut tracker, 1, (0..50).map(|_| 100));
+        feed(&mut tracker, 1, (0..50).map(|_| 600));
+
+        let ambient = tracker.get(1).unwrap();
+        assert_eq!(ambient.baseline, 600.0);
+        assert_eq!(ambient.noise(), 0.0);
+        assert!(tracker.white_threshold(1) < MAX_WHITE_THRESHOLD);
+    }
+}
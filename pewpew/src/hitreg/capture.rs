@@ -0,0 +1,304 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// with the debug capture on (see user_settings::CAPTURE_HITREG) every shot gets a bundle,
+// a directory in CAPTURE_DIR
+// the gui saves every flash frame as an image, hitreg saves everything it saw of the sequence
+// as text, a coarse to fine shot has two sequences in one bundle
+// the capture_viewer binary steps through a bundle
+
+pub const CAPTURE_DIR: &str = "captures";
+
+/// what hitreg saw of sequence `sequence` of a bundle
+pub fn sequence_file(bundle: &Path, sequence: u32) -> PathBuf {
+    bundle.join(format!("sequence-{sequence}.txt"))
+}
+
+/// frame `frame` of sequence `sequence`, counted like SequenceCapture::frame_ends
+pub fn frame_file(bundle: &Path, sequence: u32, frame: usize) -> PathBuf {
+    bundle.join(format!("sequence-{sequence}-frame-{frame}.png"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Black,
+    White,
+    Code,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// when hitreg received it, in ms since the start of the sequence
+    pub received_ms: u64,
+
+    /// as sent by the sensortag
+    pub timestamp: u32,
+    pub value: u16,
+}
+
+/// everything hitreg saw of one flashing sequence
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SequenceCapture {
+    pub sensortag_id: u16,
+    pub white_threshold: u16,
+
+    /// what was flashed (a hitbox, a region, ..) and the code hitreg expected from it
+    pub codes: Vec<(String, String)>,
+
+    /// every sample of the sensortag during the sequence
+    pub samples: Vec<Sample>,
+
+    /// when the frame ends arrived, in ms since the start of the sequence
+    pub frame_ends: Vec<(u64, FrameKind)>,
+
+    pub black_reference: u16,
+    pub white_reference: Option<u16>,
+
+    /// brightness at the end of every code frame
+    pub frame_brightness: Vec<u16>,
+
+    /// white/black decision for every code frame
+    pub decoded: Vec<bool>,
+    pub result: String,
+}
+
+impl SequenceCapture {
+    /// the samples that arrived while the frame was on screen
+    pub fn samples_in_frame(&self, frame: usize) -> &[Sample] {
+        let start = match frame {
+            0 => 0,
+            _ => self
+                .frame_ends
+                .get(frame - 1)
+                .map_or(u64::MAX, |(ms, _)| *ms),
+        };
+        let end = self.frame_ends.get(frame).map_or(u64::MAX, |(ms, _)| *ms);
+
+        let first = self.samples.partition_point(|s| s.received_ms < start);
+        let last = self.samples.partition_point(|s| s.received_ms < end);
+        &self.samples[first..last.max(first)]
+    }
+
+    /// brightness hitreg used for the frame, and for code frames whether it counted as white
+    pub fn brightness_of_frame(&self, frame: usize) -> Option<(u16, Option<bool>)> {
+        let (_, kind) = self.frame_ends.get(frame)?;
+        match kind {
+            FrameKind::Black => Some((self.black_reference, None)),
+            FrameKind::White => self.white_reference.map(|white| (white, None)),
+            FrameKind::Code => {
+                let code_frame = self.frame_ends[..frame]
+                    .iter()
+                    .filter(|(_, kind)| *kind == FrameKind::Code)
+                    .count();
+                Some((
+                    *self.frame_brightness.get(code_frame)?,
+                    self.decoded.get(code_frame).copied(),
+                ))
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        parse(&content).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// one line per value, the first word says what it is
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        // writing to a string never fails
+        writeln!(text, "sensortag {}", self.sensortag_id).unwrap();
+        writeln!(text, "white_threshold {}", self.white_threshold).unwrap();
+        for (what, code) in &self.codes {
+            writeln!(text, "code {what} {code}").unwrap();
+        }
+        for sample in &self.samples {
+            writeln!(
+                text,
+                "sample {} {} {}",
+                sample.received_ms, sample.timestamp, sample.value
+            )
+            .unwrap();
+        }
+        for (ms, kind) in &self.frame_ends {
+            let kind = match kind {
+                FrameKind::Black => "black",
+                FrameKind::White => "white",
+                FrameKind::Code => "code",
+            };
+            writeln!(text, "frame_end {ms} {kind}").unwrap();
+        }
+        writeln!(text, "black_reference {}", self.black_reference).unwrap();
+        match self.white_reference {
+            Some(white) => writeln!(text, "white_reference {white}").unwrap(),
+            None => writeln!(text, "white_reference none").unwrap(),
+        }
+        writeln!(text, "frame_brightness {}", join(&self.frame_brightness)).unwrap();
+        let decoded = self
+            .decoded
+            .iter()
+            .map(|bit| *bit as u8)
+            .collect::<Vec<_>>();
+        writeln!(text, "decoded {}", join(&decoded)).unwrap();
+        writeln!(text, "result {}", self.result).unwrap();
+
+        text
+    }
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(T::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse(content: &str) -> Result<SequenceCapture, String> {
+    let mut capture = SequenceCapture::default();
+
+    for (number, line) in content.lines().enumerate() {
+        let error = |what: &str| format!("line {}: {what}", number + 1);
+        let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut words = rest.split_whitespace();
+        let mut number_of = |what: &str| -> Result<u64, String> {
+            words
+                .next()
+                .and_then(|word| word.parse().ok())
+                .ok_or_else(|| error(&format!("expected {what}")))
+        };
+
+        match key {
+            "sensortag" => capture.sensortag_id = number_of("an id")? as u16,
+            "white_threshold" => capture.white_threshold = number_of("a threshold")? as u16,
+            "code" => {
+                let (what, code) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| error("expected a code"))?;
+                capture.codes.push((what.to_string(), code.to_string()));
+            }
+            "sample" => capture.samples.push(Sample {
+                received_ms: number_of("a time")?,
+                timestamp: number_of("a timestamp")? as u32,
+                value: number_of("a value")? as u16,
+            }),
+            "frame_end" => {
+                let ms = number_of("a time")?;
+                let kind = match words.next() {
+                    Some("black") => FrameKind::Black,
+                    Some("white") => FrameKind::White,
+                    Some("code") => FrameKind::Code,
+                    _ => return Err(error("expected black, white or code")),
+                };
+                capture.frame_ends.push((ms, kind));
+            }
+            "black_reference" => capture.black_reference = number_of("a brightness")? as u16,
+            "white_reference" => {
+                capture.white_reference = match rest {
+                    "none" => None,
+                    _ => Some(number_of("a brightness")? as u16),
+                }
+            }
+            "frame_brightness" => {
+                capture.frame_brightness = rest
+                    .split_whitespace()
+                    .map(|word| {
+                        word.parse()
+                            .map_err(|_| error("expected brightness values"))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            "decoded" => {
+                capture.decoded = rest
+                    .split_whitespace()
+                    .map(|word| match word {
+                        "0" => Ok(false),
+                        "1" => Ok(true),
+                        _ => Err(error("expected 0 or 1")),
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            "result" => capture.result = rest.to_string(),
+            "" => {}
+            _ => return Err(error(&format!("unknown key {key}"))),
+        }
+    }
+
+    Ok(capture)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hitreg::capture::{FrameKind, Sample, SequenceCapture, parse};
+
+    fn capture() -> SequenceCapture {
+        SequenceCapture {
+            sensortag_id: 0x1234,
+            white_threshold: 25,
+            codes: vec![
+                ("entity-0".to_string(), "10".to_string()),
+                ("entity-1".to_string(), "01".to_string()),
+            ],
+            samples: vec![
+                Sample {
+                    received_ms: 100,
+                    timestamp: 100,
+                    value: 10,
+                },
+                Sample {
+                    received_ms: 330,
+                    timestamp: 150,
+                    value: 12,
+                },
+                Sample {
+                    received_ms: 550,
+                    timestamp: 200,
+                    value: 300,
+                },
+                Sample {
+                    received_ms: 600,
+                    timestamp: 225,
+                    value: 410,
+                },
+            ],
+            frame_ends: vec![
+                (220, FrameKind::Black),
+                (440, FrameKind::Code),
+                (660, FrameKind::Code),
+            ],
+            black_reference: 10,
+            white_reference: None,
+            frame_brightness: vec![12, 410],
+            decoded: vec![false, true],
+            result: "Result(Some(1v1))".to_string(),
+        }
+    }
+
+    #[test]
+    fn text_can_be_read_back() {
+        let capture = capture();
+
+        assert_eq!(parse(&capture.to_text()), Ok(capture));
+        assert!(parse("frame_end 12 grey").is_err());
+    }
+
+    #[test]
+    fn samples_and_brightness_belong_to_their_frame() {
+        let capture = capture();
+
+        assert_eq!(capture.samples_in_frame(0).len(), 1);
+        assert_eq!(capture.samples_in_frame(2)[1].value, 410);
+        assert!(capture.samples_in_frame(3).is_empty());
+
+        assert_eq!(capture.brightness_of_frame(0), Some((10, None)));
+        assert_eq!(capture.brightness_of_frame(2), Some((410, Some(true))));
+        assert_eq!(capture.brightness_of_frame(3), None);
+    }
+}
@@ -0,0 +1,1212 @@
+use crate::comm::message::{GuiToHitreg, HitregToGui, SerialToHitReg, ToHitreg};
+use crate::hitreg::ambient::{Ambient, AmbientTracker};
+use crate::hitreg::capture::{FrameKind, Sample, SequenceCapture};
+use crate::hitreg::grey_levels;
+use crate::hitreg::latency_probe::LatencyProbe;
+use crate::hitreg::structured_light::StripeLayout;
+use hecs::Entity;
+use log::{debug, error, warn};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// smallest step above the black reference that counts as white,
+/// noisy sensortags get a larger one, see hitreg::ambient
+pub const BRIGHTNESS_GRADIENT_THRESHOLD: u16 = 25;
+
+/// every frame boundary of a running flashing sequence has to arrive within this time
+/// after the previous one (or after the start), otherwise the sequence times out
+pub const FRAME_DEADLINE: Duration = Duration::from_millis(1000);
+
+/// the sensortag only sends a brightness value when it changes, but the light sensor is noisy
+/// enough that a new value shows up roughly every 100ms
+/// a frame that got a value while it was shown is fine no matter how long it was, but if none
+/// arrived and the newest one was already older than this when the frame started, packets got
+/// lost on the way
+pub const BRIGHTNESS_STALE_AFTER: Duration = Duration::from_millis(500);
+
+/// number of frames needed to give num_codes things their own code
+/// the code 0 (black in every frame) is left out, it means the sensortag saw none of them
+pub fn num_frames_for(num_codes: u32) -> u32 {
+    u32::BITS - num_codes.leading_zeros()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    WaitingForFlashFrameEnd(u32),
+}
+
+/// what the frames of the running flashing sequence encode
+#[derive(Debug)]
+enum Target {
+    /// code of every hitbox that takes part in the flashing sequence
+    Hitboxes(Vec<(Entity, Vec<bool>)>),
+
+    /// stripes across the whole viewport
+    AimPoint(StripeLayout),
+
+    /// number of screen regions, region i has the code i+1
+    Regions(u32),
+
+    /// code of every hitbox, one digit in base `levels` per frame
+    GreyLevels {
+        levels: u32,
+        sequences: Vec<(Entity, Vec<u8>)>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrightnessBuffer {
+    pub val: u16,
+    pub sensortag_id: u16,
+    pub time: u32,
+    pub is_white: bool,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// the message does not fit into the current state and was dropped
+    /// the decoder stays usable, there is no need to reset it
+    UnexpectedMessage(ToHitreg),
+}
+
+/// the hitreg state machine without any channels attached to it
+///
+/// brightness samples from serial and frame boundaries from the gui go in via [`HitDecoder::feed`],
+/// once the last frame of a flashing sequence ended, the result for the gui comes out
+///
+/// every flashing sequence start gets exactly one answer, either a result or
+/// [`HitregToGui::TimedOut`], even if frames or brightness packets get lost
+pub struct HitDecoder {
+    state: State,
+    sensortag_id: u16,
+
+    target: Target,
+
+    /// white/black decision of the current sensortag for every frame that has ended so far
+    decoded_sequence: Vec<bool>,
+
+    /// brightness of the current sensortag at the end of every frame, used for grey levels
+    frame_brightness: Vec<u16>,
+    black_reference: u16,
+    white_reference: Option<u16>,
+
+    /// how far above the black reference a frame has to be to count as white,
+    /// fixed at the start of every sequence from the noise seen before it
+    white_threshold: u16,
+
+    /// the next frame boundary of the running sequence must arrive before this
+    deadline: Option<Instant>,
+
+    /// set if a frame of the running sequence ended without a fresh brightness value
+    brightness_went_stale: bool,
+    last_brightness_received_at: Option<Instant>,
+
+    /// when the frame that is shown right now started, i.e. when the previous one ended
+    frame_started_at: Option<Instant>,
+
+    last_brightness: BrightnessBuffer,
+
+    /// baseline and noise of every sensortag, only updated between shots
+    ambient: AmbientTracker,
+
+    /// runs next to the flashing sequences, it watches all sensortags and not only the current one
+    probe: LatencyProbe,
+
+    /// what the running sequence saw so far, for the debug capture
+    capture: SequenceCapture,
+    started_at: Option<Instant>,
+    last_capture: Option<SequenceCapture>,
+}
+
+impl Default for HitDecoder {
+    fn default() -> Self {
+        HitDecoder {
+            state: State::Idle,
+            sensortag_id: 0,
+            target: Target::Hitboxes(Vec::new()),
+            decoded_sequence: Vec::new(),
+            frame_brightness: Vec::new(),
+            black_reference: 0,
+            white_reference: None,
+            white_threshold: BRIGHTNESS_GRADIENT_THRESHOLD,
+            deadline: None,
+            brightness_went_stale: false,
+            last_brightness_received_at: None,
+            frame_started_at: None,
+            last_brightness: BrightnessBuffer::default(),
+            ambient: AmbientTracker::default(),
+            probe: LatencyProbe::default(),
+            capture: SequenceCapture::default(),
+            started_at: None,
+            last_capture: None,
+        }
+    }
+}
+
+impl HitDecoder {
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Idle
+    }
+
+    /// what the sensortag sees between shots, for debugging
+    pub fn ambient(&self, sensortag_id: u16) -> Option<Ambient> {
+        self.ambient.get(sensortag_id)
+    }
+
+    /// everything the last finished sequence saw, see hitreg::capture
+    pub fn take_capture(&mut self) -> Option<SequenceCapture> {
+        self.last_capture.take()
+    }
+
+    /// returns Ok(Some(..)) exactly once per flashing sequence, when its last frame ended
+    ///
+    /// `now` is the time the message was received
+    pub fn feed(
+        &mut self,
+        message: ToHitreg,
+        now: Instant,
+    ) -> Result<Option<HitregToGui>, DecodeError> {
+        match (self.state, message) {
+            (_, ToHitreg::FromSerial(serial_to_hitreg)) => {
+                let probe_result = self.probe.observe(&serial_to_hitreg, now);
+                if self.state == State::Idle && !self.probe.is_running() {
+                    self.ambient.observe(&serial_to_hitreg);
+                }
+                if self.state != State::Idle && serial_to_hitreg.sensortag_id == self.sensortag_id {
+                    self.capture.samples.push(Sample {
+                        received_ms: self.ms_since_start(now),
+                        timestamp: serial_to_hitreg.timestamp,
+                        value: serial_to_hitreg.value_raw,
+                    });
+                }
+                self.store_brightness(serial_to_hitreg, now);
+                Ok(probe_result)
+            }
+            (_, ToHitreg::FromGui(GuiToHitreg::LatencyProbe { sensortag_ids })) => {
+                Ok(self.probe.start(sensortag_ids, now))
+            }
+            (
+                _,
+                ToHitreg::FromGui(GuiToHitreg::FlashingSequenceStart {
+                    sensortag_id,
+                    num_frames,
+                    sequences,
+                }),
+            ) => Ok(self.start(sensortag_id, num_frames, Target::Hitboxes(sequences), now)),
+            (
+                _,
+                ToHitreg::FromGui(GuiToHitreg::AimSequenceStart {
+                    sensortag_id,
+                    layout,
+                }),
+            ) => Ok(self.start(
+                sensortag_id,
+                layout.num_frames(),
+                Target::AimPoint(layout),
+                now,
+            )),
+            (
+                _,
+                ToHitreg::FromGui(GuiToHitreg::RegionSequenceStart {
+                    sensortag_id,
+                    num_regions,
+                }),
+            ) => Ok(self.start(
+                sensortag_id,
+                num_frames_for(num_regions),
+                Target::Regions(num_regions),
+                now,
+            )),
+            (
+                _,
+                ToHitreg::FromGui(GuiToHitreg::GreyLevelSequenceStart {
+                    sensortag_id,
+                    levels,
+                    num_frames,
+                    sequences,
+                }),
+            ) => Ok(self.start(
+                sensortag_id,
+                num_frames,
+                Target::GreyLevels { levels, sequences },
+                now,
+            )),
+            (
+                State::WaitingForFlashFrameEnd(_),
+                ToHitreg::FromGui(GuiToHitreg::FlashBlackFrameEnd(_)),
+            ) => {
+                // the black frame is the reference every following frame is compared to
+                self.capture_frame_end(FrameKind::Black, now);
+                self.last_brightness.is_white = false;
+                self.black_reference = self.last_brightness.val;
+                self.deadline = Some(now + FRAME_DEADLINE);
+                self.frame_started_at = Some(now);
+                debug!(target: "Hitreg Thread", "new brightness {:?} at t={}", self.last_brightness, now_ms());
+
+                Ok(self.finish_if_done())
+            }
+            (
+                State::WaitingForFlashFrameEnd(num_frames_to_go),
+                ToHitreg::FromGui(GuiToHitreg::FlashFrameEnd(_)),
+            ) if num_frames_to_go > 0 => {
+                if self.last_brightness.time == 0 {
+                    error!(target: "Hitreg Thread", "no brightness measurements available (or timestamp is 0)");
+                }
+
+                self.check_brightness_is_fresh();
+                self.capture_frame_end(FrameKind::Code, now);
+
+                self.decoded_sequence.push(self.last_brightness.is_white);
+                self.frame_brightness.push(self.last_brightness.val);
+                self.deadline = Some(now + FRAME_DEADLINE);
+                self.frame_started_at = Some(now);
+                self.change_state(State::WaitingForFlashFrameEnd(num_frames_to_go - 1));
+
+                Ok(self.finish_if_done())
+            }
+            (
+                State::WaitingForFlashFrameEnd(_),
+                ToHitreg::FromGui(GuiToHitreg::FlashWhiteFrameEnd(_)),
+            ) => {
+                self.check_brightness_is_fresh();
+                self.capture_frame_end(FrameKind::White, now);
+
+                self.white_reference = Some(self.last_brightness.val);
+                self.deadline = Some(now + FRAME_DEADLINE);
+                self.frame_started_at = Some(now);
+
+                Ok(None)
+            }
+            (_, x) => Err(DecodeError::UnexpectedMessage(x)),
+        }
+    }
+
+    fn start(
+        &mut self,
+        sensortag_id: u16,
+        num_frames: u32,
+        target: Target,
+        now: Instant,
+    ) -> Option<HitregToGui> {
+        // a new sequence while the old one is still running means the gui gave up on
+        // the old one, answer it anyway so that every start gets exactly one answer
+        let aborted = (self.state != State::Idle).then(|| {
+            warn!(target: "Hitreg Thread", "flashing sequence started while another one was running, dropping the old one");
+            HitregToGui::TimedOut
+        });
+        if let Some(aborted) = &aborted {
+            self.finish_capture(aborted);
+        }
+
+        self.sensortag_id = sensortag_id;
+        self.target = target;
+        self.decoded_sequence.clear();
+        self.frame_brightness.clear();
+        self.white_reference = None;
+        self.white_threshold = self.ambient.white_threshold(sensortag_id);
+        self.brightness_went_stale = false;
+        self.deadline = Some(now + FRAME_DEADLINE);
+        self.started_at = Some(now);
+        self.frame_started_at = Some(now);
+        self.capture = SequenceCapture {
+            sensortag_id,
+            white_threshold: self.white_threshold,
+            codes: code_table(&self.target),
+            ..SequenceCapture::default()
+        };
+        debug!(target: "Hitreg Thread", "{:?}", self.target);
+        debug!(target: "Hitreg Thread", "ambient of sensortag {sensortag_id}: {:?}, white threshold {}", self.ambient.get(sensortag_id), self.white_threshold);
+
+        self.change_state(State::WaitingForFlashFrameEnd(num_frames));
+        aborted
+    }
+
+    /// must be called regularly, even if no messages arrive
+    /// returns [`HitregToGui::TimedOut`] once the running sequence missed its deadline,
+    /// and the result of a latency probe once its window is over
+    pub fn poll(&mut self, now: Instant) -> Option<HitregToGui> {
+        match self.deadline {
+            Some(deadline) if self.state != State::Idle && now >= deadline => {
+                warn!(target: "Hitreg Thread", "flashing sequence timed out in state {:?}", self.state);
+                self.finish_capture(&HitregToGui::TimedOut);
+                self.reset();
+                Some(HitregToGui::TimedOut)
+            }
+            _ => self.probe.poll(now),
+        }
+    }
+
+    fn finish_if_done(&mut self) -> Option<HitregToGui> {
+        if self.state != State::WaitingForFlashFrameEnd(0) {
+            return None;
+        }
+
+        // all frames of the flashing sequence have arrived
+        debug!(target: "Hitreg Thread", "{:?}", self.decoded_sequence);
+        let result = if self.brightness_went_stale {
+            // the decoded sequence is built from old values, it would be a guess at best
+            HitregToGui::TimedOut
+        } else if self
+            .white_reference
+            .is_some_and(|white| white.saturating_sub(self.black_reference) <= self.white_threshold)
+        {
+            // the white reference frame did not get brighter than the black one,
+            // so the sensortag did not point at anything that was flashed
+            self.nothing_seen()
+        } else {
+            match &self.target {
+                Target::Hitboxes(sequences) => {
+                    let decoded_sequence = self.black_and_white_sequence();
+                    HitregToGui::Result(sequences.iter().find_map(|(entity, sequence)| {
+                        (sequence == &decoded_sequence).then_some(*entity)
+                    }))
+                }
+                Target::AimPoint(layout) => {
+                    HitregToGui::AimPoint(layout.decode(&self.black_and_white_sequence()))
+                }
+                Target::Regions(num_regions) => {
+                    let code = self
+                        .black_and_white_sequence()
+                        .iter()
+                        .enumerate()
+                        .fold(0, |acc, (bit, is_white)| acc | ((*is_white as u32) << bit));
+                    HitregToGui::Region((1..=*num_regions).contains(&code).then(|| code - 1))
+                }
+                Target::GreyLevels { levels, sequences } => match self.white_reference {
+                    Some(white) => {
+                        let digits = self
+                            .frame_brightness
+                            .iter()
+                            .map(|val| {
+                                grey_levels::classify(*val, self.black_reference, white, *levels)
+                            })
+                            .collect::<Vec<_>>();
+                        debug!(target: "Hitreg Thread", "{:?}", digits);
+
+                        HitregToGui::Result(sequences.iter().find_map(|(entity, sequence)| {
+                            (sequence == &digits).then_some(*entity)
+                        }))
+                    }
+                    None => {
+                        warn!(target: "Hitreg Thread", "grey level sequence ended without a white reference frame");
+                        HitregToGui::TimedOut
+                    }
+                },
+            }
+        };
+
+        self.finish_capture(&result);
+        self.reset();
+        Some(result)
+    }
+
+    /// white/black decision for every frame
+    ///
+    /// with a white reference frame (the gui flashes at reduced contrast) a frame is white if it
+    /// is closer to the white reference than to the black one, otherwise it is white if it is
+    /// brighter than the black reference, see store_brightness
+    fn black_and_white_sequence(&self) -> Vec<bool> {
+        match self.white_reference {
+            Some(white) => self
+                .frame_brightness
+                .iter()
+                .map(|val| grey_levels::classify(*val, self.black_reference, white, 2) == 1)
+                .collect(),
+            None => self.decoded_sequence.clone(),
+        }
+    }
+
+    /// the answer for a sensortag that did not point at anything that was flashed
+    fn nothing_seen(&self) -> HitregToGui {
+        match self.target {
+            Target::Hitboxes(_) | Target::GreyLevels { .. } => HitregToGui::Result(None),
+            Target::AimPoint(_) => HitregToGui::AimPoint(None),
+            Target::Regions(_) => HitregToGui::Region(None),
+        }
+    }
+
+    fn ms_since_start(&self, now: Instant) -> u64 {
+        self.started_at.map_or(0, |started_at| {
+            now.duration_since(started_at).as_millis() as u64
+        })
+    }
+
+    fn capture_frame_end(&mut self, kind: FrameKind, now: Instant) {
+        let ms = self.ms_since_start(now);
+        self.capture.frame_ends.push((ms, kind));
+    }
+
+    /// must be called before reset, while the references of the sequence are still there
+    fn finish_capture(&mut self, result: &HitregToGui) {
+        let mut capture = std::mem::take(&mut self.capture);
+        capture.black_reference = self.black_reference;
+        capture.white_reference = self.white_reference;
+        capture.frame_brightness = self.frame_brightness.clone();
+        capture.decoded = self.black_and_white_sequence();
+        capture.result = format!("{result:?}");
+        self.last_capture = Some(capture);
+    }
+
+    fn reset(&mut self) {
+        self.target = Target::Hitboxes(Vec::new());
+        self.decoded_sequence.clear();
+        self.frame_brightness.clear();
+        self.white_reference = None;
+        self.deadline = None;
+        self.frame_started_at = None;
+        self.brightness_went_stale = false;
+        self.change_state(State::Idle);
+    }
+
+    /// called at the end of every frame, see BRIGHTNESS_STALE_AFTER
+    fn check_brightness_is_fresh(&mut self) {
+        let Some(frame_started_at) = self.frame_started_at else {
+            return;
+        };
+        // measured from the start of the frame and not from its end, a long frame that shows
+        // the same brightness all the time gets no new values, but it is not stale
+        let is_stale = self.last_brightness_received_at.is_none_or(|received_at| {
+            frame_started_at.saturating_duration_since(received_at) > BRIGHTNESS_STALE_AFTER
+        });
+        if is_stale {
+            warn!(target: "Hitreg Thread", "frame ended without a fresh brightness value from sensortag {}", self.sensortag_id);
+            self.brightness_went_stale = true;
+        }
+    }
+
+    fn store_brightness(&mut self, sample: SerialToHitReg, now: Instant) {
+        if sample.sensortag_id != self.sensortag_id {
+            return;
+        }
+
+        // compared to the black reference and not to the previous frame,
+        // so noise and drift do not add up over the frames of a sequence
+        let is_white = sample.value_raw.saturating_sub(self.black_reference) > self.white_threshold;
+
+        self.last_brightness = BrightnessBuffer {
+            val: sample.value_raw,
+            sensortag_id: sample.sensortag_id,
+            time: sample.timestamp,
+            is_white,
+        };
+        self.last_brightness_received_at = Some(now);
+
+        debug!(target: "Hitreg Thread", "new brightness {:?} at t={}", self.last_brightness, now_ms());
+    }
+
+    fn change_state(&mut self, state: State) {
+        self.state = state;
+        debug!(target: "Hitreg Thread", "changing state to {:?} at t={}", self.state, now_ms());
+    }
+}
+
+/// the code of everything that is flashed, as it shows up frame by frame (first frame first)
+fn code_table(target: &Target) -> Vec<(String, String)> {
+    let bits = |sequence: &mut dyn Iterator<Item = bool>| {
+        sequence
+            .map(|bit| if bit { '1' } else { '0' })
+            .collect::<String>()
+    };
+
+    match target {
+        Target::Hitboxes(sequences) => sequences
+            .iter()
+            .map(|(entity, sequence)| {
+                (
+                    format!("entity-{}", entity.id()),
+                    bits(&mut sequence.iter().copied()),
+                )
+            })
+            .collect(),
+        Target::AimPoint(layout) => vec![(
+            "stripes".to_string(),
+            format!(
+                "{}x{}-{}px",
+                layout.width, layout.height, layout.stripe_size
+            ),
+        )],
+        Target::Regions(num_regions) => (0..*num_regions)
+            .map(|region| {
+                let code = region + 1;
+                (
+                    format!("region-{region}"),
+                    bits(&mut (0..num_frames_for(*num_regions)).map(|bit| code & (1 << bit) != 0)),
+                )
+            })
+            .collect(),
+        Target::GreyLevels { sequences, .. } => sequences
+            .iter()
+            .map(|(entity, digits)| {
+                (
+                    format!("entity-{}", entity.id()),
+                    digits.iter().map(u8::to_string).collect(),
+                )
+            })
+            .collect(),
+    }
+}
+
+fn now_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comm::message::{GuiToHitreg, HitregToGui, SerialToHitReg, Stamps, ToHitreg};
+    use crate::hitreg::capture::FrameKind;
+    use crate::hitreg::decoder::{DecodeError, HitDecoder, num_frames_for};
+    use crate::hitreg::structured_light::StripeLayout;
+    use hecs::{Entity, World};
+    use std::time::{Duration, Instant, SystemTime};
+
+    const TAG: u16 = 0x1234;
+    const OTHER_TAG: u16 = 0xBEEF;
+
+    // the traces below are written down like the sequence diagram in plots/hitreg_sequence_diagram:
+    // the gui announces the flashing sequence, then every frame end is interleaved with
+    // whatever brightness values the sensortag reported in the meantime
+    // the first number of every step is the time in ms at which hitreg received the message,
+    // frames are 220ms long like in flashing_sequence
+
+    fn start(num_frames: u32, sequences: Vec<(Entity, Vec<bool>)>) -> ToHitreg {
+        ToHitreg::FromGui(GuiToHitreg::FlashingSequenceStart {
+            sensortag_id: TAG,
+            num_frames,
+            sequences,
+        })
+    }
+
+    fn black_frame_end() -> ToHitreg {
+        ToHitreg::FromGui(GuiToHitreg::FlashBlackFrameEnd(SystemTime::now()))
+    }
+
+    fn frame_end() -> ToHitreg {
+        ToHitreg::FromGui(GuiToHitreg::FlashFrameEnd(SystemTime::now()))
+    }
+
+    fn brightness(sensortag_id: u16, timestamp: u32, value_raw: u16) -> ToHitreg {
+        ToHitreg::FromSerial(SerialToHitReg {
+            sensortag_id,
+            timestamp,
+            value_raw,
+            stamps: Stamps::now(),
+        })
+    }
+
+    /// three hitboxes, coded the same way flashing_sequence does it (index + 1, lsb first)
+    fn three_hitboxes() -> (Vec<Entity>, Vec<(Entity, Vec<bool>)>) {
+        let mut world = World::new();
+        let entities = (0..3).map(|_| world.spawn(())).collect::<Vec<_>>();
+        let sequences = vec![
+            (entities[0], vec![true, false]),
+            (entities[1], vec![false, true]),
+            (entities[2], vec![true, true]),
+        ];
+        (entities, sequences)
+    }
+
+    /// feeds the trace into the decoder and polls it before every step, like the hitreg thread does
+    fn replay(decoder: &mut HitDecoder, trace: Vec<(u64, ToHitreg)>) -> Vec<HitregToGui> {
+        let t0 = Instant::now();
+        let mut results = Vec::new();
+
+        for (ms, message) in trace {
+            let now = t0 + Duration::from_millis(ms);
+            results.extend(decoder.poll(now));
+            results.extend(decoder.feed(message, now).unwrap());
+        }
+
+        results
+    }
+
+    #[test]
+    fn hit_is_decoded_from_frame_sequence() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                // frame 1: black
+                (330, brightness(TAG, 150, 12)),
+                (440, frame_end()),
+                // frame 2: white
+                (550, brightness(TAG, 200, 300)),
+                (600, brightness(TAG, 225, 410)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[1]))]);
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn all_black_frames_are_a_miss() {
+        let (_, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 14)),
+                (440, frame_end()),
+                (550, brightness(TAG, 200, 9)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(None)]);
+    }
+
+    #[test]
+    fn brightness_is_kept_when_sensortag_reports_nothing() {
+        // the sensortag only sends brightness when it changes
+        // so a white frame followed by another white frame produces no sample in the second one
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[2]))]);
+    }
+
+    #[test]
+    fn white_to_black_transition_is_detected() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                // small dip is noise, not a switch to black
+                (500, brightness(TAG, 175, 490)),
+                (600, brightness(TAG, 200, 11)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[0]))]);
+    }
+
+    #[test]
+    fn samples_of_other_sensortags_are_ignored() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (340, brightness(OTHER_TAG, 160, 3)),
+                (440, frame_end()),
+                (500, brightness(OTHER_TAG, 190, 800)),
+                (600, brightness(TAG, 200, 12)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[0]))]);
+    }
+
+    #[test]
+    fn consecutive_sequences_use_their_own_black_reference() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences.clone())),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                (660, frame_end()),
+                // the second shot starts while the sensor still sees white, then the black frame
+                // pulls it down again
+                (2000, start(2, sequences)),
+                (2100, brightness(TAG, 300, 11)),
+                (2220, black_frame_end()),
+                (2330, brightness(TAG, 350, 13)),
+                (2440, frame_end()),
+                (2550, brightness(TAG, 400, 450)),
+                (2660, frame_end()),
+            ],
+        );
+
+        assert_eq!(
+            results,
+            vec![
+                HitregToGui::Result(Some(entities[2])),
+                HitregToGui::Result(Some(entities[1]))
+            ]
+        );
+    }
+
+    #[test]
+    fn first_frame_is_compared_to_the_black_frame() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences.clone())),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (440, frame_end()),
+                (560, frame_end()),
+                // the room got brighter since the last shot, the black frame is brighter than
+                // the last frame of the first sequence, so a bit of noise must not count as white
+                (2000, start(2, sequences)),
+                (2100, brightness(TAG, 300, 200)),
+                (2220, black_frame_end()),
+                (2330, brightness(TAG, 350, 205)),
+                (2440, frame_end()),
+                (2550, brightness(TAG, 400, 480)),
+                (2660, frame_end()),
+            ],
+        );
+
+        assert_eq!(
+            results,
+            vec![
+                HitregToGui::Result(None),
+                HitregToGui::Result(Some(entities[1]))
+            ]
+        );
+    }
+
+    #[test]
+    fn sequence_without_hitboxes_finishes_after_black_frame() {
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![(0, start(0, Vec::new())), (220, black_frame_end())],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(None)]);
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn frame_end_without_sequence_is_unexpected() {
+        let mut decoder = HitDecoder::default();
+
+        assert!(matches!(
+            decoder.feed(frame_end(), Instant::now()),
+            Err(DecodeError::UnexpectedMessage(ToHitreg::FromGui(
+                GuiToHitreg::FlashFrameEnd(_)
+            )))
+        ));
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn lost_frame_end_times_out() {
+        let (_, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                // second frame end never arrives
+                (1500, brightness(TAG, 300, 12)),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::TimedOut]);
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn lost_brightness_packets_time_out() {
+        let (_, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, brightness(TAG, 0, 10)),
+                (100, start(2, sequences)),
+                (320, black_frame_end()),
+                // the radio went quiet after the first sample, it is 540ms old when the second
+                // frame starts and none arrived during it
+                (540, frame_end()),
+                (760, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::TimedOut]);
+    }
+
+    #[test]
+    fn long_frame_without_new_brightness_is_not_stale() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, brightness(TAG, 0, 10)),
+                (50, start(2, sequences)),
+                (270, black_frame_end()),
+                (330, brightness(TAG, 60, 500)),
+                // the gui stalled, the frame was shown for 800ms without the brightness changing
+                (1070, frame_end()),
+                (1130, brightness(TAG, 860, 12)),
+                (1290, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[0]))]);
+    }
+
+    #[test]
+    fn decoder_recovers_after_timeout() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let t0 = Instant::now();
+        decoder.feed(start(2, sequences.clone()), t0).unwrap();
+        assert_eq!(
+            decoder.poll(t0 + Duration::from_millis(1500)),
+            Some(HitregToGui::TimedOut)
+        );
+
+        // the late frame ends of the timed out sequence are dropped without breaking anything
+        let late = t0 + Duration::from_millis(1600);
+        assert!(decoder.feed(black_frame_end(), late).is_err());
+        assert!(decoder.feed(frame_end(), late).is_err());
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[2]))]);
+    }
+
+    #[test]
+    fn new_sequence_answers_the_abandoned_one() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences.clone())),
+                (220, black_frame_end()),
+                (300, start(2, sequences)),
+                (400, brightness(TAG, 100, 10)),
+                (520, black_frame_end()),
+                (630, brightness(TAG, 150, 500)),
+                (740, frame_end()),
+                (850, brightness(TAG, 200, 13)),
+                (960, frame_end()),
+            ],
+        );
+
+        assert_eq!(
+            results,
+            vec![
+                HitregToGui::TimedOut,
+                HitregToGui::Result(Some(entities[0]))
+            ]
+        );
+    }
+
+    #[test]
+    fn frame_count_leaves_out_the_all_black_code() {
+        assert_eq!(num_frames_for(0), 0);
+        assert_eq!(num_frames_for(1), 1);
+        assert_eq!(num_frames_for(3), 2);
+        assert_eq!(num_frames_for(4), 3);
+        assert_eq!(num_frames_for(250), 8);
+    }
+
+    #[test]
+    fn region_is_decoded_from_frame_sequence() {
+        // 4 regions need 3 frames, the sensortag sees white, white, black: code 3, region 2
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (
+                    0,
+                    ToHitreg::FromGui(GuiToHitreg::RegionSequenceStart {
+                        sensortag_id: TAG,
+                        num_regions: 4,
+                    }),
+                ),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                (550, brightness(TAG, 200, 495)),
+                (660, frame_end()),
+                (770, brightness(TAG, 250, 12)),
+                (880, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Region(Some(2))]);
+    }
+
+    fn grey_level_start(entities: &[Entity]) -> ToHitreg {
+        // codes 1, 2 and 3 fit into a single frame with 4 levels
+        ToHitreg::FromGui(GuiToHitreg::GreyLevelSequenceStart {
+            sensortag_id: TAG,
+            levels: 4,
+            num_frames: 1,
+            sequences: entities
+                .iter()
+                .enumerate()
+                .map(|(i, entity)| (*entity, vec![i as u8 + 1]))
+                .collect(),
+        })
+    }
+
+    fn white_frame_end() -> ToHitreg {
+        ToHitreg::FromGui(GuiToHitreg::FlashWhiteFrameEnd(SystemTime::now()))
+    }
+
+    #[test]
+    fn grey_level_is_decoded_between_black_and_white_reference() {
+        let (entities, _) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, grey_level_start(&entities)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 400)),
+                (440, white_frame_end()),
+                // two thirds of the way from black to white
+                (550, brightness(TAG, 200, 270)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[1]))]);
+    }
+
+    #[test]
+    fn grey_levels_without_bright_white_reference_are_a_miss() {
+        let (entities, _) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, grey_level_start(&entities)),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 20)),
+                (440, white_frame_end()),
+                (550, brightness(TAG, 200, 15)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(None)]);
+    }
+
+    #[test]
+    fn aim_point_is_decoded_from_stripes() {
+        // two columns and one row, the sensortag points at the right column
+        // column 2 has gray code 0b11, row 1 has gray code 0b1, so all three frames are white
+        let layout = StripeLayout::new(32, 16, 16);
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (
+                    0,
+                    ToHitreg::FromGui(GuiToHitreg::AimSequenceStart {
+                        sensortag_id: TAG,
+                        layout,
+                    }),
+                ),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 500)),
+                (440, frame_end()),
+                (550, brightness(TAG, 200, 495)),
+                (660, frame_end()),
+                (770, brightness(TAG, 250, 505)),
+                (880, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::AimPoint(Some((24, 8)))]);
+    }
+
+    #[test]
+    fn noisy_sensortag_is_classified_relative_to_its_noise() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        // between shots the sensortag flickers between 80 and 120
+        let mut trace = (0..40)
+            .map(|i| {
+                let value = if i % 2 == 0 { 80 } else { 120 };
+                (i * 100, brightness(TAG, i as u32, value))
+            })
+            .collect::<Vec<_>>();
+        trace.extend([
+            (4000, start(2, sequences)),
+            (4100, brightness(TAG, 100, 100)),
+            (4220, black_frame_end()),
+            // as bright as the flicker, still black
+            (4330, brightness(TAG, 150, 140)),
+            (4440, frame_end()),
+            // samples during a shot are not ambient light
+            (4550, brightness(TAG, 200, 900)),
+            (4660, frame_end()),
+        ]);
+
+        let results = replay(&mut decoder, trace);
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[1]))]);
+        let ambient = decoder.ambient(TAG).unwrap();
+        assert!((90.0..=110.0).contains(&ambient.baseline));
+        assert!(ambient.noise() > 15.0);
+    }
+
+    #[test]
+    fn reduced_contrast_is_classified_against_the_white_reference() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 100)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 200)),
+                (440, white_frame_end()),
+                // above the fixed threshold, but closer to black than to the white reference
+                (550, brightness(TAG, 200, 128)),
+                (660, frame_end()),
+                (770, brightness(TAG, 250, 190)),
+                (880, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Result(Some(entities[1]))]);
+    }
+
+    #[test]
+    fn dim_white_reference_is_nothing_seen() {
+        let mut decoder = HitDecoder::default();
+
+        let results = replay(
+            &mut decoder,
+            vec![
+                (
+                    0,
+                    ToHitreg::FromGui(GuiToHitreg::RegionSequenceStart {
+                        sensortag_id: TAG,
+                        num_regions: 1,
+                    }),
+                ),
+                (100, brightness(TAG, 100, 10)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 20)),
+                (440, white_frame_end()),
+                (550, brightness(TAG, 200, 18)),
+                (660, frame_end()),
+            ],
+        );
+
+        assert_eq!(results, vec![HitregToGui::Region(None)]);
+    }
+
+    #[test]
+    fn capture_records_what_the_sequence_saw() {
+        let (entities, sequences) = three_hitboxes();
+        let mut decoder = HitDecoder::default();
+
+        replay(
+            &mut decoder,
+            vec![
+                (0, start(2, sequences)),
+                (100, brightness(TAG, 100, 10)),
+                (150, brightness(OTHER_TAG, 100, 999)),
+                (220, black_frame_end()),
+                (330, brightness(TAG, 150, 12)),
+                (440, frame_end()),
+                (550, brightness(TAG, 200, 410)),
+                (660, frame_end()),
+            ],
+        );
+
+        let capture = decoder.take_capture().unwrap();
+        assert_eq!(capture.sensortag_id, TAG);
+        assert_eq!(
+            capture.codes[1],
+            (format!("entity-{}", entities[1].id()), "01".to_string())
+        );
+        assert_eq!(
+            capture
+                .samples
+                .iter()
+                .map(|sample| (sample.received_ms, sample.value))
+                .collect::<Vec<_>>(),
+            vec![(100, 10), (330, 12), (550, 410)]
+        );
+        assert_eq!(capture.frame_ends.len(), 3);
+        assert_eq!(capture.frame_ends[2], (660, FrameKind::Code));
+        assert_eq!(capture.frame_brightness, vec![12, 410]);
+        assert_eq!(capture.decoded, vec![false, true]);
+        assert!(capture.result.starts_with("Result(Some"));
+        assert!(decoder.take_capture().is_none());
+    }
+}
@@ -0,0 +1,141 @@
+// with only black and white, every frame carries one bit of a hitbox code
+// the light sensor measures a continuous value though, so a frame can also show grey levels in
+// between, every frame then carries one digit of the code in base `levels`
+
+// black and white depend on the screen, the room and how far away the sensortag is,
+// so a sequence with grey levels shows a white reference frame right after the black one
+// every following frame is placed between those two measurements
+
+// codes are index + 1 like in the black and white sequence, least significant digit first
+
+/// screens do not emit light proportional to the pixel value, this undoes that
+/// so that the grey levels are evenly spaced in brightness
+const SCREEN_GAMMA: f32 = 2.2;
+
+/// number of frames needed to give num_codes things their own code, leaving out the all black code
+pub fn num_frames_for(num_codes: u32, levels: u32) -> u32 {
+    assert!(levels >= 2, "at least black and white are needed");
+
+    let mut num_frames = 0;
+    let mut num_expressible = 1_u64;
+    while num_expressible <= num_codes as u64 {
+        num_expressible *= levels as u64;
+        num_frames += 1;
+    }
+    num_frames
+}
+
+/// frames after the black one, including the white reference frame
+pub fn sequence_length(num_codes: u32, levels: u32) -> u32 {
+    let num_frames = num_frames_for(num_codes, levels);
+    if levels > 2 && num_frames > 0 {
+        num_frames + 1
+    } else {
+        num_frames
+    }
+}
+
+/// the digits of code in base levels, least significant first
+pub fn digits(mut code: u32, levels: u32, num_frames: u32) -> Vec<u8> {
+    (0..num_frames)
+        .map(|_| {
+            let digit = code % levels;
+            code /= levels;
+            digit as u8
+        })
+        .collect()
+}
+
+/// pixel value to draw a digit with
+pub fn grey(digit: u8, levels: u32) -> u8 {
+    grey_between(digit, levels, 0, 255)
+}
+
+/// like grey, but the lowest digit is drawn with the pixel value black and the highest
+/// with white, for flashing at reduced contrast
+pub fn grey_between(digit: u8, levels: u32, black: u8, white: u8) -> u8 {
+    let to_light = |pixel: u8| (pixel as f32 / 255.0).powf(SCREEN_GAMMA);
+    let (black, white) = (to_light(black), to_light(white));
+
+    let brightness = black + (white - black) * digit as f32 / (levels - 1) as f32;
+    (255.0 * brightness.powf(1.0 / SCREEN_GAMMA)).round() as u8
+}
+
+/// the digit whose grey level is closest to the measured value
+///
+/// returns 0 if white is not brighter than black, the sensortag then did not see any of
+/// the flashed hitboxes
+pub fn classify(value: u16, black: u16, white: u16, levels: u32) -> u8 {
+    if white <= black {
+        return 0;
+    }
+
+    let brightness = value.saturating_sub(black) as f32 / (white - black) as f32;
+    (brightness.clamp(0.0, 1.0) * (levels - 1) as f32).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hitreg::grey_levels::{
+        classify, digits, grey, grey_between, num_frames_for, sequence_length,
+    };
+
+    #[test]
+    fn frame_count() {
+        assert_eq!(num_frames_for(0, 4), 0);
+        assert_eq!(num_frames_for(3, 4), 1);
+        assert_eq!(num_frames_for(4, 4), 2);
+        assert_eq!(num_frames_for(10, 3), 3);
+        assert_eq!(num_frames_for(250, 4), 4);
+
+        // two levels are the plain black and white sequence
+        assert_eq!(num_frames_for(250, 2), 8);
+    }
+
+    #[test]
+    fn white_reference_frame_is_only_needed_for_grey_levels() {
+        assert_eq!(sequence_length(250, 2), 8);
+        assert_eq!(sequence_length(250, 4), 5);
+        assert_eq!(sequence_length(0, 4), 0);
+    }
+
+    #[test]
+    fn digits_are_least_significant_first() {
+        assert_eq!(digits(7, 3, 3), vec![1, 2, 0]);
+        assert_eq!(digits(5, 2, 3), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn grey_levels_are_gamma_corrected() {
+        assert_eq!(grey(0, 4), 0);
+        assert_eq!(grey(3, 4), 255);
+
+        // a third of the brightness needs more than a third of the pixel value
+        assert!(grey(1, 4) > 85);
+        assert!(grey(1, 4) < grey(2, 4));
+    }
+
+    #[test]
+    fn reduced_contrast_keeps_the_ends() {
+        assert_eq!(grey_between(0, 2, 40, 128), 40);
+        assert_eq!(grey_between(1, 2, 40, 128), 128);
+
+        let middle = grey_between(1, 3, 40, 128);
+        assert!(middle > 84 && middle < 128);
+    }
+
+    #[test]
+    fn measurements_go_to_the_closest_level() {
+        assert_eq!(classify(100, 100, 400, 4), 0);
+        assert_eq!(classify(190, 100, 400, 4), 1);
+        assert_eq!(classify(310, 100, 400, 4), 2);
+        assert_eq!(classify(420, 100, 400, 4), 3);
+        assert_eq!(classify(80, 100, 400, 4), 0);
+    }
+
+    #[test]
+    fn no_white_reference_means_no_hitbox_seen() {
+        assert_eq!(classify(300, 100, 100, 4), 0);
+        assert_eq!(classify(300, 100, 90, 4), 0);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::comm::message::{HitregToGui, SerialToHitReg};
+use crate::hitreg::decoder::BRIGHTNESS_GRADIENT_THRESHOLD;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// measures how long it takes from a change on the screen until the sensortag reports it
+// this includes the display latency, the integration time of the light sensor,
+// the radio and the serial connection
+// the gui flips the whole screen from black to white (or back) and sends a LatencyProbe right after,
+// the probe then waits for the brightness of every sensortag to move away from where it was before
+
+/// a sensortag that did not react within this time is reported without a latency
+pub const PROBE_WINDOW: Duration = Duration::from_millis(1500);
+
+struct RunningProbe {
+    started_at: Instant,
+    baselines: HashMap<u16, u16>,
+    latencies: Vec<(u16, Option<Duration>)>,
+}
+
+#[derive(Default)]
+pub struct LatencyProbe {
+    /// newest brightness of every sensortag, whether a probe is running or not
+    last_values: HashMap<u16, u16>,
+    running: Option<RunningProbe>,
+}
+
+impl LatencyProbe {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn start(&mut self, sensortag_ids: Vec<u16>, now: Instant) -> Option<HitregToGui> {
+        let aborted = self.running.take().map(|probe| {
+            warn!(target: "Hitreg Thread", "latency probe started while another one was running, dropping the old one");
+            HitregToGui::ProbeLatencies(probe.latencies)
+        });
+
+        let baselines = sensortag_ids
+            .iter()
+            .filter_map(|id| self.last_values.get(id).map(|val| (*id, *val)))
+            .collect::<HashMap<_, _>>();
+        for id in &sensortag_ids {
+            if !baselines.contains_key(id) {
+                warn!(target: "Hitreg Thread", "no brightness from sensortag {id} before the latency probe, it cannot be measured");
+            }
+        }
+
+        self.running = Some(RunningProbe {
+            started_at: now,
+            baselines,
+            latencies: sensortag_ids.into_iter().map(|id| (id, None)).collect(),
+        });
+
+        aborted
+    }
+
+    pub fn observe(&mut self, sample: &SerialToHitReg, now: Instant) -> Option<HitregToGui> {
+        self.last_values
+            .insert(sample.sensortag_id, sample.value_raw);
+
+        let probe = self.running.as_mut()?;
+        let baseline = *probe.baselines.get(&sample.sensortag_id)?;
+        let (_, latency) = probe
+            .latencies
+            .iter_mut()
+            .find(|(id, _)| *id == sample.sensortag_id)?;
+
+        if latency.is_none() && baseline.abs_diff(sample.value_raw) > BRIGHTNESS_GRADIENT_THRESHOLD
+        {
+            *latency = Some(now.duration_since(probe.started_at));
+            debug!(target: "Hitreg Thread", "sensortag {} reacted after {:?}", sample.sensortag_id, latency);
+        }
+
+        if probe.latencies.iter().all(|(_, latency)| latency.is_some()) {
+            self.finish()
+        } else {
+            None
+        }
+    }
+
+    /// must be called regularly, answers the probe once PROBE_WINDOW is over
+    pub fn poll(&mut self, now: Instant) -> Option<HitregToGui> {
+        let probe = self.running.as_ref()?;
+        if now.duration_since(probe.started_at) >= PROBE_WINDOW {
+            self.finish()
+        } else {
+            None
+        }
+    }
+
+    fn finish(&mut self) -> Option<HitregToGui> {
+        self.running
+            .take()
+            .map(|probe| HitregToGui::ProbeLatencies(probe.latencies))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::comm::message::{HitregToGui, SerialToHitReg, Stamps};
+    use crate::hitreg::latency_probe::{LatencyProbe, PROBE_WINDOW};
+    use std::time::{Duration, Instant};
+
+    fn sample(sensortag_id: u16, value_raw: u16) -> SerialToHitReg {
+        SerialToHitReg {
+            sensortag_id,
+            timestamp: 1,
+            value_raw,
+            stamps: Stamps::now(),
+        }
+    }
+
+    #[test]
+    fn latency_is_measured_per_sensortag() {
+        let t0 = Instant::now();
+        let mut probe = LatencyProbe::default();
+
+        assert_eq!(probe.observe(&sample(1, 10), t0), None);
+        assert_eq!(probe.observe(&sample(2, 20), t0), None);
+        assert_eq!(probe.start(vec![1, 2], t0), None);
+
+        // noise does not count as a reaction
+        let t = |ms| t0 + Duration::from_millis(ms);
+        assert_eq!(probe.observe(&sample(1, 15), t(40)), None);
+        assert_eq!(probe.observe(&sample(1, 400), t(120)), None);
+        assert_eq!(
+            probe.observe(&sample(2, 380), t(150)),
+            Some(HitregToGui::ProbeLatencies(vec![
+                (1, Some(Duration::from_millis(120))),
+                (2, Some(Duration::from_millis(150))),
+            ]))
+        );
+        assert!(!probe.is_running());
+    }
+
+    #[test]
+    fn white_to_black_is_measured_too() {
+        let t0 = Instant::now();
+        let mut probe = LatencyProbe::default();
+
+        probe.observe(&sample(1, 400), t0);
+        probe.start(vec![1], t0);
+
+        assert_eq!(
+            probe.observe(&sample(1, 12), t0 + Duration::from_millis(90)),
+            Some(HitregToGui::ProbeLatencies(vec![(
+                1,
+                Some(Duration::from_millis(90))
+            )]))
+        );
+    }
+
+    #[test]
+    fn silent_sensortag_is_reported_after_the_window() {
+        let t0 = Instant::now();
+        let mut probe = LatencyProbe::default();
+
+        probe.observe(&sample(1, 10), t0);
+        probe.start(vec![1, 2], t0);
+        probe.observe(&sample(1, 400), t0 + Duration::from_millis(100));
+
+        assert_eq!(probe.poll(t0 + Duration::from_millis(1000)), None);
+        assert_eq!(
+            probe.poll(t0 + PROBE_WINDOW),
+            Some(HitregToGui::ProbeLatencies(vec![
+                (1, Some(Duration::from_millis(100))),
+                (2, None),
+            ]))
+        );
+        assert_eq!(probe.poll(t0 + PROBE_WINDOW * 2), None);
+    }
+}
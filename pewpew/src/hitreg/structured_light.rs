@@ -0,0 +1,186 @@
+// instead of giving every hitbox a code, the whole viewport is cut into stripes
+// first all vertical stripes are flashed (one frame per bit), then all horizontal stripes
+// the bits a sensortag saw form the code of the column and the row it points at
+
+// stripes are numbered starting with 1, like the hitboxes in the flashing sequence,
+// so a sensortag that does not point at the screen at all (only black frames) decodes to nothing
+// the numbers are gray coded, neighboring stripes only differ in one bit
+// if the sensortag sits right on the border between two stripes, the one bit it gets wrong
+// moves the aim point by one stripe at most
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripeLayout {
+    pub width: u32,
+    pub height: u32,
+
+    /// width of a vertical stripe and height of a horizontal stripe in pixels
+    pub stripe_size: u32,
+}
+
+impl StripeLayout {
+    pub fn new(width: u32, height: u32, stripe_size: u32) -> Self {
+        assert!(stripe_size > 0, "stripes must be at least one pixel wide");
+        StripeLayout {
+            width,
+            height,
+            stripe_size,
+        }
+    }
+
+    pub fn num_stripes(&self, axis: Axis) -> u32 {
+        match axis {
+            Axis::X => self.width.div_ceil(self.stripe_size),
+            Axis::Y => self.height.div_ceil(self.stripe_size),
+        }
+    }
+
+    pub fn num_frames_for(&self, axis: Axis) -> u32 {
+        // the highest code is num_stripes, its gray code has the same number of bits
+        u32::BITS - self.num_stripes(axis).leading_zeros()
+    }
+
+    pub fn num_frames(&self) -> u32 {
+        self.num_frames_for(Axis::X) + self.num_frames_for(Axis::Y)
+    }
+
+    /// which axis and which bit of the stripe code a frame shows
+    pub fn frame(&self, frame: u32) -> (Axis, u32) {
+        let x_frames = self.num_frames_for(Axis::X);
+        if frame < x_frames {
+            (Axis::X, frame)
+        } else {
+            (Axis::Y, frame - x_frames)
+        }
+    }
+
+    pub fn is_white(&self, frame: u32, stripe: u32) -> bool {
+        let (_, bit) = self.frame(frame);
+        (gray_encode(stripe + 1) >> bit) & 1 == 1
+    }
+
+    /// turns the white/black decision of every frame into the center of the stripe cell the
+    /// sensortag pointed at, or None if the decisions do not describe a stripe on the screen
+    pub fn decode(&self, frames: &[bool]) -> Option<(i32, i32)> {
+        if frames.len() != self.num_frames() as usize {
+            return None;
+        }
+
+        let (x_bits, y_bits) = frames.split_at(self.num_frames_for(Axis::X) as usize);
+        let column = self.decode_stripe(Axis::X, x_bits)?;
+        let row = self.decode_stripe(Axis::Y, y_bits)?;
+
+        Some((
+            self.stripe_center(column, self.width),
+            self.stripe_center(row, self.height),
+        ))
+    }
+
+    fn decode_stripe(&self, axis: Axis, bits: &[bool]) -> Option<u32> {
+        let gray = bits
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (bit, is_white)| acc | ((*is_white as u32) << bit));
+
+        match gray_decode(gray) {
+            0 => None,
+            code if code > self.num_stripes(axis) => None,
+            code => Some(code - 1),
+        }
+    }
+
+    fn stripe_center(&self, stripe: u32, length: u32) -> i32 {
+        let start = stripe * self.stripe_size;
+        let end = (start + self.stripe_size).min(length);
+        ((start + end) / 2) as i32
+    }
+}
+
+pub fn gray_encode(value: u32) -> u32 {
+    value ^ (value >> 1)
+}
+
+pub fn gray_decode(mut gray: u32) -> u32 {
+    let mut value = gray;
+    while gray > 0 {
+        gray >>= 1;
+        value ^= gray;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hitreg::structured_light::{Axis, StripeLayout, gray_decode, gray_encode};
+
+    fn frames_seen_at(layout: &StripeLayout, x: u32, y: u32) -> Vec<bool> {
+        (0..layout.num_frames())
+            .map(|frame| match layout.frame(frame) {
+                (Axis::X, _) => layout.is_white(frame, x / layout.stripe_size),
+                (Axis::Y, _) => layout.is_white(frame, y / layout.stripe_size),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gray_code_roundtrip() {
+        for value in 0..1024 {
+            assert_eq!(gray_decode(gray_encode(value)), value);
+        }
+    }
+
+    #[test]
+    fn neighboring_codes_differ_in_one_bit() {
+        for value in 0..1024 {
+            let diff = gray_encode(value) ^ gray_encode(value + 1);
+            assert_eq!(diff.count_ones(), 1);
+        }
+    }
+
+    #[test]
+    fn frame_count() {
+        // 120 columns need 7 bits, 68 rows need 7 bits
+        let layout = StripeLayout::new(1920, 1080, 16);
+        assert_eq!(layout.num_stripes(Axis::X), 120);
+        assert_eq!(layout.num_stripes(Axis::Y), 68);
+        assert_eq!(layout.num_frames(), 14);
+        assert_eq!(layout.frame(6), (Axis::X, 6));
+        assert_eq!(layout.frame(7), (Axis::Y, 0));
+    }
+
+    #[test]
+    fn every_pixel_decodes_to_its_stripe_cell() {
+        let layout = StripeLayout::new(200, 90, 16);
+
+        for x in 0..layout.width {
+            for y in 0..layout.height {
+                let (aim_x, aim_y) = layout.decode(&frames_seen_at(&layout, x, y)).unwrap();
+                assert_eq!(aim_x as u32 / layout.stripe_size, x / layout.stripe_size);
+                assert_eq!(aim_y as u32 / layout.stripe_size, y / layout.stripe_size);
+                assert!((aim_x as u32) < layout.width && (aim_y as u32) < layout.height);
+            }
+        }
+    }
+
+    #[test]
+    fn off_screen_decodes_to_nothing() {
+        let layout = StripeLayout::new(1920, 1080, 16);
+        assert_eq!(layout.decode(&[false; 14]), None);
+        assert_eq!(layout.decode(&[true, false]), None);
+    }
+
+    #[test]
+    fn codes_past_the_last_stripe_decode_to_nothing() {
+        // 2 columns use the codes 1 and 2 (gray 0b01 and 0b11)
+        // 2 bits can also express gray 0b10 (code 3), which is no column
+        let layout = StripeLayout::new(32, 16, 16);
+        assert_eq!(layout.decode(&[true, false, true]), Some((8, 8)));
+        assert_eq!(layout.decode(&[true, true, true]), Some((24, 8)));
+        assert_eq!(layout.decode(&[false, true, true]), None);
+    }
+}
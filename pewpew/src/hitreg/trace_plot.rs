@@ -0,0 +1,334 @@
+use crate::hitreg::capture::{FrameKind, SequenceCapture};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// brightness over time of one sensortag as svg, for the plot_brightness binary
+// frames of a flashing sequence are drawn as bands behind the trace, light if hitreg decided
+// white and dark if it decided black, the thresholds hitreg compared against are dashed lines
+// so a sample that is just below the line shows where a shot went wrong
+
+/// the sensortag counts its timestamp up this often per second, see sensortag.c
+pub const SENSORTAG_TICKS_PER_SECOND: u64 = 444;
+
+const WIDTH: f32 = 1200.0;
+const HEIGHT: f32 = 500.0;
+const MARGIN: f32 = 50.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameMark {
+    /// when the frame ended, in ms
+    pub end_ms: u64,
+    pub label: String,
+
+    /// whether hitreg decided white, None for reference frames
+    pub is_white: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub title: String,
+
+    /// (time in ms, brightness)
+    pub samples: Vec<(u64, u16)>,
+
+    /// in the order they were shown, every frame starts where the one before ended
+    pub frames: Vec<FrameMark>,
+
+    /// horizontal lines, (brightness, label)
+    pub levels: Vec<(u16, String)>,
+}
+
+impl Trace {
+    /// everything hitreg saw of one flashing sequence, timed by when hitreg received it
+    pub fn from_capture(capture: &SequenceCapture, title: String) -> Self {
+        let frames = capture
+            .frame_ends
+            .iter()
+            .enumerate()
+            .map(|(frame, (end_ms, kind))| FrameMark {
+                end_ms: *end_ms,
+                label: match kind {
+                    FrameKind::Black => "black".to_string(),
+                    FrameKind::White => "white".to_string(),
+                    FrameKind::Code => format!("{frame}"),
+                },
+                is_white: capture
+                    .brightness_of_frame(frame)
+                    .and_then(|(_, is_white)| is_white),
+            })
+            .collect();
+
+        let black = capture.black_reference;
+        let mut levels = vec![(black, "black reference".to_string())];
+        match capture.white_reference {
+            // with a white reference, frames are compared to the middle between the two
+            Some(white) => {
+                levels.push((white, "white reference".to_string()));
+                levels.push((
+                    ((black as u32 + white as u32) / 2) as u16,
+                    "midpoint".to_string(),
+                ));
+            }
+            None => levels.push((
+                black.saturating_add(capture.white_threshold),
+                "white threshold".to_string(),
+            )),
+        }
+
+        Trace {
+            title,
+            samples: capture
+                .samples
+                .iter()
+                .map(|sample| (sample.received_ms, sample.value))
+                .collect(),
+            frames,
+            levels,
+        }
+    }
+
+    /// one trace per sensortag from what the serial_print binary prints,
+    /// timed by the timestamps of the sensortags
+    pub fn from_serial_print(text: &str) -> BTreeMap<u16, Trace> {
+        let mut traces = BTreeMap::new();
+
+        for (sensortag_id, timestamp, value) in text.lines().filter_map(parse_brightness_packet) {
+            let trace = traces.entry(sensortag_id).or_insert_with(|| Trace {
+                title: format!("sensortag {sensortag_id:#06x}"),
+                ..Trace::default()
+            });
+            trace
+                .samples
+                .push((timestamp as u64 * 1000 / SENSORTAG_TICKS_PER_SECOND, value));
+        }
+
+        // the time axis starts at the first sample of every sensortag
+        for trace in traces.values_mut() {
+            let start = trace.samples.iter().map(|(ms, _)| *ms).min().unwrap_or(0);
+            for (ms, _) in &mut trace.samples {
+                *ms -= start;
+            }
+        }
+
+        traces
+    }
+
+    pub fn to_svg(&self) -> String {
+        let end_ms = self
+            .samples
+            .iter()
+            .map(|(ms, _)| *ms)
+            .chain(self.frames.iter().map(|frame| frame.end_ms))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let max_brightness = self
+            .samples
+            .iter()
+            .map(|(_, value)| *value)
+            .chain(self.levels.iter().map(|(value, _)| *value))
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32
+            * 1.1;
+
+        let x = |ms: u64| MARGIN + ms as f32 / end_ms as f32 * (WIDTH - 2.0 * MARGIN);
+        let y =
+            |value: u16| HEIGHT - MARGIN - value as f32 / max_brightness * (HEIGHT - 2.0 * MARGIN);
+
+        let mut svg = String::new();
+
+        // writing to a string never fails
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" font-family="sans-serif" font-size="12">"#
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{MARGIN}" y="{}" font-size="16">{}</text>"#,
+            MARGIN / 2.0,
+            escape(&self.title)
+        )
+        .unwrap();
+
+        let mut frame_start = 0;
+        for frame in &self.frames {
+            let fill = match frame.is_white {
+                Some(true) => "#fff3b0",
+                Some(false) => "#9a9a9a",
+                None => "#dde6f0",
+            };
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{MARGIN}" width="{}" height="{}" fill="{fill}"/>"#,
+                x(frame_start),
+                x(frame.end_ms) - x(frame_start),
+                HEIGHT - 2.0 * MARGIN
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r##"<line x1="{0}" y1="{MARGIN}" x2="{0}" y2="{1}" stroke="#555"/>"##,
+                x(frame.end_ms),
+                HEIGHT - MARGIN
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                (x(frame_start) + x(frame.end_ms)) / 2.0,
+                MARGIN - 5.0,
+                escape(&frame.label)
+            )
+            .unwrap();
+            frame_start = frame.end_ms;
+        }
+
+        // axes
+        writeln!(
+            svg,
+            r#"<polyline points="{MARGIN},{MARGIN} {MARGIN},{0} {1},{0}" fill="none" stroke="black"/>"#,
+            HEIGHT - MARGIN,
+            WIDTH - MARGIN
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="end">{end_ms}ms</text>"#,
+            WIDTH - MARGIN,
+            HEIGHT - MARGIN + 20.0
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#,
+            MARGIN - 5.0,
+            MARGIN + 12.0,
+            max_brightness.round()
+        )
+        .unwrap();
+
+        for (value, label) in &self.levels {
+            writeln!(
+                svg,
+                r##"<line x1="{MARGIN}" y1="{0}" x2="{1}" y2="{0}" stroke="#c03030" stroke-dasharray="6,4"/>"##,
+                y(*value),
+                WIDTH - MARGIN
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r##"<text x="{}" y="{}" fill="#c03030">{} ({value})</text>"##,
+                MARGIN + 5.0,
+                y(*value) - 4.0,
+                escape(label)
+            )
+            .unwrap();
+        }
+
+        // the sensortag only sends when the brightness changes, so a value holds until the next
+        let mut points = String::new();
+        for (i, (ms, value)) in self.samples.iter().enumerate() {
+            if i > 0 {
+                write!(points, "{},{} ", x(*ms), y(self.samples[i - 1].1)).unwrap();
+            }
+            write!(points, "{},{} ", x(*ms), y(*value)).unwrap();
+        }
+        writeln!(
+            svg,
+            r##"<polyline points="{}" fill="none" stroke="#1f4fbf" stroke-width="2"/>"##,
+            points.trim_end()
+        )
+        .unwrap();
+        for (ms, value) in &self.samples {
+            writeln!(
+                svg,
+                r##"<circle cx="{}" cy="{}" r="3" fill="#1f4fbf"/>"##,
+                x(*ms),
+                y(*value)
+            )
+            .unwrap();
+        }
+
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+}
+
+/// (sensortag_id, timestamp, brightness) from a line like
+/// `Packet { sensortag_id: 4660, timestamp: 100, content: Brightness(300) }`
+fn parse_brightness_packet(line: &str) -> Option<(u16, u32, u16)> {
+    let field = |name: &str, end: char| -> Option<&str> {
+        let start = line.find(name)? + name.len();
+        let rest = &line[start..];
+        Some(rest[..rest.find(end)?].trim())
+    };
+
+    Some((
+        field("sensortag_id:", ',')?.parse().ok()?,
+        field("timestamp:", ',')?.parse().ok()?,
+        field("Brightness(", ')')?.parse().ok()?,
+    ))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hitreg::capture::{FrameKind, Sample, SequenceCapture};
+    use crate::hitreg::trace_plot::{FrameMark, Trace};
+
+    #[test]
+    fn serial_print_is_split_by_sensortag() {
+        let text = "\
+Packet { sensortag_id: 1, timestamp: 444, content: Brightness(300) }
+Packet { sensortag_id: 2, timestamp: 10, content: ButtonPressed(MagazineStatus { ammo: 3, ammo_max: 6 }) }
+Packet { sensortag_id: 1, timestamp: 888, content: Brightness(20) }
+Packet { sensortag_id: 2, timestamp: 0, content: Brightness(7) }
+";
+        let traces = Trace::from_serial_print(text);
+
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[&1].samples, vec![(0, 300), (1000, 20)]);
+        assert_eq!(traces[&2].samples, vec![(0, 7)]);
+    }
+
+    #[test]
+    fn capture_shows_decisions_and_threshold() {
+        let capture = SequenceCapture {
+            white_threshold: 25,
+            samples: vec![Sample {
+                received_ms: 100,
+                timestamp: 1,
+                value: 10,
+            }],
+            frame_ends: vec![(220, FrameKind::Black), (440, FrameKind::Code)],
+            black_reference: 10,
+            frame_brightness: vec![400],
+            decoded: vec![true],
+            ..SequenceCapture::default()
+        };
+        let trace = Trace::from_capture(&capture, "shot".to_string());
+
+        assert_eq!(
+            trace.frames[1],
+            FrameMark {
+                end_ms: 440,
+                label: "1".to_string(),
+                is_white: Some(true),
+            }
+        );
+        assert_eq!(trace.levels[1], (35, "white threshold".to_string()));
+
+        let svg = trace.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("white threshold (35)"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+}
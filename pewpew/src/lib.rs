@@ -3,4 +3,5 @@ pub mod user_settings;
 pub mod serial;
 pub mod hitreg;
 pub mod comm;
-pub mod common;
\ No newline at end of file
+pub mod common;
+pub mod supervisor;
\ No newline at end of file
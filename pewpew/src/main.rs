@@ -1,6 +1,9 @@
+use log::error;
 use pewpew::common::cancel_token::CancelToken;
+use pewpew::common::latency;
+use pewpew::{comm, gui, supervisor};
+use std::path::Path;
 use std::thread;
-use pewpew::{comm, gui, hitreg};
 
 fn main() {
     // set the environment variable "RUST_LOG" to "error", "warn", "info", "debug", "trace" or "off"
@@ -9,15 +12,28 @@ fn main() {
     // with a local time
     env_logger::init();
 
-    let (serial_comm, hitreg_comm, gui_comm) = comm::new();
+    let (supervisor_comm, gui_comm) = comm::new();
     let cancelled = CancelToken::default();
 
-    thread::spawn(pewpew::serial::run(serial_comm, cancelled.clone()));
-    thread::spawn(hitreg::run(hitreg_comm, cancelled.clone()));
+    // the supervisor runs the serial and hitreg threads and starts them again if they end
+    let supervisor = thread::spawn(supervisor::run(supervisor_comm, cancelled.clone()));
 
     // run gui on main thread
     // because macOS is shit, it allows gui operations to run only on the main thread
     // and because sdl2 wants to be cross-platform, it also only allows gui operations
     // on the main thread
-    gui::run(gui_comm, cancelled);
+    gui::run(gui_comm, cancelled.clone());
+
+    // the gui is done, stop everything else
+    cancelled.cancel();
+    if supervisor.join().is_err() {
+        error!("supervisor thread panicked");
+    }
+
+    if let Err(e) = latency::dump(Path::new(latency::DUMP_FILE)) {
+        error!(
+            "failed to write latency histograms to {}: {e}",
+            latency::DUMP_FILE
+        );
+    }
 }
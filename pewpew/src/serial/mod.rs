@@ -2,7 +2,7 @@ pub mod config;
 pub mod packet;
 pub mod reader;
 
-use crate::comm::message::{SerialToGui, SerialToGuiKind, SerialToHitReg};
+use crate::comm::message::{SerialToGui, SerialToGuiKind, SerialToHitReg, Stamps};
 use crate::comm::serial::SerialComm;
 use crate::common::cancel_token::CancelToken;
 use crate::serial::config::SerialConfig;
@@ -42,6 +42,7 @@ pub fn run(comm: SerialComm, cancel_token: CancelToken) -> impl FnOnce() {
 
             match packet {
                 Ok(packet) => {
+                    let stamps = Stamps::now();
                     match packet.content {
                         PacketContent::ButtonPressed(MagazineStatus { ammo, ammo_max }) => {
                             if comm
@@ -51,6 +52,7 @@ pub fn run(comm: SerialComm, cancel_token: CancelToken) -> impl FnOnce() {
                                     ammo,
                                     ammo_max,
                                     kind: SerialToGuiKind::Shot,
+                                    stamps,
                                 })
                                 .is_err()
                             {
@@ -66,6 +68,7 @@ pub fn run(comm: SerialComm, cancel_token: CancelToken) -> impl FnOnce() {
                                     sensortag_id: packet.sensortag_id,
                                     timestamp: packet.timestamp,
                                     value_raw,
+                                    stamps,
                                 })
                                 .is_err()
                             {
@@ -83,6 +86,7 @@ pub fn run(comm: SerialComm, cancel_token: CancelToken) -> impl FnOnce() {
                                     ammo,
                                     ammo_max,
                                     kind: SerialToGuiKind::Reload,
+                                    stamps,
                                 })
                                 .is_err()
                             {
@@ -0,0 +1,194 @@
+use crate::comm::message::{SupervisorToGui, Worker, WorkerHealth};
+use crate::comm::supervisor::SupervisorComm;
+use crate::common::cancel_token::CancelToken;
+use crate::{hitreg, serial};
+use log::{error, info, warn};
+use std::any::Any;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+// serial and hitreg run on worker threads, if one of them ends because it panicked or returned
+// (serial does when the port cannot be opened) the supervisor starts it again with fresh comm
+// endpoints and tells the gui how the workers are doing
+// a worker that keeps ending is restarted with a delay that doubles every time, so an unplugged
+// serial port is tried once in a while instead of all the time
+// every worker gets a child of the cancel token of the gui, so a worker that ends does not stop
+// everything else by dropping its token, once the token of the gui is canceled the workers are joined
+
+/// how often the supervisor looks at the workers
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+const FIRST_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// a worker that ran this long before it ended was fine until then,
+/// it is restarted as if it ended for the first time
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+/// how long a worker gets to notice the cancel token, serial only looks at it between packets
+const JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct WorkerThread {
+    worker: Worker,
+    handle: Option<JoinHandle<()>>,
+    started_at: Instant,
+    restarts_in_a_row: u32,
+
+    /// set while the worker is not running
+    restart_at: Option<Instant>,
+}
+
+pub fn run(mut comm: SupervisorComm, cancel_token: CancelToken) -> impl FnOnce() {
+    move || {
+        let mut workers = [Worker::Serial, Worker::Hitreg].map(|worker| WorkerThread {
+            worker,
+            handle: None,
+            started_at: Instant::now(),
+            restarts_in_a_row: 0,
+            restart_at: Some(Instant::now()),
+        });
+
+        loop {
+            if cancel_token.was_canceled() {
+                info!(target: "Supervisor Thread", "joining workers because of cancel token");
+                join_all(&mut workers);
+                return;
+            }
+
+            let now = Instant::now();
+            for worker in &mut workers {
+                if worker
+                    .handle
+                    .as_ref()
+                    .is_some_and(|handle| handle.is_finished())
+                {
+                    let reason = match worker.handle.take().unwrap().join() {
+                        Ok(()) => "returned".to_string(),
+                        Err(payload) => format!("panicked: {}", panic_message(payload.as_ref())),
+                    };
+
+                    if now.duration_since(worker.started_at) >= HEALTHY_RUN {
+                        worker.restarts_in_a_row = 0;
+                    }
+                    let restart_in = restart_delay(worker.restarts_in_a_row);
+                    worker.restarts_in_a_row += 1;
+                    worker.restart_at = Some(now + restart_in);
+
+                    warn!(target: "Supervisor Thread", "{:?} thread {reason}, restarting in {}s", worker.worker, restart_in.as_secs());
+                    report(
+                        &comm,
+                        worker.worker,
+                        WorkerHealth::Stopped { reason, restart_in },
+                    );
+                }
+
+                if worker
+                    .restart_at
+                    .is_some_and(|restart_at| now >= restart_at)
+                {
+                    worker.restart_at = None;
+                    worker.started_at = now;
+                    worker.handle = spawn(worker.worker, &mut comm, &cancel_token);
+
+                    if worker.handle.is_some() {
+                        info!(target: "Supervisor Thread", "started {:?} thread", worker.worker);
+                        report(&comm, worker.worker, WorkerHealth::Running);
+                    } else {
+                        worker.restart_at = Some(now + restart_delay(worker.restarts_in_a_row));
+                    }
+                }
+            }
+
+            thread::sleep(CHECK_INTERVAL);
+        }
+    }
+}
+
+fn spawn(
+    worker: Worker,
+    comm: &mut SupervisorComm,
+    cancel_token: &CancelToken,
+) -> Option<JoinHandle<()>> {
+    let spawned = match worker {
+        Worker::Serial => thread::Builder::new()
+            .name("serial".to_string())
+            .spawn(serial::run(comm.serial_comm(), cancel_token.child())),
+        Worker::Hitreg => {
+            let Some(hitreg_comm) = comm.hitreg_comm() else {
+                error!(target: "Supervisor Thread", "the comm of the last hitreg thread still exists");
+                return None;
+            };
+            thread::Builder::new()
+                .name("hitreg".to_string())
+                .spawn(hitreg::run(hitreg_comm, cancel_token.child()))
+        }
+    };
+
+    spawned
+        .inspect_err(
+            |e| error!(target: "Supervisor Thread", "failed to spawn {worker:?} thread: {e}"),
+        )
+        .ok()
+}
+
+fn report(comm: &SupervisorComm, worker: Worker, health: WorkerHealth) {
+    // the gui being gone is not the business of the supervisor, main cancels everything then
+    let _ = comm.send(SupervisorToGui { worker, health });
+}
+
+fn join_all(workers: &mut [WorkerThread]) {
+    let deadline = Instant::now() + JOIN_TIMEOUT;
+    while Instant::now() < deadline
+        && workers.iter().any(|worker| {
+            worker
+                .handle
+                .as_ref()
+                .is_some_and(|handle| !handle.is_finished())
+        })
+    {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    for worker in workers {
+        let Some(handle) = worker.handle.take() else {
+            continue;
+        };
+
+        if !handle.is_finished() {
+            warn!(target: "Supervisor Thread", "{:?} thread did not stop in time, leaving it", worker.worker);
+        } else if handle.join().is_err() {
+            warn!(target: "Supervisor Thread", "{:?} thread panicked while stopping", worker.worker);
+        }
+    }
+}
+
+/// FIRST_RESTART_DELAY, doubled for every restart in a row, at most MAX_RESTART_DELAY
+fn restart_delay(restarts_in_a_row: u32) -> Duration {
+    FIRST_RESTART_DELAY
+        .saturating_mul(2_u32.saturating_pow(restarts_in_a_row))
+        .min(MAX_RESTART_DELAY)
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown reason".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::supervisor::{MAX_RESTART_DELAY, restart_delay};
+    use std::time::Duration;
+
+    #[test]
+    fn restart_delay_doubles_up_to_the_maximum() {
+        assert_eq!(restart_delay(0), Duration::from_secs(1));
+        assert_eq!(restart_delay(3), Duration::from_secs(8));
+        assert_eq!(restart_delay(40), MAX_RESTART_DELAY);
+    }
+}
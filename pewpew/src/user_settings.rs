@@ -3,3 +3,33 @@
 // set the path to the serial port here
 // e.g. Some("/dev/ttyoAss") or Some("COM69420")
 pub const SERIAL_PORT: Option<&str> = Some("/dev/ttyACM0");
+
+// locate the exact point a shot lands on, instead of only finding out which chicken was hit
+// needs more frames per shot (14 on a 1080p screen instead of 4)
+pub const AIM_POINT_MODE: bool = false;
+
+// with many chickens on screen, first flash screen regions and then only the chickens in the
+// region the shot went to, whenever that needs fewer frames on average
+// the regions cover the whole screen, so this flashes more of it than only the chickens
+pub const COARSE_TO_FINE_MODE: bool = false;
+
+// grey levels a hitbox can show per frame, 2 is black and white
+// 3 or 4 need fewer frames, but the sensortag has to tell the grey levels apart reliably
+// the decoder only supports 2 to 4
+pub const BRIGHTNESS_LEVELS: u32 = 2;
+const _: () = assert!(
+    2 <= BRIGHTNESS_LEVELS && BRIGHTNESS_LEVELS <= 4,
+    "BRIGHTNESS_LEVELS has to be 2, 3 or 4"
+);
+
+// flash only the hitboxes, at reduced contrast and at most 3 times per second,
+// for public events where photosensitive people might play or watch
+pub const SAFE_FLASHING: bool = false;
+
+// save every flash frame, the brightness samples and the decoded result of every shot
+// into captures/, to find out why a shot was misregistered, see hitreg/capture.rs
+// the frames are read back from the gpu, which makes the flashing slower
+pub const CAPTURE_HITREG: bool = false;
+
+// volume of all sounds, from 0.0 (muted) to 1.0
+pub const VOLUME: f32 = 1.0;